@@ -6,6 +6,7 @@
 //! Run with: cargo run --bin test-calendar
 
 use chrono::{FixedOffset, TimeZone};
+use hoyoverse_api::hoyolab::{DsSalt, HeaderPreset, HoyolabRequest};
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 
@@ -59,20 +60,6 @@ fn zenless_default_region() -> String {
     "prod_gf_us".to_string()
 }
 
-const DS_SALT_GENSHIN: &str = "xV8v4Qu54lUKrEYFZkJhB8cuOh9Asafs";
-const DS_SALT_STARRAIL: &str = "6s25p5ox5y14umn1p61aqyyvbvvl3lrt";
-
-fn random_r() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-    let nanos = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .subsec_nanos() as usize;
-    (0..6)
-        .map(|i| CHARSET[(nanos.wrapping_add(i * 7919)) % CHARSET.len()] as char)
-        .collect()
-}
-
 fn map_profession(id: u8) -> &'static str {
     match id {
         1 => "attack",
@@ -97,43 +84,9 @@ fn map_element(id: u16) -> &'static str {
     }
 }
 
-fn generate_ds_genshin(body: &str) -> String {
-    let t = chrono::Utc::now().timestamp();
-    let r = random_r();
-    let raw = format!("salt={DS_SALT_GENSHIN}&t={t}&r={r}&b={body}&q=");
-    let hash = format!("{:x}", md5::compute(raw.as_bytes()));
-    format!("{t},{r},{hash}")
-}
-
-fn generate_ds_starrail() -> String {
-    let t = chrono::Utc::now().timestamp();
-    let r = random_r();
-    let raw = format!("salt={DS_SALT_STARRAIL}&t={t}&r={r}");
-    let hash = format!("{:x}", md5::compute(raw.as_bytes()));
-    format!("{t},{r},{hash}")
-}
-
-fn cookie_with_lang(cookie: &str, lang: &str) -> String {
-    let mut parts: Vec<String> = cookie
-        .split(';')
-        .map(str::trim)
-        .filter(|part| !part.is_empty() && !part.starts_with("mi18nLang="))
-        .map(ToOwned::to_owned)
-        .collect();
-    parts.insert(0, format!("mi18nLang={lang}"));
-    parts.join("; ")
-}
-
 const GENSHIN_CALENDAR_API: &str =
     "https://sg-public-api.hoyolab.com/event/game_record/genshin/api/act_calendar";
 
-#[derive(Deserialize)]
-struct GenshinResponse {
-    retcode: i32,
-    message: String,
-    data: Option<GenshinCalendarData>,
-}
-
 #[derive(Deserialize)]
 struct GenshinCalendarData {
     act_list: Vec<GenshinActivity>,
@@ -189,13 +142,6 @@ struct GenshinWeapon {
 const STARRAIL_CALENDAR_API: &str =
     "https://sg-public-api.hoyolab.com/event/game_record/hkrpg/api/get_act_calender";
 
-#[derive(Deserialize)]
-struct StarRailResponse {
-    retcode: i32,
-    message: String,
-    data: Option<StarRailCalendarData>,
-}
-
 #[derive(Deserialize)]
 struct StarRailCalendarData {
     avatar_card_pool_list: Vec<SRAvatarPool>,
@@ -284,13 +230,6 @@ const ZENLESS_SHIYU_DEFENSE_API: &str =
 const ZENLESS_ANNIHILATION_SIMULACRUM_API: &str =
     "https://sg-act-nap-api.hoyolab.com/event/game_record_zzz/api/zzz/holo_boss_detail";
 
-#[derive(Deserialize)]
-struct ZenlessActivityResponse {
-    retcode: i32,
-    message: String,
-    data: Option<ZenlessActivityData>,
-}
-
 #[derive(Deserialize)]
 struct ZenlessActivityData {
     activity_list: Vec<ZenlessActivity>,
@@ -306,13 +245,6 @@ struct ZenlessActivity {
     end_ts: i64,
 }
 
-#[derive(Deserialize)]
-struct ZenlessGachaResponse {
-    retcode: i32,
-    message: String,
-    data: Option<ZenlessGachaData>,
-}
-
 #[derive(Deserialize)]
 struct ZenlessGachaData {
     avatar_gacha_schedule_list: Vec<ZenlessAvatarGacha>,
@@ -357,13 +289,6 @@ struct ZenlessWeapon {
     profession: u8,
 }
 
-#[derive(Deserialize)]
-struct ZenlessChallengeResponse<T> {
-    retcode: i32,
-    message: String,
-    data: Option<T>,
-}
-
 #[derive(Deserialize)]
 struct ZenlessDateTime {
     year: i32,
@@ -505,32 +430,17 @@ fn print_zenless_challenge(name: &str, start_time: Option<i64>, end_time: Option
     println!("       {start_time} → {end_time}");
 }
 
-async fn fetch_zenless_challenge<T>(request: reqwest::RequestBuilder, name: &str) -> Option<T>
+async fn fetch_zenless_challenge<T>(request: HoyolabRequest<'_>, name: &str) -> Option<T>
 where
     T: DeserializeOwned,
 {
-    let response = match request.send().await {
-        Ok(response) => response,
-        Err(error) => {
-            println!("  {name}: request failed: {error}");
-            return None;
-        }
-    };
-    let response: ZenlessChallengeResponse<T> = match response.json().await {
-        Ok(response) => response,
+    match request.send().await {
+        Ok(data) => Some(data),
         Err(error) => {
-            println!("  {name}: response parse failed: {error}");
-            return None;
+            println!("  {name}: {error}");
+            None
         }
-    };
-    if response.retcode != 0 {
-        println!(
-            "  {name}: API error {}: {}",
-            response.retcode, response.message
-        );
-        return None;
     }
-    response.data
 }
 
 #[tokio::main]
@@ -572,30 +482,13 @@ async fn print_genshin_calendar(
     println!();
 
     let body = serde_json::json!({ "role_id": creds.uid, "server": creds.region });
-    let body_str = body.to_string();
-    let ds = generate_ds_genshin(&body_str);
-    let cookie = cookie_with_lang(&creds.cookie, lang);
-
-    let resp = client
-        .post(GENSHIN_CALENDAR_API)
-        .header("Cookie", cookie)
-        .header("DS", ds)
-        .header("x-rpc-app_version", "1.5.0")
-        .header("x-rpc-client_type", "5")
-        .header("x-rpc-language", lang)
-        .json(&body)
-        .send()
-        .await?
-        .json::<GenshinResponse>()
-        .await?;
-
-    if resp.retcode != 0 {
-        anyhow::bail!("API error {}: {}", resp.retcode, resp.message);
-    }
-
-    let data = resp
-        .data
-        .ok_or_else(|| anyhow::anyhow!("API returned no data"))?;
+    let data: GenshinCalendarData =
+        HoyolabRequest::post(client, "genshin_calendar", GENSHIN_CALENDAR_API, body)
+            .localized_cookie(&creds.cookie, lang)
+            .preset(HeaderPreset::GameRecord, lang)
+            .sign(DsSalt::X4)
+            .send()
+            .await?;
 
     println!(
         "── Events ({}) ──────────────────────────────",
@@ -693,29 +586,17 @@ async fn print_starrail_calendar(
     println!("Lang:   {}", lang);
     println!();
 
-    let ds = generate_ds_starrail();
-    let cookie = cookie_with_lang(&creds.cookie, lang);
-
-    let resp = client
-        .get(STARRAIL_CALENDAR_API)
-        .query(&[("server", &creds.region), ("role_id", &creds.uid)])
-        .header("Cookie", cookie)
-        .header("DS", ds)
-        .header("x-rpc-app_version", "1.5.0")
-        .header("x-rpc-client_type", "5")
-        .header("x-rpc-language", lang)
-        .send()
-        .await?
-        .json::<StarRailResponse>()
-        .await?;
-
-    if resp.retcode != 0 {
-        anyhow::bail!("API error {}: {}", resp.retcode, resp.message);
-    }
-
-    let data = resp
-        .data
-        .ok_or_else(|| anyhow::anyhow!("API returned no data"))?;
+    let data: StarRailCalendarData =
+        HoyolabRequest::get(client, "starrail_calendar", STARRAIL_CALENDAR_API)
+            .query(&[
+                ("server", creds.region.as_str()),
+                ("role_id", creds.uid.as_str()),
+            ])
+            .localized_cookie(&creds.cookie, lang)
+            .preset(HeaderPreset::GameRecord, lang)
+            .sign(DsSalt::Lk2)
+            .send()
+            .await?;
 
     let events: Vec<_> = data
         .act_list
@@ -839,105 +720,58 @@ async fn print_zenless_calendar(
     println!("Lang:   {}", lang);
     println!();
 
-    let cookie = cookie_with_lang(&creds.cookie, lang);
-
-    let activity_resp = client
-        .get(ZENLESS_ACTIVITY_CALENDAR_API)
-        .query(&[
-            ("uid", creds.uid.as_str()),
-            ("region", creds.region.as_str()),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie.clone())
-        .header("x-rpc-language", lang)
-        .send()
-        .await?
-        .json::<ZenlessActivityResponse>()
-        .await?;
-
-    if activity_resp.retcode != 0 {
-        anyhow::bail!(
-            "activity API error {}: {}",
-            activity_resp.retcode,
-            activity_resp.message
-        );
-    }
-
-    let activity_data = activity_resp
-        .data
-        .ok_or_else(|| anyhow::anyhow!("activity API returned no data"))?;
-
-    let gacha_resp = client
-        .get(ZENLESS_GACHA_CALENDAR_API)
-        .query(&[
-            ("uid", creds.uid.as_str()),
-            ("region", creds.region.as_str()),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie.clone())
-        .header("x-rpc-language", lang)
-        .send()
-        .await?
-        .json::<ZenlessGachaResponse>()
-        .await?;
-
-    if gacha_resp.retcode != 0 {
-        anyhow::bail!(
-            "gacha API error {}: {}",
-            gacha_resp.retcode,
-            gacha_resp.message
-        );
-    }
+    let uid = creds.uid.as_str();
+    let region = creds.region.as_str();
+    let request = |endpoint: &'static str, url: &'static str| {
+        HoyolabRequest::get(client, endpoint, url)
+            .localized_cookie(&creds.cookie, lang)
+            .preset(HeaderPreset::ZenlessRecord, lang)
+    };
 
-    let gacha_data = gacha_resp
-        .data
-        .ok_or_else(|| anyhow::anyhow!("gacha API returned no data"))?;
-
-    let deadly_request = client
-        .get(ZENLESS_DEADLY_ASSAULT_API)
-        .query(&[
-            ("uid", creds.uid.as_str()),
-            ("region", creds.region.as_str()),
-            ("schedule_type", "1"),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie.clone())
-        .header("x-rpc-lang", lang)
-        .header("x-rpc-language", lang);
-    let threshold_request = client
-        .get(ZENLESS_THRESHOLD_SIMULATION_API)
-        .query(&[
-            ("region", creds.region.as_str()),
-            ("uid", creds.uid.as_str()),
-            ("schedule_type", "1"),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie.clone())
-        .header("x-rpc-lang", lang)
-        .header("x-rpc-language", lang);
-    let shiyu_request = client
-        .get(ZENLESS_SHIYU_DEFENSE_API)
-        .query(&[
-            ("server", creds.region.as_str()),
-            ("role_id", creds.uid.as_str()),
-            ("schedule_type", "1"),
-            ("without_v2_detail", "true"),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie.clone())
-        .header("x-rpc-lang", lang)
-        .header("x-rpc-language", lang);
-    let annihilation_request = client
-        .get(ZENLESS_ANNIHILATION_SIMULACRUM_API)
-        .query(&[
-            ("region", creds.region.as_str()),
-            ("uid", creds.uid.as_str()),
-            ("schedule_type", "1"),
-            ("lang", lang),
-        ])
-        .header("Cookie", cookie)
-        .header("x-rpc-lang", lang)
-        .header("x-rpc-language", lang);
+    let activity_data: ZenlessActivityData =
+        request("zenless_activity_calendar", ZENLESS_ACTIVITY_CALENDAR_API)
+            .query(&[("uid", uid), ("region", region), ("lang", lang)])
+            .send()
+            .await?;
+    let gacha_data: ZenlessGachaData =
+        request("zenless_gacha_calendar", ZENLESS_GACHA_CALENDAR_API)
+            .query(&[("uid", uid), ("region", region), ("lang", lang)])
+            .send()
+            .await?;
+
+    let deadly_request = request("zenless_deadly_assault", ZENLESS_DEADLY_ASSAULT_API).query(&[
+        ("uid", uid),
+        ("region", region),
+        ("schedule_type", "1"),
+        ("lang", lang),
+    ]);
+    let threshold_request = request(
+        "zenless_threshold_simulation",
+        ZENLESS_THRESHOLD_SIMULATION_API,
+    )
+    .query(&[
+        ("region", region),
+        ("uid", uid),
+        ("schedule_type", "1"),
+        ("lang", lang),
+    ]);
+    let shiyu_request = request("zenless_shiyu_defense", ZENLESS_SHIYU_DEFENSE_API).query(&[
+        ("server", region),
+        ("role_id", uid),
+        ("schedule_type", "1"),
+        ("without_v2_detail", "true"),
+        ("lang", lang),
+    ]);
+    let annihilation_request = request(
+        "zenless_annihilation_simulacrum",
+        ZENLESS_ANNIHILATION_SIMULACRUM_API,
+    )
+    .query(&[
+        ("region", region),
+        ("uid", uid),
+        ("schedule_type", "1"),
+        ("lang", lang),
+    ]);
 
     let (deadly, threshold, shiyu, annihilation) = tokio::join!(
         fetch_zenless_challenge::<ZenlessChallengePeriod>(deadly_request, "Deadly Assault"),
@@ -1,76 +1,61 @@
-//! Standalone test for the Discord webhook notifier.
+//! Standalone test for the notification backends.
 //!
-//! Reads the webhook URL from config.toml and sends a test embed to verify
-//! the webhook is reachable and formatted correctly.
+//! Reads `[notifications]` from config.toml and sends a test message through
+//! every backend configured for the given game (default: starrail). Point
+//! `telegram.api_url`, `matrix.homeserver` or the webhook URLs at a local
+//! mock server to inspect the requests without posting anywhere.
 //!
-//! Run with: cargo run --bin test-notifier
+//! Run with: cargo run --bin test-notifier -- [game]
 
-use serde::Deserialize;
-use serde_json::json;
-
-#[derive(Deserialize)]
-struct Config {
-    notifications: NotificationsConfig,
-}
-
-#[derive(Deserialize)]
-struct NotificationsConfig {
-    discord_webhook: String,
-}
+use hoyoverse_api::config::Config;
+use hoyoverse_api::games::Game;
+use hoyoverse_api::games::server::Server;
+use hoyoverse_api::notifier::{NewCode, Notifiers};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config_str = std::fs::read_to_string("config.toml")
-        .map_err(|_| anyhow::anyhow!("config.toml not found — run from project root"))?;
-    let config: Config = toml::from_str(&config_str)?;
-
-    if config.notifications.discord_webhook.is_empty() {
-        anyhow::bail!("discord_webhook is empty in config.toml [notifications]");
+    let slug = std::env::args().nth(1).unwrap_or_else(|| "starrail".into());
+    let game = Game::from_slug(&slug).ok_or_else(|| anyhow::anyhow!("unknown game: {slug}"))?;
+
+    let config = Config::load()?;
+    let notifiers = Notifiers::from_config(&config.notifications);
+    let backends = notifiers.for_game(game);
+    if backends.is_empty() {
+        anyhow::bail!("no notification backend is configured for {slug} in config.toml");
     }
 
-    println!("Sending test notification to Discord...");
-
-    let payload = json!({
-        "embeds": [{
-            "title": "New Honkai: Star Rail Codes",
-            "color": 0x9C59D1,
-            "fields": [
-                {
-                    "name": "`TESTCODE123`",
-                    "value": "Stellar Jade ×60, Credit ×5000\nSource: fandom",
-                    "inline": false
-                },
-                {
-                    "name": "`ANOTHERCODE`",
-                    "value": "Stellar Jade ×30\nSource: game8",
-                    "inline": false
-                }
-            ],
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-            "footer": {
-                "text": "this is a test notification"
-            }
-        }]
-    });
-
     let client = reqwest::Client::builder()
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
         .build()?;
 
-    let resp = client
-        .post(&config.notifications.discord_webhook)
-        .json(&payload)
-        .send()
-        .await?;
-
-    let status = resp.status();
-    let body = resp.text().await.unwrap_or_default();
-
-    if status.is_success() {
-        println!("Success! status={status}");
-    } else {
-        println!("Failed! status={status}");
-        println!("Response body: {body}");
+    let codes = [
+        NewCode {
+            code: "TESTCODE123".into(),
+            server: Server::Global,
+            rewards: vec!["Stellar Jade ×60".into(), "Credit ×5000".into()],
+            source: "fandom".into(),
+        },
+        NewCode {
+            code: "ANOTHERCODE".into(),
+            server: Server::Global,
+            rewards: vec!["Stellar Jade ×30".into()],
+            source: "game8".into(),
+        },
+    ];
+
+    for notifier in backends {
+        println!("Sending test notification via {}...", notifier.name());
+        match notifier.send_new_codes(&client, game, &codes).await {
+            Ok(()) => println!("  new codes: ok"),
+            Err(e) => println!("  new codes: failed: {e:#}"),
+        }
+        match notifier
+            .send_validation_error(&client, game, "TESTCODE123", "this is a test notification")
+            .await
+        {
+            Ok(()) => println!("  validation error: ok"),
+            Err(e) => println!("  validation error: failed: {e:#}"),
+        }
     }
 
     Ok(())
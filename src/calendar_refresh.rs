@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::global::Global;
+use crate::http::error::ApiErrorCode;
+use crate::http::routes::calendar::{CALENDAR_GAMES, SUPPORTED_LANGS, refresh_calendar};
+use crate::util::sleep_until_aligned;
+
+/// Keeps every (game, language) calendar in memory, so requests never wait on
+/// HoYoLab and an upstream outage serves the last good calendar instead of an
+/// error.
+#[tracing::instrument(name = "CalendarRefresh", skip_all)]
+pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
+    let interval_secs = global.config.calendar.refresh_interval_secs;
+    tracing::info!(interval_secs, "starting calendar refresh");
+
+    loop {
+        for game in CALENDAR_GAMES {
+            for &lang in SUPPORTED_LANGS {
+                match refresh_calendar(&global, game, lang).await {
+                    Ok(()) => {
+                        metrics::counter!("calendar_refreshes_total", "game" => game.slug(), "outcome" => "ok")
+                            .increment(1);
+                    }
+                    Err(e) if e.error_code == ApiErrorCode::NOT_CONFIGURED => {
+                        tracing::debug!(game = game.slug(), "calendar not configured, skipping");
+                        break;
+                    }
+                    Err(e) => {
+                        metrics::counter!("calendar_refreshes_total", "game" => game.slug(), "outcome" => "error")
+                            .increment(1);
+                        tracing::warn!(game = game.slug(), lang, error = ?e, "calendar refresh failed");
+                    }
+                }
+            }
+        }
+
+        sleep_until_aligned(interval_secs).await;
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use crate::database::calendar_history::CalendarHistoryEntry;
+use crate::global::Global;
+use crate::http::error::ApiErrorCode;
+use crate::http::routes::calendar::{CALENDAR_GAMES, calendar_snapshot};
+use crate::util::sleep_until_aligned;
+
+/// Language of the stored snapshots.
+const SNAPSHOT_LANG: &str = "en-us";
+
+/// Periodically stores each game's calendar, so banners and events can still
+/// be queried after they leave the live calendar.
+#[tracing::instrument(name = "CalendarSnapshot", skip_all)]
+pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
+    if !global.config.calendar.snapshots_enabled {
+        tracing::info!("calendar snapshots are disabled");
+        std::future::pending::<()>().await;
+        return Ok(());
+    }
+
+    let interval_secs = global.config.calendar.snapshot_interval_secs;
+    tracing::info!(interval_secs, "starting calendar snapshot job");
+
+    loop {
+        for game in CALENDAR_GAMES {
+            let snapshot = match calendar_snapshot(&global, game, SNAPSHOT_LANG).await {
+                Ok(snapshot) => snapshot,
+                Err(e) if e.error_code == ApiErrorCode::NOT_CONFIGURED => {
+                    tracing::debug!(game = game.slug(), "calendar not configured, skipping");
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!(game = game.slug(), error = ?e, "failed to fetch calendar");
+                    continue;
+                }
+            };
+
+            // Already recorded before the refresh started failing.
+            if snapshot.stale {
+                continue;
+            }
+            let entries = match snapshot.entries() {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!(game = game.slug(), error = ?e, "failed to read calendar");
+                    continue;
+                }
+            };
+
+            match CalendarHistoryEntry::record(&global.db, &entries).await {
+                Ok(()) => {
+                    metrics::counter!("calendar_snapshots_total", "game" => game.slug())
+                        .increment(1);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        game = game.slug(),
+                        "failed to store calendar snapshot: {:#}",
+                        e
+                    );
+                }
+            }
+        }
+
+        sleep_until_aligned(interval_secs).await;
+    }
+}
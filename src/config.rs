@@ -15,6 +15,12 @@ pub struct Config {
     /// Scraper configuration
     pub scraper: ScraperConfig,
 
+    /// Expiry job configuration
+    pub expiry: ExpiryConfig,
+
+    /// Event calendar configuration
+    pub calendar: CalendarConfig,
+
     /// Notifications configuration
     pub notifications: NotificationsConfig,
 
@@ -33,9 +39,17 @@ pub struct ApiConfig {
     /// Rate limiting configuration
     pub rate_limit: RateLimitConfig,
 
+    /// Stricter rate limit for code submissions
+    #[default(RateLimitConfig { per_second: 30, burst_size: 5 })]
+    pub submit_rate_limit: RateLimitConfig,
+
     /// Response cache TTL in seconds
     #[default(300)]
     pub cache_ttl_secs: u64,
+
+    /// Bearer token for the `/admin` endpoints (empty = admin API disabled)
+    #[default("".into())]
+    pub admin_token: String,
 }
 
 #[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
@@ -73,6 +87,22 @@ pub struct ValidatorConfig {
     #[default(1800)]
     pub interval_secs: u64,
 
+    /// How long an account is taken out of rotation after a credentials error
+    #[default(21600)]
+    pub quarantine_secs: u64,
+
+    /// Attempts before a validation job is given up on
+    #[default(5)]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry of a failed job, doubled on each attempt
+    #[default(30)]
+    pub retry_backoff_secs: u64,
+
+    /// How often idle workers check the queue for new jobs
+    #[default(5)]
+    pub poll_interval_secs: u64,
+
     /// Per-game validator settings (only games with a known redeem endpoint)
     #[default(Default::default())]
     pub genshin: GameValidatorConfig,
@@ -100,11 +130,47 @@ pub struct GameValidatorConfig {
     #[default("".into())]
     pub uid: String,
 
-    /// Server region (e.g. os_usa, os_euro, os_asia, os_cht)
+    /// Server region (e.g. os_usa, os_euro, os_asia, os_cht, cn_gf01)
+    #[default("os_usa".into())]
+    pub region: String,
+
+    /// Additional accounts, used in rotation with the one above
+    #[default(Vec::new())]
+    pub accounts: Vec<ValidatorAccount>,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ValidatorAccount {
+    /// Full HoYoLab cookie string
+    #[default("".into())]
+    pub cookie: String,
+
+    /// In-game UID for redemption
+    #[default("".into())]
+    pub uid: String,
+
+    /// Server region (e.g. os_usa, os_euro, os_asia, os_cht, cn_gf01)
     #[default("os_usa".into())]
     pub region: String,
 }
 
+impl GameValidatorConfig {
+    /// Every configured account: the inline one (if set) followed by `accounts`.
+    pub fn all_accounts(&self) -> Vec<ValidatorAccount> {
+        let primary = ValidatorAccount {
+            cookie: self.cookie.clone(),
+            uid: self.uid.clone(),
+            region: self.region.clone(),
+        };
+
+        std::iter::once(primary)
+            .chain(self.accounts.iter().cloned())
+            .filter(|a| !a.cookie.is_empty() && !a.uid.is_empty())
+            .collect()
+    }
+}
+
 impl ValidatorConfig {
     /// Get the per-game config for a given game.
     pub fn game_config(&self, game: crate::games::Game) -> Option<&GameValidatorConfig> {
@@ -130,12 +196,226 @@ pub struct ScraperConfig {
     pub interval_secs: u64,
 }
 
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ExpiryConfig {
+    /// Whether codes past their `expires_at` are deactivated automatically
+    #[default(true)]
+    pub enabled: bool,
+
+    /// Expiry check interval in seconds
+    #[default(600)]
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// How often every (game, language) calendar is re-fetched, in seconds
+    #[default(300)]
+    pub refresh_interval_secs: u64,
+
+    /// Whether calendar snapshots are stored for the banner history
+    #[default(true)]
+    pub snapshots_enabled: bool,
+
+    /// Snapshot interval in seconds
+    #[default(3600)]
+    pub snapshot_interval_secs: u64,
+
+    /// HoYoLab account for the Honkai Impact 3rd calendar, which has no
+    /// validator account to borrow
+    pub honkai: CalendarAccount,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CalendarAccount {
+    /// Full HoYoLab cookie string
+    #[default("".into())]
+    pub cookie: String,
+
+    /// In-game UID
+    #[default("".into())]
+    pub uid: String,
+
+    /// Server region (e.g. usa01, eur01, overseas01, asia01)
+    #[default("usa01".into())]
+    pub region: String,
+}
+
 #[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct NotificationsConfig {
-    /// Discord webhook URL for new code notifications (empty = disabled)
+    /// Discord webhook URL for new code notifications, used by games without
+    /// their own webhook (empty = disabled)
     #[default("".into())]
     pub discord_webhook: String,
+
+    /// Per-game Discord routing
+    pub discord: PerGame<DiscordTarget>,
+
+    /// Telegram Bot API notifications
+    pub telegram: TelegramConfig,
+
+    /// Slack incoming-webhook notifications
+    pub slack: SlackConfig,
+
+    /// Matrix notifications
+    pub matrix: MatrixConfig,
+
+    /// Delivery settings for webhook subscriptions
+    pub webhooks: WebhooksConfig,
+
+    /// Repeats of an unresolved validator alert are suppressed for this long
+    #[default(21600)]
+    pub alert_window_secs: u64,
+
+    /// Daily summary of new and expired codes
+    pub digest: DigestConfig,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Whether the daily digest is sent
+    #[default(false)]
+    pub enabled: bool,
+
+    /// Hour of the day (UTC) at which the digest is sent
+    #[default(9)]
+    pub hour_utc: u32,
+}
+
+/// One value per game, read from `[<section>.<game>]` tables.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PerGame<T> {
+    pub genshin: T,
+    pub starrail: T,
+    pub zenless: T,
+    pub honkai: T,
+    pub themis: T,
+}
+
+impl<T> PerGame<T> {
+    pub fn get(&self, game: crate::games::Game) -> &T {
+        match game {
+            crate::games::Game::Genshin => &self.genshin,
+            crate::games::Game::Starrail => &self.starrail,
+            crate::games::Game::Zenless => &self.zenless,
+            crate::games::Game::Honkai => &self.honkai,
+            crate::games::Game::Themis => &self.themis,
+        }
+    }
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DiscordTarget {
+    /// Whether this game posts to Discord at all
+    #[default(true)]
+    pub enabled: bool,
+
+    /// Webhook for new codes (empty = `discord_webhook`)
+    #[default("".into())]
+    pub webhook_url: String,
+
+    /// Role to mention on new codes (empty = no mention)
+    #[default("".into())]
+    pub role_id: String,
+
+    /// Webhook for validator errors (empty = same as new codes)
+    #[default("".into())]
+    pub error_webhook_url: String,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TelegramConfig {
+    /// Bot API base URL
+    #[default("https://api.telegram.org".into())]
+    pub api_url: String,
+
+    /// Bot token from @BotFather (empty = disabled)
+    #[default("".into())]
+    pub bot_token: String,
+
+    /// Chat to post to for each game
+    #[serde(flatten)]
+    pub games: PerGame<TelegramTarget>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TelegramTarget {
+    pub enabled: bool,
+    /// Chat ID or `@channelusername`
+    pub chat_id: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SlackConfig {
+    /// Incoming webhook for each game
+    #[serde(flatten)]
+    pub games: PerGame<SlackTarget>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SlackTarget {
+    pub enabled: bool,
+    /// Incoming webhook URL
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MatrixConfig {
+    /// Homeserver base URL
+    #[default("https://matrix.org".into())]
+    pub homeserver: String,
+
+    /// Access token of the bot user (empty = disabled)
+    #[default("".into())]
+    pub access_token: String,
+
+    /// Room to post to for each game
+    #[serde(flatten)]
+    pub games: PerGame<MatrixTarget>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MatrixTarget {
+    pub enabled: bool,
+    /// Room ID (`!abc:example.org`); the bot must have joined it
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, smart_default::SmartDefault, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Attempts per delivery before it is marked failed
+    #[default(8)]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubles with each attempt (max 1h)
+    #[default(30)]
+    pub retry_backoff_secs: u64,
+
+    /// How often to check for due deliveries when the queue is empty
+    #[default(5)]
+    pub poll_interval_secs: u64,
+
+    /// Request timeout per delivery attempt
+    #[default(10)]
+    pub timeout_secs: u64,
+
+    /// How long the delivery log is kept
+    #[default(30)]
+    pub log_retention_days: u64,
 }
 
 impl Config {
@@ -162,6 +442,9 @@ impl Config {
         if let Ok(v) = std::env::var("BIND") {
             config.api.bind = v.parse().expect("invalid BIND address");
         }
+        if let Ok(v) = std::env::var("ADMIN_TOKEN") {
+            config.api.admin_token = v;
+        }
 
         Ok(config)
     }
@@ -0,0 +1,101 @@
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+use crate::http::routes::calendar::unified::{CalendarEntry, EntryKind, FeaturedItem};
+
+pub const COLLECTION: &str = "calendar_history";
+
+/// One calendar event, banner or challenge as last seen in a snapshot. There
+/// is a single document per (game, kind, id), so entries outlive the live
+/// calendar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarHistoryEntry {
+    pub game: Game,
+    pub kind: EntryKind,
+    /// The activity ID, as in the unified calendar.
+    pub entry_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub start: Option<i64>,
+    #[serde(default)]
+    pub end: Option<i64>,
+    #[serde(default)]
+    pub featured: Vec<FeaturedItem>,
+    pub first_seen: bson::DateTime,
+    pub last_seen: bson::DateTime,
+}
+
+impl CalendarHistoryEntry {
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    /// Store a snapshot of `entries`, updating entries that were seen before.
+    #[tracing::instrument(skip(db, entries), fields(count = entries.len()))]
+    pub async fn record(db: &mongodb::Database, entries: &[CalendarEntry]) -> anyhow::Result<()> {
+        let collection = Self::collection(db);
+        let now = bson::DateTime::now();
+
+        for entry in entries {
+            collection
+                .update_one(
+                    doc! {
+                        "game": entry.game.slug(),
+                        "kind": entry.kind.name(),
+                        "entry_id": &entry.id,
+                    },
+                    doc! {
+                        "$set": {
+                            "name": &entry.name,
+                            "version": &entry.version,
+                            "image": &entry.image,
+                            "start": entry.start,
+                            "end": entry.end,
+                            "featured": bson::to_bson(&entry.featured)?,
+                            "last_seen": now,
+                        },
+                        "$setOnInsert": { "first_seen": now },
+                    },
+                )
+                .upsert(true)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Banners for `game`, newest first, optionally only those featuring an
+    /// item with this name (case-insensitive).
+    #[tracing::instrument(skip(db))]
+    pub async fn find_banners(
+        db: &mongodb::Database,
+        game: Game,
+        featured: Option<&str>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let mut filter = doc! { "game": game.slug(), "kind": EntryKind::Banner.name() };
+        if let Some(name) = featured {
+            filter.insert(
+                "featured.name",
+                doc! { "$regex": format!("^{}$", regex::escape(name)), "$options": "i" },
+            );
+        }
+
+        let banners = Self::collection(db)
+            .find(filter)
+            .sort(doc! { "start": -1 })
+            .limit(limit)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(banners)
+    }
+}
@@ -0,0 +1,174 @@
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+use crate::games::server::Server;
+use crate::validator::hoyoverse_api::RedeemOutcome;
+
+pub const COLLECTION: &str = "code_events";
+/// One document per (game, retcode) for retcodes outside `RedeemOutcome`.
+pub const UNKNOWN_RETCODES_COLLECTION: &str = "unknown_retcodes";
+
+/// Reason recorded when a newly discovered code fails its first validation.
+pub const REJECTED_ON_DISCOVERY: &str = "failed validation on discovery";
+
+/// An append-only record of something that happened to a code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeEvent {
+    pub game: Game,
+    /// Server the code belongs to.
+    #[serde(default)]
+    pub server: Server,
+    pub code: String,
+    pub kind: CodeEventKind,
+    pub at: bson::DateTime,
+    /// Classified redemption result, for validation events.
+    #[serde(default)]
+    pub outcome: Option<RedeemOutcome>,
+    /// Redemption API retcode, for validation events.
+    #[serde(default)]
+    pub retcode: Option<i32>,
+    /// Redemption API message or a human-readable reason.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// UID of the account used for validation. Kept internal: the public
+    /// history does not expose validator accounts.
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Sources that reported the code, for discovery events.
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeEventKind {
+    Discovered,
+    Validated,
+    Deactivated,
+    Reactivated,
+    ManualOverride,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeEventResponse {
+    pub kind: CodeEventKind,
+    pub at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<RedeemOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retcode: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+impl CodeEvent {
+    pub fn new(game: Game, server: Server, code: &str, kind: CodeEventKind) -> Self {
+        Self {
+            game,
+            server,
+            code: code.to_string(),
+            kind,
+            at: bson::DateTime::now(),
+            outcome: None,
+            retcode: None,
+            message: None,
+            account: None,
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    /// Append an event. Failures are logged rather than returned so that a
+    /// history write never interrupts scraping or validation.
+    #[tracing::instrument(skip(db, self), fields(game = self.game.slug(), server = self.server.slug(), code = self.code, kind = ?self.kind))]
+    pub async fn record(self, db: &mongodb::Database) {
+        if let Err(e) = Self::collection(db).insert_one(&self).await {
+            tracing::warn!(error = %e, "failed to record code event");
+        }
+    }
+
+    /// Fetch the full history of a code, oldest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_for_code(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        code: &str,
+    ) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let events = Self::collection(db)
+            .find(doc! { "game": game.slug(), "server": server.filter(), "code": code })
+            .sort(doc! { "at": 1 })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(events)
+    }
+
+    /// Fetch every event for `game` on `server` since `since`, oldest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_since(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        since: bson::DateTime,
+    ) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let events = Self::collection(db)
+            .find(doc! { "game": game.slug(), "server": server.filter(), "at": { "$gte": since } })
+            .sort(doc! { "at": 1 })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(events)
+    }
+}
+
+/// Remember an unrecognised redemption retcode. Returns `true` the first time
+/// a retcode is seen for a game.
+#[tracing::instrument(skip(db, message))]
+pub async fn note_unknown_retcode(
+    db: &mongodb::Database,
+    game: Game,
+    retcode: i32,
+    message: &str,
+) -> anyhow::Result<bool> {
+    let now = bson::DateTime::now();
+    let result = db
+        .collection::<bson::Document>(UNKNOWN_RETCODES_COLLECTION)
+        .update_one(
+            doc! { "game": game.slug(), "retcode": retcode },
+            doc! {
+                "$setOnInsert": { "first_seen": now, "message": message },
+                "$set": { "last_seen": now },
+                "$inc": { "count": 1 },
+            },
+        )
+        .upsert(true)
+        .await?;
+
+    Ok(result.upserted_id.is_some())
+}
+
+impl From<CodeEvent> for CodeEventResponse {
+    fn from(event: CodeEvent) -> Self {
+        Self {
+            kind: event.kind,
+            at: event.at.to_chrono(),
+            outcome: event.outcome,
+            retcode: event.retcode,
+            message: event.message,
+            sources: event.sources,
+        }
+    }
+}
@@ -1 +1,7 @@
+pub mod calendar_history;
+pub mod code_event;
+pub mod notification_state;
 pub mod redemption_code;
+pub mod validation_job;
+pub mod webhook_delivery;
+pub mod webhook_subscription;
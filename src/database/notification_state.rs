@@ -0,0 +1,171 @@
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+
+pub const COLLECTION: &str = "notification_state";
+
+/// Key of the state document tracking when a game's digest was last sent.
+const DIGEST_KEY: &str = "digest";
+
+/// What has been announced for one (game, key) pair, so that repeated alerts
+/// are suppressed and the digest is sent once a day, also across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationState {
+    pub game: Game,
+    /// Identifies the alert condition, e.g. `credentials:<uid>`.
+    pub key: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    pub first_seen: bson::DateTime,
+    pub last_seen: bson::DateTime,
+    pub last_sent: bson::DateTime,
+    /// Occurrences since the alert was raised, including suppressed ones.
+    pub count: u32,
+    /// Set once the condition has cleared.
+    #[serde(default)]
+    pub resolved_at: Option<bson::DateTime>,
+}
+
+impl NotificationState {
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    /// Record an occurrence of an alert. Returns `true` if it should be sent:
+    /// the alert is new, was resolved before, or was last sent more than
+    /// `window` ago.
+    #[tracing::instrument(skip(db, message))]
+    pub async fn raise(
+        db: &mongodb::Database,
+        game: Game,
+        key: &str,
+        message: &str,
+        window: std::time::Duration,
+    ) -> anyhow::Result<bool> {
+        let collection = Self::collection(db);
+        let now = bson::DateTime::now();
+        let cutoff = bson::DateTime::from_millis(
+            now.timestamp_millis() - i64::try_from(window.as_millis()).unwrap_or(i64::MAX / 2),
+        );
+        let active = doc! { "game": game.slug(), "key": key, "resolved_at": null };
+
+        // Still active and due for a reminder.
+        let mut due = active.clone();
+        due.insert("last_sent", doc! { "$lte": cutoff });
+        let result = collection
+            .update_one(
+                due,
+                doc! {
+                    "$set": { "message": message, "last_seen": now, "last_sent": now },
+                    "$inc": { "count": 1 },
+                },
+            )
+            .await?;
+        if result.modified_count > 0 {
+            return Ok(true);
+        }
+
+        // Still active and sent recently.
+        let result = collection
+            .update_one(
+                active,
+                doc! { "$set": { "last_seen": now }, "$inc": { "count": 1 } },
+            )
+            .await?;
+        if result.matched_count > 0 {
+            return Ok(false);
+        }
+
+        // New, or resolved before. A concurrent raise that got here first
+        // makes the upsert hit the unique index; that one sends.
+        let result = collection
+            .update_one(
+                doc! { "game": game.slug(), "key": key, "resolved_at": { "$ne": null } },
+                doc! { "$set": {
+                    "message": message,
+                    "first_seen": now,
+                    "last_seen": now,
+                    "last_sent": now,
+                    "count": 1,
+                    "resolved_at": null,
+                } },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Mark an alert as cleared. Returns the alert if it was active, so the
+    /// caller can announce that it is resolved.
+    #[tracing::instrument(skip(db))]
+    pub async fn resolve(
+        db: &mongodb::Database,
+        game: Game,
+        key: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let resolved = Self::collection(db)
+            .find_one_and_update(
+                doc! { "game": game.slug(), "key": key, "resolved_at": null },
+                doc! { "$set": { "resolved_at": bson::DateTime::now() } },
+            )
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(resolved)
+    }
+
+    /// Claim today's digest for `game`. Returns `false` if it was already
+    /// sent after `since`.
+    #[tracing::instrument(skip(db))]
+    pub async fn claim_digest(
+        db: &mongodb::Database,
+        game: Game,
+        since: bson::DateTime,
+    ) -> anyhow::Result<bool> {
+        let now = bson::DateTime::now();
+        let result = Self::collection(db)
+            .update_one(
+                doc! { "game": game.slug(), "key": DIGEST_KEY, "last_sent": { "$lt": since } },
+                doc! {
+                    "$set": { "last_sent": now, "last_seen": now },
+                    "$setOnInsert": { "first_seen": now },
+                    "$inc": { "count": 1 },
+                },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Give up a digest claim after the digest could not be delivered, so
+    /// that the next attempt can claim it again.
+    #[tracing::instrument(skip(db))]
+    pub async fn release_digest(db: &mongodb::Database, game: Game) -> anyhow::Result<()> {
+        Self::collection(db)
+            .update_one(
+                doc! { "game": game.slug(), "key": DIGEST_KEY },
+                doc! { "$set": { "last_sent": bson::DateTime::from_millis(0) } },
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        *error.kind,
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(ref e)) if e.code == 11000
+    )
+}
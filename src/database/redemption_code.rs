@@ -1,34 +1,136 @@
 use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
 
+use crate::database::code_event::{CodeEvent, CodeEventKind};
 use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
+use crate::rewards::{self, Reward};
+use crate::scraper::sources::ParsedCode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedemptionCode {
     pub code: String,
     pub active: bool,
     pub date: bson::DateTime,
+    /// Display form of `reward_items`, kept for existing API consumers.
     pub rewards: Vec<String>,
+    /// Rewards reconciled across all sources.
+    #[serde(default)]
+    pub reward_items: Vec<Reward>,
+    /// The source that first reported this code.
     pub source: String,
+    /// Every source that has reported this code, with what it reported.
+    #[serde(default)]
+    pub sources: Vec<SourceReport>,
+    /// Earliest release date reported by any source.
+    #[serde(default)]
+    pub published_at: Option<bson::DateTime>,
+    /// Latest expiry reported by any source. Once passed, the expiry job
+    /// deactivates the code.
+    #[serde(default)]
+    pub expires_at: Option<bson::DateTime>,
+    /// Regions the code is restricted to. Empty means it works everywhere.
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    /// Set by an admin to freeze `active` and `rewards`: the scraper,
+    /// validator and expiry job leave pinned codes as they are.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Set while a newly discovered code waits for its first validation.
+    /// Pending codes are stored inactive and left out of the codes API.
+    #[serde(default)]
+    pub pending: bool,
+}
+
+/// One source's view of a code, refreshed on every scrape cycle that lists it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceReport {
+    pub name: String,
+    pub first_seen: bson::DateTime,
+    pub last_seen: bson::DateTime,
+    pub rewards: Vec<String>,
+    #[serde(default)]
+    pub published_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub expires_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub regions: Vec<Region>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedemptionCodeResponse {
     pub code: String,
     pub rewards: Vec<String>,
+    pub reward_items: Vec<Reward>,
+    pub sources: Vec<SourceReportResponse>,
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub regions: Vec<Region>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReportResponse {
+    pub name: String,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    pub rewards: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<Region>,
 }
 
 impl RedemptionCode {
-    pub fn collection(db: &mongodb::Database, game: Game) -> mongodb::Collection<Self> {
-        db.collection(game.collection_name())
+    /// Build a new, active code from the reports of the sources that found it.
+    pub fn from_reports(
+        game: Game,
+        code: &str,
+        reports: &[(&str, ParsedCode)],
+        now: bson::DateTime,
+    ) -> Self {
+        let mut record = Self {
+            code: code.to_string(),
+            active: true,
+            date: now,
+            rewards: Vec::new(),
+            reward_items: Vec::new(),
+            source: reports
+                .first()
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_default(),
+            sources: Vec::new(),
+            published_at: None,
+            expires_at: None,
+            regions: Vec::new(),
+            pinned: false,
+            pending: false,
+        };
+        record.record_reports(reports, now);
+        record.reconcile(game);
+        record
+    }
+
+    pub fn collection(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+    ) -> mongodb::Collection<Self> {
+        db.collection(game.collection_name(server))
     }
 
     /// Fetch all active codes for a game.
     #[tracing::instrument(skip(db))]
-    pub async fn find_active(db: &mongodb::Database, game: Game) -> anyhow::Result<Vec<Self>> {
+    pub async fn find_active(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+    ) -> anyhow::Result<Vec<Self>> {
         use futures::TryStreamExt;
 
-        let collection = Self::collection(db, game);
+        let collection = Self::collection(db, game, server);
         let codes = collection
             .find(doc! { "active": true })
             .await?
@@ -39,38 +141,273 @@ impl RedemptionCode {
     }
 
     #[tracing::instrument(skip(db))]
-    pub async fn find_all(db: &mongodb::Database, game: Game) -> anyhow::Result<Vec<Self>> {
+    pub async fn find_all(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+    ) -> anyhow::Result<Vec<Self>> {
         use futures::TryStreamExt;
 
-        let collection = Self::collection(db, game);
+        let collection = Self::collection(db, game, server);
         let codes = collection.find(doc! {}).await?.try_collect().await?;
 
         Ok(codes)
     }
 
-    #[allow(dead_code)]
+    /// Fetch active codes whose expiry has passed.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_expired(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        now: bson::DateTime,
+    ) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let collection = Self::collection(db, game, server);
+        let codes = collection
+            .find(doc! {
+                "active": true,
+                "pinned": { "$ne": true },
+                "expires_at": { "$lte": now },
+            })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(codes)
+    }
+
     #[tracing::instrument(skip(db))]
-    pub async fn exists(db: &mongodb::Database, game: Game, code: &str) -> anyhow::Result<bool> {
-        let collection = Self::collection(db, game);
+    pub async fn exists(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        code: &str,
+    ) -> anyhow::Result<bool> {
+        let collection = Self::collection(db, game, server);
         let count = collection.count_documents(doc! { "code": code }).await?;
 
         Ok(count > 0)
     }
 
+    /// Set the active flag, recording a deactivation or reactivation event
+    /// with `reason` if the flag actually changed. Pinned codes are left
+    /// untouched. Returns whether the flag changed.
     #[tracing::instrument(skip(db))]
     pub async fn set_active(
         db: &mongodb::Database,
         game: Game,
+        server: Server,
         code: &str,
         active: bool,
+        reason: &str,
+    ) -> anyhow::Result<bool> {
+        let collection = Self::collection(db, game, server);
+        let previous = collection
+            .find_one_and_update(
+                doc! { "code": code, "pinned": { "$ne": true } },
+                doc! { "$set": { "active": active } },
+            )
+            .await?;
+
+        let changed = previous.is_some_and(|p| p.active != active);
+        if changed {
+            let kind = if active {
+                CodeEventKind::Reactivated
+            } else {
+                CodeEventKind::Deactivated
+            };
+            let mut event = CodeEvent::new(game, server, code, kind);
+            event.message = Some(reason.to_string());
+            event.record(db).await;
+        }
+
+        Ok(changed)
+    }
+
+    /// Settle a pending code once its discovery validation is done: publish
+    /// it as active or keep it inactive. Returns the updated code, or `None`
+    /// if it was no longer pending.
+    #[tracing::instrument(skip(db))]
+    pub async fn resolve_pending(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        code: &str,
+        active: bool,
+    ) -> anyhow::Result<Option<Self>> {
+        let collection = Self::collection(db, game, server);
+        let resolved = collection
+            .find_one_and_update(
+                doc! { "code": code, "pending": true },
+                doc! { "$set": { "active": active, "pending": false } },
+            )
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(resolved)
+    }
+
+    /// Apply an admin change and return the updated code, or `None` if the
+    /// code does not exist.
+    #[tracing::instrument(skip(db))]
+    pub async fn admin_update(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        code: &str,
+        update: bson::Document,
+    ) -> anyhow::Result<Option<Self>> {
+        let collection = Self::collection(db, game, server);
+        let updated = collection
+            .find_one_and_update(doc! { "code": code }, doc! { "$set": update })
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(updated)
+    }
+
+    /// Remove a code. Returns whether it existed.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        code: &str,
+    ) -> anyhow::Result<bool> {
+        let collection = Self::collection(db, game, server);
+        let result = collection.delete_one(doc! { "code": code }).await?;
+
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Persist the per-source history and reconciled rewards and dates of a
+    /// code.
+    #[tracing::instrument(skip(db, record), fields(code = record.code))]
+    pub async fn save_reports(
+        db: &mongodb::Database,
+        game: Game,
+        server: Server,
+        record: &RedemptionCode,
     ) -> anyhow::Result<()> {
-        let collection = Self::collection(db, game);
+        let collection = Self::collection(db, game, server);
         collection
-            .update_one(doc! { "code": code }, doc! { "$set": { "active": active } })
+            .update_one(
+                doc! { "code": &record.code },
+                doc! { "$set": {
+                    "sources": bson::to_bson(&record.sources)?,
+                    "rewards": &record.rewards,
+                    "reward_items": bson::to_bson(&record.reward_items)?,
+                    "published_at": record.published_at,
+                    "expires_at": record.expires_at,
+                    "regions": bson::to_bson(&record.regions)?,
+                } },
+            )
             .await?;
 
         Ok(())
     }
+
+    /// Merge the reports from one scrape cycle into this code's source history.
+    ///
+    /// Sources seen before keep their `first_seen` and get a fresh `last_seen`,
+    /// rewards and dates; new sources are appended. Documents written before
+    /// `sources` existed are seeded from the legacy `source` field.
+    pub fn record_reports(&mut self, reports: &[(&str, ParsedCode)], now: bson::DateTime) {
+        if self.sources.is_empty() && !self.source.is_empty() {
+            self.sources.push(SourceReport {
+                name: self.source.clone(),
+                first_seen: self.date,
+                last_seen: self.date,
+                rewards: self.rewards.clone(),
+                published_at: None,
+                expires_at: None,
+                regions: Vec::new(),
+            });
+        }
+
+        for (name, parsed) in reports {
+            let published_at = parsed.published_at.map(bson::DateTime::from_chrono);
+            let expires_at = parsed.expires_at.map(bson::DateTime::from_chrono);
+            match self.sources.iter_mut().find(|s| s.name == *name) {
+                Some(existing) => {
+                    existing.last_seen = now;
+                    existing.rewards = parsed.rewards.clone();
+                    existing.published_at = published_at;
+                    existing.expires_at = expires_at;
+                    existing.regions = parsed.regions.clone();
+                }
+                None => self.sources.push(SourceReport {
+                    name: name.to_string(),
+                    first_seen: now,
+                    last_seen: now,
+                    rewards: parsed.rewards.clone(),
+                    published_at,
+                    expires_at,
+                    regions: parsed.regions.clone(),
+                }),
+            }
+        }
+    }
+
+    /// Recompute rewards, dates and regions from what each source reported.
+    ///
+    /// `published_at` is the earliest release date any source gives and
+    /// `expires_at` the latest expiry, so a single source with a wrong date
+    /// cannot retire a code early. `regions` is the union of every region
+    /// restriction reported; sources that list none are not counted. The
+    /// rewards of pinned codes are kept as an admin set them.
+    pub fn reconcile(&mut self, game: Game) {
+        if !self.pinned {
+            self.reconcile_rewards(game);
+        }
+        self.published_at = self.sources.iter().filter_map(|s| s.published_at).min();
+        self.expires_at = self.sources.iter().filter_map(|s| s.expires_at).max();
+
+        let mut regions: Vec<Region> = self
+            .sources
+            .iter()
+            .flat_map(|s| s.regions.iter().copied())
+            .collect();
+        regions.sort();
+        regions.dedup();
+        self.regions = regions;
+    }
+
+    /// Whether the code can be redeemed on `region`'s servers.
+    pub fn available_in(&self, region: Region) -> bool {
+        self.regions.is_empty() || self.regions.contains(&region)
+    }
+
+    /// Recompute `reward_items` and `rewards` from what each source reported.
+    fn reconcile_rewards(&mut self, game: Game) {
+        let lists: Vec<Vec<Reward>> = if self.sources.is_empty() {
+            vec![Reward::parse_all(game, &self.rewards)]
+        } else {
+            self.sources
+                .iter()
+                .map(|s| Reward::parse_all(game, &s.rewards))
+                .collect()
+        };
+
+        self.reward_items = rewards::reconcile(&lists);
+        self.rewards = self.reward_items.iter().map(ToString::to_string).collect();
+    }
+}
+
+impl From<SourceReport> for SourceReportResponse {
+    fn from(report: SourceReport) -> Self {
+        Self {
+            name: report.name,
+            first_seen: report.first_seen.to_chrono(),
+            last_seen: report.last_seen.to_chrono(),
+            rewards: report.rewards,
+            published_at: report.published_at.map(|d| d.to_chrono()),
+            expires_at: report.expires_at.map(|d| d.to_chrono()),
+            regions: report.regions,
+        }
+    }
 }
 
 impl From<RedemptionCode> for RedemptionCodeResponse {
@@ -78,6 +415,169 @@ impl From<RedemptionCode> for RedemptionCodeResponse {
         Self {
             code: code.code,
             rewards: code.rewards,
+            reward_items: code.reward_items,
+            sources: code.sources.into_iter().map(Into::into).collect(),
+            published_at: code.published_at.map(|d| d.to_chrono()),
+            expires_at: code.expires_at.map(|d| d.to_chrono()),
+            regions: code.regions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::*;
+
+    fn report(rewards: &[&str]) -> ParsedCode {
+        ParsedCode {
+            rewards: rewards.iter().map(ToString::to_string).collect(),
+            ..Default::default()
         }
     }
+
+    fn code(source: &str, sources: Vec<SourceReport>) -> RedemptionCode {
+        RedemptionCode {
+            code: "TESTCODE".to_string(),
+            active: true,
+            date: bson::DateTime::from_millis(1_000),
+            rewards: vec!["Primogem ×60".to_string()],
+            reward_items: Vec::new(),
+            source: source.to_string(),
+            sources,
+            published_at: None,
+            expires_at: None,
+            regions: Vec::new(),
+            pinned: false,
+            pending: false,
+        }
+    }
+
+    #[test]
+    fn refreshes_known_sources_and_appends_new_ones() {
+        let mut code = code(
+            "fandom",
+            vec![SourceReport {
+                name: "fandom".to_string(),
+                first_seen: bson::DateTime::from_millis(1_000),
+                last_seen: bson::DateTime::from_millis(1_000),
+                rewards: vec!["Primogem ×60".to_string()],
+                published_at: None,
+                expires_at: None,
+                regions: Vec::new(),
+            }],
+        );
+        let now = bson::DateTime::from_millis(5_000);
+        code.record_reports(
+            &[
+                ("fandom", report(&["Primogem ×60", "Mora ×5000"])),
+                ("game8", report(&["Primogem ×60"])),
+            ],
+            now,
+        );
+
+        assert_eq!(code.sources.len(), 2);
+        assert_eq!(
+            code.sources[0].first_seen,
+            bson::DateTime::from_millis(1_000)
+        );
+        assert_eq!(code.sources[0].last_seen, now);
+        assert_eq!(code.sources[0].rewards.len(), 2);
+        assert_eq!(code.sources[1].name, "game8");
+        assert_eq!(code.sources[1].first_seen, now);
+    }
+
+    #[test]
+    fn seeds_history_from_legacy_source() {
+        let mut code = code("hoyolab", Vec::new());
+        let now = bson::DateTime::from_millis(5_000);
+        code.record_reports(&[("game8", report(&[]))], now);
+
+        let names: Vec<_> = code.sources.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["hoyolab", "game8"]);
+        assert_eq!(
+            code.sources[0].last_seen,
+            bson::DateTime::from_millis(1_000)
+        );
+    }
+
+    #[test]
+    fn reconciles_rewards_across_sources() {
+        let mut code = code("fandom", Vec::new());
+        let now = bson::DateTime::from_millis(5_000);
+        code.record_reports(
+            &[
+                ("fandom", report(&["Primogems ×60"])),
+                ("game8", report(&["Primogem x60", "Mora x10,000"])),
+            ],
+            now,
+        );
+        code.reconcile(Game::Genshin);
+
+        assert_eq!(code.rewards, vec!["Primogem ×60", "Mora ×10000"]);
+        assert_eq!(code.reward_items.len(), 2);
+    }
+
+    #[test]
+    fn reconciles_dates_across_sources() {
+        let day = |d| Utc.with_ymd_and_hms(2024, 8, d, 0, 0, 0).unwrap();
+        let mut code = code("fandom", Vec::new());
+        code.record_reports(
+            &[
+                (
+                    "fandom",
+                    ParsedCode {
+                        published_at: Some(day(2)),
+                        expires_at: Some(day(10)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "crimson_witch",
+                    ParsedCode {
+                        published_at: Some(day(1)),
+                        expires_at: Some(day(12)),
+                        ..Default::default()
+                    },
+                ),
+                ("game8", report(&[])),
+            ],
+            bson::DateTime::from_millis(5_000),
+        );
+        code.reconcile(Game::Genshin);
+
+        assert_eq!(code.published_at, Some(bson::DateTime::from_chrono(day(1))));
+        assert_eq!(code.expires_at, Some(bson::DateTime::from_chrono(day(12))));
+    }
+
+    #[test]
+    fn unions_region_restrictions() {
+        let mut code = code("fandom", Vec::new());
+        code.record_reports(
+            &[
+                (
+                    "fandom",
+                    ParsedCode {
+                        regions: vec![Region::Asia],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "crimson_witch",
+                    ParsedCode {
+                        regions: vec![Region::TwHkMo, Region::Asia],
+                        ..Default::default()
+                    },
+                ),
+                ("game8", report(&[])),
+            ],
+            bson::DateTime::from_millis(5_000),
+        );
+        code.reconcile(Game::Genshin);
+
+        assert_eq!(code.regions, vec![Region::Asia, Region::TwHkMo]);
+        assert!(code.available_in(Region::Asia));
+        assert!(!code.available_in(Region::Europe));
+    }
 }
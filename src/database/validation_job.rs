@@ -0,0 +1,322 @@
+use anyhow::Context as _;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
+use crate::validator::hoyoverse_api::RedeemOutcome;
+
+pub const COLLECTION: &str = "validation_jobs";
+
+/// A queued request to check one code against the redemption API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub game: Game,
+    /// Server the code is redeemed on.
+    #[serde(default)]
+    pub server: Server,
+    pub code: String,
+    pub kind: JobKind,
+    /// Lower runs first; see [`JobKind::priority`].
+    pub priority: i32,
+    pub status: JobStatus,
+    /// Regions the code is locked to, for routing to a matching account.
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    pub attempts: u32,
+    /// The job is not picked up before this time.
+    pub run_after: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+    #[serde(default)]
+    pub outcome: Option<RedeemOutcome>,
+    #[serde(default)]
+    pub retcode: Option<i32>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Whether a submitted code was stored once validation finished. False
+    /// when it was rejected or had meanwhile been stored by a scraper.
+    #[serde(default)]
+    pub stored: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// First check of a code the scraper just found.
+    Discovery,
+    /// A code sent in through the submission endpoint; only stored if valid.
+    Submission,
+    /// Periodic recheck of a code that is already active.
+    Revalidation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobKind {
+    pub fn priority(&self) -> i32 {
+        match self {
+            Self::Discovery => 0,
+            Self::Submission => 5,
+            Self::Revalidation => 10,
+        }
+    }
+}
+
+impl ValidationJob {
+    pub fn new(
+        game: Game,
+        server: Server,
+        code: &str,
+        kind: JobKind,
+        regions: Vec<Region>,
+    ) -> Self {
+        let now = bson::DateTime::now();
+        Self {
+            id: None,
+            game,
+            server,
+            code: code.to_string(),
+            kind,
+            priority: kind.priority(),
+            status: JobStatus::Pending,
+            regions,
+            attempts: 0,
+            run_after: now,
+            created_at: now,
+            updated_at: now,
+            outcome: None,
+            retcode: None,
+            message: None,
+            last_error: None,
+            stored: false,
+        }
+    }
+
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    /// Queue a job unless one is already pending or running for the same
+    /// code on the same server. An existing job is bumped to the new job's priority (and kind)
+    /// if that is more urgent. Returns the id of the queued job and whether
+    /// it was newly inserted.
+    #[tracing::instrument(skip(db, self), fields(game = self.game.slug(), server = self.server.slug(), code = self.code, kind = ?self.kind))]
+    pub async fn enqueue(self, db: &mongodb::Database) -> anyhow::Result<(ObjectId, bool)> {
+        let collection = Self::collection(db);
+        let queued = doc! {
+            "game": self.game.slug(),
+            "server": self.server.filter(),
+            "code": &self.code,
+            "status": { "$in": ["pending", "running"] },
+        };
+
+        if let Some(existing) = collection.find_one(queued).await? {
+            let id = existing.id.context("queued job has no id")?;
+            if existing.priority > self.priority {
+                collection
+                    .update_one(
+                        doc! { "_id": id },
+                        doc! { "$set": {
+                            "priority": self.priority,
+                            "kind": bson::to_bson(&self.kind)?,
+                            "updated_at": bson::DateTime::now(),
+                        } },
+                    )
+                    .await?;
+            }
+            return Ok((id, false));
+        }
+
+        let result = collection.insert_one(&self).await?;
+        let id = result
+            .inserted_id
+            .as_object_id()
+            .context("inserted job has no object id")?;
+        Ok((id, true))
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id(
+        db: &mongodb::Database,
+        game: Game,
+        id: ObjectId,
+    ) -> anyhow::Result<Option<Self>> {
+        let job = Self::collection(db)
+            .find_one(doc! { "_id": id, "game": game.slug() })
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Claim the most urgent due job for `game`, marking it running.
+    #[tracing::instrument(skip(db))]
+    pub async fn claim_next(db: &mongodb::Database, game: Game) -> anyhow::Result<Option<Self>> {
+        let now = bson::DateTime::now();
+        let job = Self::collection(db)
+            .find_one_and_update(
+                doc! {
+                    "game": game.slug(),
+                    "status": "pending",
+                    "run_after": { "$lte": now },
+                },
+                doc! {
+                    "$set": { "status": "running", "updated_at": now },
+                    "$inc": { "attempts": 1 },
+                },
+            )
+            .sort(doc! { "priority": 1, "created_at": 1 })
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Mark the job finished with the redemption API's answer and whether
+    /// the code was stored.
+    pub async fn complete(
+        &self,
+        db: &mongodb::Database,
+        outcome: RedeemOutcome,
+        retcode: i32,
+        message: &str,
+        stored: bool,
+    ) -> anyhow::Result<()> {
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! { "$set": {
+                    "status": "done",
+                    "outcome": bson::to_bson(&outcome)?,
+                    "retcode": retcode,
+                    "message": message,
+                    "stored": stored,
+                    "updated_at": bson::DateTime::now(),
+                } },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Put the job back in the queue after `delay`, or mark it failed once
+    /// `max_attempts` is reached.
+    pub async fn retry_or_fail(
+        &self,
+        db: &mongodb::Database,
+        error: &str,
+        delay: std::time::Duration,
+        max_attempts: u32,
+    ) -> anyhow::Result<JobStatus> {
+        let now = bson::DateTime::now();
+        let status = if self.attempts >= max_attempts {
+            JobStatus::Failed
+        } else {
+            JobStatus::Pending
+        };
+        let run_after = bson::DateTime::from_millis(
+            now.timestamp_millis() + i64::try_from(delay.as_millis()).unwrap_or(i64::MAX / 2),
+        );
+
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! { "$set": {
+                    "status": bson::to_bson(&status)?,
+                    "last_error": error,
+                    "run_after": run_after,
+                    "updated_at": now,
+                } },
+            )
+            .await?;
+
+        Ok(status)
+    }
+
+    /// Put the job back in the queue after `delay` without counting the
+    /// run towards `max_attempts`, for waits that say nothing about the code
+    /// such as the redemption cooldown.
+    pub async fn postpone(
+        &self,
+        db: &mongodb::Database,
+        reason: &str,
+        delay: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        let now = bson::DateTime::now();
+        let run_after = bson::DateTime::from_millis(
+            now.timestamp_millis() + i64::try_from(delay.as_millis()).unwrap_or(i64::MAX / 2),
+        );
+
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! {
+                    "$set": {
+                        "status": "pending",
+                        "last_error": reason,
+                        "run_after": run_after,
+                        "updated_at": now,
+                    },
+                    "$inc": { "attempts": -1 },
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Return jobs left running by a previous process to the queue.
+    #[tracing::instrument(skip(db))]
+    pub async fn requeue_running(db: &mongodb::Database) -> anyhow::Result<u64> {
+        let result = Self::collection(db)
+            .update_many(
+                doc! { "status": "running" },
+                doc! { "$set": { "status": "pending", "updated_at": bson::DateTime::now() } },
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+}
+
+/// Exponential backoff for the `attempts`-th failure: `base`, `2 * base`,
+/// `4 * base`, ... capped at one hour.
+pub fn backoff(base: std::time::Duration, attempts: u32) -> std::time::Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    base.saturating_mul(factor)
+        .min(std::time::Duration::from_secs(3600))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let base = Duration::from_secs(30);
+        assert_eq!(backoff(base, 1), Duration::from_secs(30));
+        assert_eq!(backoff(base, 2), Duration::from_secs(60));
+        assert_eq!(backoff(base, 4), Duration::from_secs(240));
+        assert_eq!(backoff(base, 40), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn discovery_runs_before_revalidation() {
+        assert!(JobKind::Discovery.priority() < JobKind::Revalidation.priority());
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Context as _;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::database::webhook_subscription::WebhookEvent;
+use crate::games::Game;
+
+pub const COLLECTION: &str = "webhook_deliveries";
+
+/// One notification for one subscription. Doubles as the retry queue and the
+/// delivery log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub subscription_id: ObjectId,
+    pub game: Game,
+    pub event: WebhookEvent,
+    /// The exact JSON body sent, so that every attempt is signed over the
+    /// same bytes.
+    pub body: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// The delivery is not attempted before this time.
+    pub run_after: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub updated_at: bson::DateTime,
+    /// HTTP status of the last attempt, if a response was received.
+    #[serde(default)]
+    pub response_status: Option<u16>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Sending,
+    Delivered,
+    Failed,
+}
+
+impl WebhookDelivery {
+    pub fn new(subscription_id: ObjectId, game: Game, event: WebhookEvent, body: String) -> Self {
+        let now = bson::DateTime::now();
+        Self {
+            id: None,
+            subscription_id,
+            game,
+            event,
+            body,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            run_after: now,
+            created_at: now,
+            updated_at: now,
+            response_status: None,
+            last_error: None,
+        }
+    }
+
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    pub async fn insert(&self, db: &mongodb::Database) -> anyhow::Result<ObjectId> {
+        let result = Self::collection(db).insert_one(self).await?;
+        result
+            .inserted_id
+            .as_object_id()
+            .context("inserted delivery has no object id")
+    }
+
+    /// Claim the oldest due delivery, marking it as being sent.
+    #[tracing::instrument(skip(db))]
+    pub async fn claim_next(db: &mongodb::Database) -> anyhow::Result<Option<Self>> {
+        let now = bson::DateTime::now();
+        let delivery = Self::collection(db)
+            .find_one_and_update(
+                doc! { "status": "pending", "run_after": { "$lte": now } },
+                doc! {
+                    "$set": { "status": "sending", "updated_at": now },
+                    "$inc": { "attempts": 1 },
+                },
+            )
+            .sort(doc! { "run_after": 1 })
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(delivery)
+    }
+
+    pub async fn mark_delivered(
+        &self,
+        db: &mongodb::Database,
+        response_status: u16,
+    ) -> anyhow::Result<()> {
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! { "$set": {
+                    "status": "delivered",
+                    "response_status": i32::from(response_status),
+                    "last_error": null,
+                    "updated_at": bson::DateTime::now(),
+                } },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Schedule another attempt after `delay`, or mark the delivery failed
+    /// once `max_attempts` is reached.
+    pub async fn retry_or_fail(
+        &self,
+        db: &mongodb::Database,
+        response_status: Option<u16>,
+        error: &str,
+        delay: std::time::Duration,
+        max_attempts: u32,
+    ) -> anyhow::Result<DeliveryStatus> {
+        let now = bson::DateTime::now();
+        let status = if self.attempts >= max_attempts {
+            DeliveryStatus::Failed
+        } else {
+            DeliveryStatus::Pending
+        };
+        let run_after = bson::DateTime::from_millis(
+            now.timestamp_millis() + i64::try_from(delay.as_millis()).unwrap_or(i64::MAX / 2),
+        );
+
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! { "$set": {
+                    "status": bson::to_bson(&status)?,
+                    "response_status": response_status.map(i32::from),
+                    "last_error": error,
+                    "run_after": run_after,
+                    "updated_at": now,
+                } },
+            )
+            .await?;
+
+        Ok(status)
+    }
+
+    /// Give up on a delivery without further attempts.
+    pub async fn fail(&self, db: &mongodb::Database, error: &str) -> anyhow::Result<()> {
+        Self::collection(db)
+            .update_one(
+                doc! { "_id": self.id },
+                doc! { "$set": {
+                    "status": "failed",
+                    "last_error": error,
+                    "updated_at": bson::DateTime::now(),
+                } },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Return deliveries left mid-send by a previous process to the queue.
+    #[tracing::instrument(skip(db))]
+    pub async fn requeue_sending(db: &mongodb::Database) -> anyhow::Result<u64> {
+        let result = Self::collection(db)
+            .update_many(
+                doc! { "status": "sending" },
+                doc! { "$set": { "status": "pending", "updated_at": bson::DateTime::now() } },
+            )
+            .await?;
+
+        Ok(result.modified_count)
+    }
+
+    /// The most recent deliveries for a subscription, newest first.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_recent(
+        db: &mongodb::Database,
+        subscription_id: ObjectId,
+        limit: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let deliveries = Self::collection(db)
+            .find(doc! { "subscription_id": subscription_id })
+            .sort(doc! { "created_at": -1 })
+            .limit(limit)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(deliveries)
+    }
+}
@@ -0,0 +1,149 @@
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+
+pub const COLLECTION: &str = "webhook_subscriptions";
+
+/// A downstream endpoint that receives raw JSON notifications.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub url: String,
+    /// Games to notify about. Empty means every game.
+    #[serde(default)]
+    pub games: Vec<Game>,
+    /// Event types to deliver. Empty means every event type.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Key for the `X-Webhook-Signature` HMAC; unsigned if `None`.
+    #[serde(default)]
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub created_at: bson::DateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A new active code was found.
+    NewCode,
+    /// An active code was deactivated, by its expiry date or the validator.
+    CodeExpired,
+    /// The validator could not check a code.
+    ValidatorError,
+    /// The condition behind an earlier `validator_error` has cleared.
+    ValidatorResolved,
+}
+
+impl WebhookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NewCode => "new_code",
+            Self::CodeExpired => "code_expired",
+            Self::ValidatorError => "validator_error",
+            Self::ValidatorResolved => "validator_resolved",
+        }
+    }
+}
+
+impl WebhookSubscription {
+    pub fn collection(db: &mongodb::Database) -> mongodb::Collection<Self> {
+        db.collection(COLLECTION)
+    }
+
+    /// Whether this subscription wants `event` for `game`.
+    pub fn wants(&self, game: Game, event: WebhookEvent) -> bool {
+        self.enabled
+            && (self.games.is_empty() || self.games.contains(&game))
+            && (self.events.is_empty() || self.events.contains(&event))
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_all(db: &mongodb::Database) -> anyhow::Result<Vec<Self>> {
+        use futures::TryStreamExt;
+
+        let subscriptions = Self::collection(db)
+            .find(doc! {})
+            .sort(doc! { "created_at": 1 })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(subscriptions)
+    }
+
+    /// Enabled subscriptions that want `event` for `game`.
+    #[tracing::instrument(skip(db))]
+    pub async fn find_matching(
+        db: &mongodb::Database,
+        game: Game,
+        event: WebhookEvent,
+    ) -> anyhow::Result<Vec<Self>> {
+        let subscriptions = Self::find_all(db).await?;
+        Ok(subscriptions
+            .into_iter()
+            .filter(|s| s.wants(game, event))
+            .collect())
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn find_by_id(db: &mongodb::Database, id: ObjectId) -> anyhow::Result<Option<Self>> {
+        let subscription = Self::collection(db).find_one(doc! { "_id": id }).await?;
+        Ok(subscription)
+    }
+
+    /// Apply an admin change and return the updated subscription, or `None`
+    /// if it does not exist.
+    #[tracing::instrument(skip(db))]
+    pub async fn update(
+        db: &mongodb::Database,
+        id: ObjectId,
+        update: bson::Document,
+    ) -> anyhow::Result<Option<Self>> {
+        let updated = Self::collection(db)
+            .find_one_and_update(doc! { "_id": id }, doc! { "$set": update })
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await?;
+
+        Ok(updated)
+    }
+
+    /// Delete a subscription. Returns `false` if it did not exist.
+    #[tracing::instrument(skip(db))]
+    pub async fn delete(db: &mongodb::Database, id: ObjectId) -> anyhow::Result<bool> {
+        let result = Self::collection(db).delete_one(doc! { "_id": id }).await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_by_game_and_event() {
+        let mut subscription = WebhookSubscription {
+            id: None,
+            url: "https://example.com/hook".to_string(),
+            games: vec![Game::Genshin],
+            events: vec![WebhookEvent::NewCode],
+            secret: None,
+            enabled: true,
+            created_at: bson::DateTime::now(),
+        };
+
+        assert!(subscription.wants(Game::Genshin, WebhookEvent::NewCode));
+        assert!(!subscription.wants(Game::Starrail, WebhookEvent::NewCode));
+        assert!(!subscription.wants(Game::Genshin, WebhookEvent::CodeExpired));
+
+        subscription.games.clear();
+        subscription.events.clear();
+        assert!(subscription.wants(Game::Starrail, WebhookEvent::ValidatorError));
+
+        subscription.enabled = false;
+        assert!(!subscription.wants(Game::Starrail, WebhookEvent::ValidatorError));
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crate::database::redemption_code::RedemptionCode;
+use crate::games::Game;
+use crate::global::Global;
+use crate::notifier::webhook;
+use crate::util::sleep_until_aligned;
+
+/// Deactivates codes whose `expires_at` has passed. Unlike the validator this
+/// never calls the redemption API, so it also covers games without one.
+#[tracing::instrument(name = "Expiry", skip_all)]
+pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
+    if !global.config.expiry.enabled {
+        tracing::info!("expiry job is disabled");
+        std::future::pending::<()>().await;
+        return Ok(());
+    }
+
+    let interval_secs = global.config.expiry.interval_secs;
+    tracing::info!(interval_secs, "starting expiry job");
+
+    loop {
+        sleep_until_aligned(interval_secs).await;
+
+        if let Err(e) = expire_all_codes(&global).await {
+            tracing::error!("expiry cycle failed: {:#}", e);
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn expire_all_codes(global: &Arc<Global>) -> anyhow::Result<()> {
+    let all_games = [
+        Game::Genshin,
+        Game::Starrail,
+        Game::Zenless,
+        Game::Honkai,
+        Game::Themis,
+    ];
+    let now = bson::DateTime::now();
+
+    for (game, &server) in all_games
+        .iter()
+        .flat_map(|game| game.servers().iter().map(move |server| (*game, server)))
+    {
+        let codes = RedemptionCode::find_expired(&global.db, game, server, now).await?;
+        if codes.is_empty() {
+            continue;
+        }
+
+        let mut deactivated = 0;
+        for code in &codes {
+            let expires_at = code.expires_at.unwrap_or(now).to_chrono();
+            let reason = format!("expired at {}", expires_at.to_rfc3339());
+            if RedemptionCode::set_active(&global.db, game, server, &code.code, false, &reason)
+                .await?
+            {
+                global
+                    .code_feed
+                    .deactivated(game, server, &code.code, &reason);
+                webhook::notify_code_expired(global, game, server, &code.code, &reason).await;
+                metrics::counter!("expiry_codes_deactivated_total", "game" => game.slug())
+                    .increment(1);
+                deactivated += 1;
+            }
+        }
+
+        tracing::info!(
+            game = game.display_name(),
+            server = server.slug(),
+            count = deactivated,
+            "deactivated expired codes"
+        );
+
+        global.invalidate_codes_cache(game).await;
+    }
+
+    Ok(())
+}
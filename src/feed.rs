@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+use crate::games::Game;
+use crate::games::server::Server;
+
+/// Events kept for clients resuming with `Last-Event-ID`.
+const REPLAY_CAPACITY: usize = 512;
+
+/// In-process fan-out of code changes to the SSE stream endpoints.
+///
+/// Event ids increase monotonically. They are seeded from the wall clock at
+/// startup so that ids handed out by a previous process still sort before new
+/// ones, and a client resuming across a restart does not replay old events.
+pub struct CodeFeed {
+    sender: broadcast::Sender<FeedEvent>,
+    state: Mutex<FeedState>,
+}
+
+struct FeedState {
+    next_id: u64,
+    recent: VecDeque<FeedEvent>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FeedEvent {
+    #[serde(skip)]
+    pub id: u64,
+    pub kind: FeedEventKind,
+    pub game: Game,
+    pub server: Server,
+    pub code: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rewards: Vec<String>,
+    /// Why the code was deactivated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedEventKind {
+    /// A new active code was stored.
+    Added,
+    /// An active code was marked inactive.
+    Deactivated,
+}
+
+impl FeedEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Deactivated => "deactivated",
+        }
+    }
+}
+
+impl CodeFeed {
+    pub fn new() -> Self {
+        let seed = u64::try_from(chrono::Utc::now().timestamp_millis()).unwrap_or_default();
+        Self::starting_at(seed)
+    }
+
+    fn starting_at(next_id: u64) -> Self {
+        let (sender, _) = broadcast::channel(REPLAY_CAPACITY);
+        Self {
+            sender,
+            state: Mutex::new(FeedState {
+                next_id,
+                recent: VecDeque::with_capacity(REPLAY_CAPACITY),
+            }),
+        }
+    }
+
+    pub fn added(&self, game: Game, server: Server, code: &str, rewards: Vec<String>) {
+        self.publish(FeedEventKind::Added, game, server, code, rewards, None);
+    }
+
+    pub fn deactivated(&self, game: Game, server: Server, code: &str, reason: &str) {
+        self.publish(
+            FeedEventKind::Deactivated,
+            game,
+            server,
+            code,
+            Vec::new(),
+            Some(reason.to_string()),
+        );
+    }
+
+    fn publish(
+        &self,
+        kind: FeedEventKind,
+        game: Game,
+        server: Server,
+        code: &str,
+        rewards: Vec<String>,
+        reason: Option<String>,
+    ) {
+        let mut state = self.state.lock().expect("code feed lock poisoned");
+        let event = FeedEvent {
+            id: state.next_id,
+            kind,
+            game,
+            server,
+            code: code.to_string(),
+            rewards,
+            reason,
+            at: chrono::Utc::now(),
+        };
+        state.next_id += 1;
+
+        if state.recent.len() == REPLAY_CAPACITY {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(event.clone());
+
+        // No receivers just means nobody is connected.
+        let _ = self.sender.send(event);
+        metrics::counter!("feed_events_total", "game" => game.slug(), "kind" => kind.name())
+            .increment(1);
+    }
+
+    /// Subscribe to new events, along with the buffered events after
+    /// `last_event_id` for a resuming client. Events are neither missed nor
+    /// repeated between the two.
+    pub fn subscribe(
+        &self,
+        last_event_id: Option<u64>,
+    ) -> (Vec<FeedEvent>, broadcast::Receiver<FeedEvent>) {
+        let state = self.state.lock().expect("code feed lock poisoned");
+        let replay = match last_event_id {
+            Some(last) => state
+                .recent
+                .iter()
+                .filter(|e| e.id > last)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        (replay, self.sender.subscribe())
+    }
+}
+
+impl Default for CodeFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_events_after_last_id() {
+        let feed = CodeFeed::starting_at(1);
+        feed.added(Game::Genshin, Server::Global, "FIRST", Vec::new());
+        feed.added(Game::Starrail, Server::Global, "SECOND", Vec::new());
+        feed.deactivated(Game::Genshin, Server::Global, "FIRST", "expired");
+
+        let (replay, _) = feed.subscribe(Some(1));
+        let ids: Vec<u64> = replay.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+
+        let (replay, _) = feed.subscribe(None);
+        assert!(replay.is_empty());
+    }
+
+    #[test]
+    fn delivers_new_events_to_subscribers() {
+        let feed = CodeFeed::starting_at(1);
+        feed.added(Game::Genshin, Server::Global, "FIRST", Vec::new());
+
+        let (replay, mut rx) = feed.subscribe(Some(1));
+        assert!(replay.is_empty());
+
+        feed.deactivated(Game::Genshin, Server::Global, "FIRST", "expired");
+        let event = rx.try_recv().unwrap();
+        assert_eq!((event.id, event.kind), (2, FeedEventKind::Deactivated));
+    }
+
+    #[test]
+    fn keeps_a_bounded_replay_buffer() {
+        let feed = CodeFeed::starting_at(1);
+        for i in 0..REPLAY_CAPACITY + 10 {
+            feed.added(
+                Game::Genshin,
+                Server::Global,
+                &format!("CODE{i}"),
+                Vec::new(),
+            );
+        }
+
+        let (replay, _) = feed.subscribe(Some(0));
+        assert_eq!(replay.len(), REPLAY_CAPACITY);
+        assert_eq!(replay[0].id, 11);
+    }
+}
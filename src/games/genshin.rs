@@ -5,6 +5,35 @@ pub const REDEEM_API: &str =
 /// Genshin Impact game biz identifier.
 pub const GAME_BIZ: &str = "hk4e_global";
 
+/// Genshin Impact redemption API endpoint (mainland China).
+pub const CN_REDEEM_API: &str = "https://hk4e-api.mihoyo.com/common/apicdkey/api/webExchangeCdkey";
+
+/// Genshin Impact game biz identifier on the mainland China servers.
+pub const CN_GAME_BIZ: &str = "hk4e_cn";
+
 /// Genshin Impact activity calendar API endpoint.
 pub const CALENDAR_API: &str =
     "https://sg-public-api.hoyolab.com/event/game_record/genshin/api/act_calendar";
+
+/// Genshin Impact Spiral Abyss record endpoint.
+pub const SPIRAL_ABYSS_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/genshin/api/spiralAbyss";
+
+/// Genshin Impact Imaginarium Theater record endpoint.
+pub const IMAGINARIUM_THEATER_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/genshin/api/role_combat";
+
+/// Genshin Impact Stygian Onslaught record endpoint.
+pub const STYGIAN_ONSLAUGHT_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/genshin/api/hard_challenge";
+
+/// Reward item aliases used by the various code sources, mapped to the
+/// in-game item name.
+pub const REWARD_ALIASES: &[(&str, &str)] = &[
+    ("Primogems", "Primogem"),
+    ("Heros Wit", "Hero's Wit"),
+    ("Hero's Wits", "Hero's Wit"),
+    ("Mystic Enhancement Ores", "Mystic Enhancement Ore"),
+    ("Fine Enhancement Ores", "Fine Enhancement Ore"),
+    ("Adventurer's Experiences", "Adventurer's Experience"),
+];
@@ -0,0 +1,12 @@
+/// HoYoLab battle chronicle calendar API for Honkai Impact 3rd.
+pub const CALENDAR_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/honkai3rd/api/act_calendar";
+
+/// Reward item aliases used by the various code sources, mapped to the
+/// in-game item name.
+pub const REWARD_ALIASES: &[(&str, &str)] = &[
+    ("Crystals", "Crystal"),
+    ("Asterites", "Asterite"),
+    ("Coins", "Coin"),
+    ("Stamina Potions", "Stamina Potion"),
+];
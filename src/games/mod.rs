@@ -1,8 +1,14 @@
 pub mod genshin;
+pub mod honkai;
+pub mod region;
+pub mod server;
 pub mod starrail;
 pub mod themis;
 pub mod zenless;
 
+use region::Region;
+use server::Server;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Game {
@@ -14,13 +20,38 @@ pub enum Game {
 }
 
 impl Game {
-    pub fn collection_name(&self) -> &'static str {
+    pub fn collection_name(&self, server: Server) -> &'static str {
+        match (self, server) {
+            (Self::Genshin, Server::Global) => "genshin_codes",
+            (Self::Starrail, Server::Global) => "starrail_codes",
+            (Self::Zenless, Server::Global) => "zenless_codes",
+            (Self::Honkai, Server::Global) => "honkai_codes",
+            (Self::Themis, Server::Global) => "themis_codes",
+            (Self::Genshin, Server::Cn) => "genshin_cn_codes",
+            (Self::Starrail, Server::Cn) => "starrail_cn_codes",
+            (Self::Zenless, Server::Cn) => "zenless_cn_codes",
+            (Self::Honkai, Server::Cn) => "honkai_cn_codes",
+            (Self::Themis, Server::Cn) => "themis_cn_codes",
+        }
+    }
+
+    /// Servers whose codes are tracked for this game.
+    pub fn servers(&self) -> &'static [Server] {
         match self {
-            Self::Genshin => "genshin_codes",
-            Self::Starrail => "starrail_codes",
-            Self::Zenless => "zenless_codes",
-            Self::Honkai => "honkai_codes",
-            Self::Themis => "themis_codes",
+            Self::Genshin | Self::Starrail | Self::Zenless => &Server::ALL,
+            Self::Honkai | Self::Themis => &[Server::Global],
+        }
+    }
+
+    /// The server a code locked to `regions` is stored and redeemed on. CN
+    /// codes of games whose CN servers are not tracked stay with the global
+    /// ones.
+    pub fn server_for(&self, regions: &[Region]) -> Server {
+        let server = Server::for_regions(regions);
+        if self.servers().contains(&server) {
+            server
+        } else {
+            Server::Global
         }
     }
 
@@ -55,12 +86,15 @@ impl Game {
         }
     }
 
-    pub fn redeem_endpoint(&self) -> Option<&'static str> {
-        match self {
-            Self::Genshin => Some(genshin::REDEEM_API),
-            Self::Starrail => Some(starrail::REDEEM_API),
-            Self::Zenless => Some(zenless::REDEEM_API),
-            Self::Themis => Some(themis::REDEEM_API),
+    pub fn redeem_endpoint(&self, server: Server) -> Option<&'static str> {
+        match (self, server) {
+            (Self::Genshin, Server::Global) => Some(genshin::REDEEM_API),
+            (Self::Starrail, Server::Global) => Some(starrail::REDEEM_API),
+            (Self::Zenless, Server::Global) => Some(zenless::REDEEM_API),
+            (Self::Themis, Server::Global) => Some(themis::REDEEM_API),
+            (Self::Genshin, Server::Cn) => Some(genshin::CN_REDEEM_API),
+            (Self::Starrail, Server::Cn) => Some(starrail::CN_REDEEM_API),
+            (Self::Zenless, Server::Cn) => Some(zenless::CN_REDEEM_API),
             _ => None,
         }
     }
@@ -85,13 +119,39 @@ impl Game {
         }
     }
 
-    pub fn game_biz(&self) -> Option<&'static str> {
-        match self {
-            Self::Genshin => Some(genshin::GAME_BIZ),
-            Self::Starrail => Some(starrail::GAME_BIZ),
-            Self::Zenless => Some(zenless::GAME_BIZ),
-            Self::Themis => Some(themis::GAME_BIZ),
+    /// Web redemption page with `code` filled in, for games that have one.
+    /// CN codes are redeemed in game only.
+    pub fn redeem_url(&self, code: &str, server: Server) -> Option<String> {
+        let base = match (self, server) {
+            (_, Server::Cn) => return None,
+            (Self::Genshin, _) => "https://genshin.hoyoverse.com/en/gift?code=",
+            (Self::Starrail, _) => "https://hsr.hoyoverse.com/gift?code=",
+            (Self::Zenless, _) => "https://zenless.hoyoverse.com/redemption?code=",
+            (Self::Honkai | Self::Themis, _) => return None,
+        };
+        Some(format!("{base}{code}"))
+    }
+
+    pub fn game_biz(&self, server: Server) -> Option<&'static str> {
+        match (self, server) {
+            (Self::Genshin, Server::Global) => Some(genshin::GAME_BIZ),
+            (Self::Starrail, Server::Global) => Some(starrail::GAME_BIZ),
+            (Self::Zenless, Server::Global) => Some(zenless::GAME_BIZ),
+            (Self::Themis, Server::Global) => Some(themis::GAME_BIZ),
+            (Self::Genshin, Server::Cn) => Some(genshin::CN_GAME_BIZ),
+            (Self::Starrail, Server::Cn) => Some(starrail::CN_GAME_BIZ),
+            (Self::Zenless, Server::Cn) => Some(zenless::CN_GAME_BIZ),
             _ => None,
         }
     }
+
+    pub fn reward_aliases(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Genshin => genshin::REWARD_ALIASES,
+            Self::Starrail => starrail::REWARD_ALIASES,
+            Self::Zenless => zenless::REWARD_ALIASES,
+            Self::Honkai => honkai::REWARD_ALIASES,
+            Self::Themis => themis::REWARD_ALIASES,
+        }
+    }
 }
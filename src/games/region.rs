@@ -0,0 +1,125 @@
+/// A game server region. Codes with no regions listed work everywhere.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    America,
+    Europe,
+    Asia,
+    #[serde(rename = "tw_hk_mo")]
+    TwHkMo,
+    Cn,
+}
+
+const ALIASES: &[(&str, Region)] = &[
+    ("america", Region::America),
+    ("americas", Region::America),
+    ("north america", Region::America),
+    ("na", Region::America),
+    ("us", Region::America),
+    ("usa", Region::America),
+    ("europe", Region::Europe),
+    ("eu", Region::Europe),
+    ("asia", Region::Asia),
+    ("sea", Region::Asia),
+    ("tw_hk_mo", Region::TwHkMo),
+    ("tw", Region::TwHkMo),
+    ("hk", Region::TwHkMo),
+    ("mo", Region::TwHkMo),
+    ("sar", Region::TwHkMo),
+    ("cht", Region::TwHkMo),
+    ("cn", Region::Cn),
+    ("china", Region::Cn),
+    ("mainland china", Region::Cn),
+];
+
+impl Region {
+    pub const ALL: [Self; 5] = [
+        Self::America,
+        Self::Europe,
+        Self::Asia,
+        Self::TwHkMo,
+        Self::Cn,
+    ];
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Self::America => "america",
+            Self::Europe => "europe",
+            Self::Asia => "asia",
+            Self::TwHkMo => "tw_hk_mo",
+            Self::Cn => "cn",
+        }
+    }
+
+    /// Parse a single region name or abbreviation, case-insensitively.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(s))
+            .map(|(_, region)| *region)
+    }
+
+    /// Parse a server column such as `Asia`, `NA, EU` or `TW/HK/MO`.
+    ///
+    /// Returns an empty list for `All`/`Global` and for anything
+    /// unrecognised, which callers treat as "no restriction".
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        let mut regions: Vec<Self> = s
+            .split([',', '/', ';', '&'])
+            .flat_map(|part| part.split(" and "))
+            .filter_map(Self::parse)
+            .collect();
+        regions.sort();
+        regions.dedup();
+        regions
+    }
+
+    /// Map a HoYoLab server id (`os_usa`, `prod_official_eur`,
+    /// `prod_gf_jp`, ...) to its region.
+    pub fn from_server(server: &str) -> Option<Self> {
+        match server.rsplit('_').next()? {
+            "usa" | "us" => Some(Self::America),
+            "euro" | "eur" | "eu" => Some(Self::Europe),
+            "asia" | "jp" => Some(Self::Asia),
+            "cht" | "sg" => Some(Self::TwHkMo),
+            "cn" | "gf01" | "qd01" => Some(Self::Cn),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_server_columns() {
+        assert_eq!(Region::parse_list("All"), vec![]);
+        assert_eq!(Region::parse_list("Asia"), vec![Region::Asia]);
+        assert_eq!(
+            Region::parse_list("NA, EU"),
+            vec![Region::America, Region::Europe]
+        );
+        assert_eq!(Region::parse_list("TW/HK/MO"), vec![Region::TwHkMo]);
+        assert_eq!(
+            Region::parse_list("America and Europe"),
+            vec![Region::America, Region::Europe]
+        );
+    }
+
+    #[test]
+    fn maps_server_ids() {
+        assert_eq!(Region::from_server("os_usa"), Some(Region::America));
+        assert_eq!(
+            Region::from_server("prod_official_eur"),
+            Some(Region::Europe)
+        );
+        assert_eq!(Region::from_server("prod_gf_jp"), Some(Region::Asia));
+        assert_eq!(Region::from_server("os_cht"), Some(Region::TwHkMo));
+        assert_eq!(Region::from_server("cn_gf01"), Some(Region::Cn));
+        assert_eq!(Region::from_server("unknown"), None);
+    }
+}
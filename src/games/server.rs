@@ -0,0 +1,109 @@
+use super::region::Region;
+
+/// Which family of game servers a code belongs to. Mainland China servers
+/// are run by miHoYo with their own accounts, redemption endpoints and codes,
+/// so CN codes are stored apart from the overseas ones.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Server {
+    /// The overseas HoYoverse servers.
+    #[default]
+    Global,
+    /// The mainland China miHoYo servers.
+    Cn,
+}
+
+const ALIASES: &[(&str, Server)] = &[
+    ("global", Server::Global),
+    ("os", Server::Global),
+    ("overseas", Server::Global),
+    ("cn", Server::Cn),
+    ("china", Server::Cn),
+    ("mainland", Server::Cn),
+];
+
+impl Server {
+    pub const ALL: [Self; 2] = [Self::Global, Self::Cn];
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Cn => "cn",
+        }
+    }
+
+    /// Parse a server name, case-insensitively.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(s))
+            .map(|(_, server)| *server)
+    }
+
+    /// The server a region belongs to.
+    pub fn of_region(region: Region) -> Self {
+        match region {
+            Region::Cn => Self::Cn,
+            _ => Self::Global,
+        }
+    }
+
+    /// The server a code locked to `regions` is redeemed on: CN when every
+    /// listed region is mainland China, global otherwise.
+    pub fn for_regions(regions: &[Region]) -> Self {
+        if !regions.is_empty() && regions.iter().all(|&r| r == Region::Cn) {
+            Self::Cn
+        } else {
+            Self::Global
+        }
+    }
+
+    /// Query value matching documents stored for this server. Documents
+    /// written before the server was recorded belong to the global server.
+    pub fn filter(&self) -> bson::Bson {
+        match self {
+            Self::Global => bson::bson!({ "$in": [self.slug(), null] }),
+            Self::Cn => self.slug().into(),
+        }
+    }
+
+    /// Region restriction implied by the server, for codes that name none.
+    pub fn regions(&self) -> Vec<Region> {
+        match self {
+            Self::Global => Vec::new(),
+            Self::Cn => vec![Region::Cn],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_regions_to_servers() {
+        assert_eq!(Server::for_regions(&[]), Server::Global);
+        assert_eq!(Server::for_regions(&[Region::Asia]), Server::Global);
+        assert_eq!(Server::for_regions(&[Region::Cn]), Server::Cn);
+        assert_eq!(
+            Server::for_regions(&[Region::Asia, Region::Cn]),
+            Server::Global
+        );
+        assert_eq!(Server::parse("CN"), Some(Server::Cn));
+        assert_eq!(Server::parse("overseas"), Some(Server::Global));
+        assert_eq!(Server::parse("moon"), None);
+    }
+}
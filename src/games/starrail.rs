@@ -5,6 +5,32 @@ pub const REDEEM_API: &str =
 /// Honkai: Star Rail game biz identifier.
 pub const GAME_BIZ: &str = "hkrpg_global";
 
+/// Honkai: Star Rail redemption API endpoint (mainland China).
+pub const CN_REDEEM_API: &str =
+    "https://api-takumi.mihoyo.com/common/apicdkey/api/webExchangeCdkey";
+
+/// Honkai: Star Rail game biz identifier on the mainland China servers.
+pub const CN_GAME_BIZ: &str = "hkrpg_cn";
+
 /// HoYoLab activity calendar API for Star Rail.
 pub const CALENDAR_API: &str =
     "https://sg-public-api.hoyolab.com/event/game_record/hkrpg/api/get_act_calender";
+
+/// Pure Fiction record endpoint.
+pub const PURE_FICTION_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/hkrpg/api/challenge_story";
+
+/// Apocalyptic Shadow record endpoint.
+pub const APOCALYPTIC_SHADOW_API: &str =
+    "https://sg-public-api.hoyolab.com/event/game_record/hkrpg/api/challenge_boss";
+
+/// Reward item aliases used by the various code sources, mapped to the
+/// in-game item name.
+pub const REWARD_ALIASES: &[(&str, &str)] = &[
+    ("Stellar Jades", "Stellar Jade"),
+    ("Credits", "Credit"),
+    ("Travelers Guide", "Traveler's Guide"),
+    ("Traveler's Guides", "Traveler's Guide"),
+    ("Refined Aethers", "Refined Aether"),
+    ("Adventure Logs", "Adventure Log"),
+];
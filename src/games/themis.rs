@@ -7,3 +7,7 @@ pub const REFERER: &str = "https://tot.hoyoverse.com/";
 
 /// Tears of Themis game biz identifier.
 pub const GAME_BIZ: &str = "nxx_global";
+
+/// Reward item aliases used by the various code sources, mapped to the
+/// in-game item name.
+pub const REWARD_ALIASES: &[(&str, &str)] = &[("Tear", "Tears"), ("Stellins", "Stellin")];
@@ -2,6 +2,10 @@
 pub const REDEEM_API: &str =
     "https://public-operation-nap.hoyoverse.com/common/apicdkey/api/webExchangeCdkey";
 
+/// Zenless Zone Zero redemption API endpoint (mainland China).
+pub const CN_REDEEM_API: &str =
+    "https://api-takumi.mihoyo.com/common/apicdkey/api/webExchangeCdkey";
+
 /// Zenless Zone Zero activity calendar endpoint.
 pub const ACTIVITY_CALENDAR_API: &str =
     "https://sg-act-public-api.hoyolab.com/event/game_record_zzz/api/zzz/activity_calendar";
@@ -27,3 +31,16 @@ pub const ANNIHILATION_SIMULACRUM_API: &str =
 
 /// Zenless Zone Zero game biz identifier.
 pub const GAME_BIZ: &str = "nap_global";
+
+/// Zenless Zone Zero game biz identifier on the mainland China servers.
+pub const CN_GAME_BIZ: &str = "nap_cn";
+
+/// Reward item aliases used by the various code sources, mapped to the
+/// in-game item name.
+pub const REWARD_ALIASES: &[(&str, &str)] = &[
+    ("Polychromes", "Polychrome"),
+    ("Denny", "Dennies"),
+    ("W-Engine Energy Modules", "W-Engine Energy Module"),
+    ("Senior Investigator Log", "Senior Investigator Logs"),
+    ("Official Investigator Log", "Official Investigator Logs"),
+];
@@ -9,8 +9,17 @@ use mongodb::IndexModel;
 use mongodb::bson::doc;
 
 use crate::config::Config;
+use crate::database::{
+    calendar_history, code_event, notification_state, validation_job, webhook_delivery,
+};
+use crate::feed::CodeFeed;
 use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
 use crate::http::error::ApiError;
+use crate::http::routes::calendar::CalendarStore;
+use crate::notifier::Notifiers;
+use crate::validator::pool::AccountPool;
 
 pub struct ResponseCache {
     store: Cache<String, Bytes>,
@@ -38,6 +47,23 @@ impl ResponseCache {
     }
 }
 
+/// Response cache key of the codes endpoint for one server and region.
+pub fn codes_cache_key(game: Game, server: Server, region: Option<Region>) -> String {
+    let mut key = format!("/mihoyo/{}/codes", game.slug());
+    let mut params = Vec::new();
+    if server != Server::Global {
+        params.push(format!("server={}", server.slug()));
+    }
+    if let Some(region) = region {
+        params.push(format!("region={}", region.slug()));
+    }
+    if !params.is_empty() {
+        key.push('?');
+        key.push_str(&params.join("&"));
+    }
+    key
+}
+
 pub struct Global {
     pub config: Config,
     #[allow(dead_code)]
@@ -48,7 +74,10 @@ pub struct Global {
     pub response_cache: ResponseCache,
     pub fandom_image_cache: ResponseCache,
     pub news_cache: ResponseCache,
-    pub discord_webhook: Option<String>,
+    pub calendars: CalendarStore,
+    pub notifiers: Notifiers,
+    pub validator_pool: AccountPool,
+    pub code_feed: CodeFeed,
 }
 
 impl Global {
@@ -61,7 +90,7 @@ impl Global {
 
         let db = mongo.database(&config.database.name);
 
-        Self::ensure_indexes(&db).await?;
+        Self::ensure_indexes(&db, &config).await?;
 
         let http_client = reqwest::Client::builder()
             .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
@@ -72,11 +101,9 @@ impl Global {
         let fandom_image_cache = ResponseCache::new(Duration::from_secs(24 * 3600));
         let news_cache = ResponseCache::new(Duration::from_secs(15 * 60));
 
-        let discord_webhook = if config.notifications.discord_webhook.is_empty() {
-            None
-        } else {
-            Some(config.notifications.discord_webhook.clone())
-        };
+        let notifiers = Notifiers::from_config(&config.notifications);
+
+        let validator_pool = AccountPool::new(&config.validator);
 
         Ok(Arc::new(Self {
             config,
@@ -87,11 +114,14 @@ impl Global {
             response_cache,
             fandom_image_cache,
             news_cache,
-            discord_webhook,
+            calendars: CalendarStore::new(),
+            notifiers,
+            validator_pool,
+            code_feed: CodeFeed::new(),
         }))
     }
 
-    async fn ensure_indexes(db: &mongodb::Database) -> anyhow::Result<()> {
+    async fn ensure_indexes(db: &mongodb::Database, config: &Config) -> anyhow::Result<()> {
         let games = [
             Game::Genshin,
             Game::Starrail,
@@ -100,8 +130,12 @@ impl Global {
             Game::Themis,
         ];
 
-        for game in games {
-            let collection = db.collection::<mongodb::bson::Document>(game.collection_name());
+        for (game, &server) in games
+            .iter()
+            .flat_map(|game| game.servers().iter().map(move |server| (game, server)))
+        {
+            let name = game.collection_name(server);
+            let collection = db.collection::<mongodb::bson::Document>(name);
             collection
                 .create_index(
                     IndexModel::builder()
@@ -114,10 +148,119 @@ impl Global {
                         .build(),
                 )
                 .await
-                .with_context(|| format!("creating unique index on {}", game.collection_name()))?;
+                .with_context(|| format!("creating unique index on {name}"))?;
         }
 
-        tracing::info!("ensured unique indexes on code collections");
+        db.collection::<mongodb::bson::Document>(code_event::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "server": 1, "code": 1, "at": 1 })
+                    .build(),
+            )
+            .await
+            .context("creating index on code_events")?;
+        db.collection::<mongodb::bson::Document>(code_event::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "server": 1, "at": 1 })
+                    .build(),
+            )
+            .await
+            .context("creating time index on code_events")?;
+
+        db.collection::<mongodb::bson::Document>(calendar_history::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "kind": 1, "entry_id": 1 })
+                    .options(
+                        mongodb::options::IndexOptions::builder()
+                            .unique(true)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .context("creating unique index on calendar_history")?;
+        db.collection::<mongodb::bson::Document>(calendar_history::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "kind": 1, "start": -1 })
+                    .build(),
+            )
+            .await
+            .context("creating start index on calendar_history")?;
+
+        db.collection::<mongodb::bson::Document>(validation_job::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "status": 1, "priority": 1, "created_at": 1 })
+                    .build(),
+            )
+            .await
+            .context("creating index on validation_jobs")?;
+
+        db.collection::<mongodb::bson::Document>(notification_state::COLLECTION)
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "game": 1, "key": 1 })
+                    .options(
+                        mongodb::options::IndexOptions::builder()
+                            .unique(true)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .context("creating unique index on notification_state")?;
+
+        let deliveries = db.collection::<mongodb::bson::Document>(webhook_delivery::COLLECTION);
+        deliveries
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "status": 1, "run_after": 1 })
+                    .build(),
+            )
+            .await
+            .context("creating queue index on webhook_deliveries")?;
+        deliveries
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "subscription_id": 1, "created_at": -1 })
+                    .build(),
+            )
+            .await
+            .context("creating log index on webhook_deliveries")?;
+        deliveries
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "created_at": 1 })
+                    .options(
+                        mongodb::options::IndexOptions::builder()
+                            .expire_after(Duration::from_secs(
+                                config.notifications.webhooks.log_retention_days * 24 * 3600,
+                            ))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .context("creating retention index on webhook_deliveries")?;
+
+        tracing::info!("ensured indexes on code collections");
         Ok(())
     }
+
+    /// Drop every cached variant of a game's codes response.
+    pub async fn invalidate_codes_cache(&self, game: Game) {
+        for &server in game.servers() {
+            self.response_cache
+                .remove(&codes_cache_key(game, server, None))
+                .await;
+            for region in Region::ALL {
+                self.response_cache
+                    .remove(&codes_cache_key(game, server, Some(region)))
+                    .await;
+            }
+        }
+    }
 }
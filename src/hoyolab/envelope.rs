@@ -0,0 +1,76 @@
+use serde::Deserialize;
+
+/// The `{ retcode, message, data }` wrapper around every HoYoLab response.
+#[derive(Debug, Deserialize)]
+pub struct HoyolabEnvelope<T> {
+    pub retcode: i32,
+    pub message: String,
+    pub data: Option<T>,
+}
+
+impl<T> HoyolabEnvelope<T> {
+    /// The data of a successful response.
+    pub fn into_data(self) -> Result<T, HoyolabError> {
+        if self.retcode != 0 {
+            return Err(HoyolabError::Api {
+                retcode: self.retcode,
+                message: self.message,
+            });
+        }
+        self.data.ok_or(HoyolabError::NoData)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HoyolabError {
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("failed to parse response: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("retcode {retcode}: {message}")]
+    Api { retcode: i32, message: String },
+    #[error("response has no data")]
+    NoData,
+}
+
+impl HoyolabError {
+    /// Metric label for this error.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Request(_) => "request_error",
+            Self::Parse(_) => "parse_error",
+            Self::Api { .. } => "api_error",
+            Self::NoData => "no_data",
+        }
+    }
+
+    /// The retcode of an API error.
+    pub fn retcode(&self) -> Option<i32> {
+        match self {
+            Self::Api { retcode, .. } => Some(*retcode),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_envelopes() {
+        let ok: HoyolabEnvelope<Vec<u32>> =
+            serde_json::from_str(r#"{"retcode":0,"message":"OK","data":[1,2]}"#).unwrap();
+        assert_eq!(ok.into_data().unwrap(), vec![1, 2]);
+
+        let failed: HoyolabEnvelope<Vec<u32>> =
+            serde_json::from_str(r#"{"retcode":-100,"message":"Please log in"}"#).unwrap();
+        let error = failed.into_data().unwrap_err();
+        assert_eq!(error.retcode(), Some(-100));
+        assert_eq!(error.to_string(), "retcode -100: Please log in");
+
+        let empty: HoyolabEnvelope<Vec<u32>> =
+            serde_json::from_str(r#"{"retcode":0,"message":"OK","data":null}"#).unwrap();
+        assert!(matches!(empty.into_data(), Err(HoyolabError::NoData)));
+    }
+}
@@ -0,0 +1,228 @@
+//! Requests to HoYoLab APIs: DS signing, per-endpoint header presets and
+//! decoding of the `retcode` envelope.
+
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+
+mod ds;
+mod envelope;
+
+pub use ds::DsSalt;
+pub use envelope::{HoyolabEnvelope, HoyolabError};
+
+/// Headers an endpoint family expects besides the cookie and DS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderPreset {
+    /// Calendars and battle records on `sg-public-api.hoyolab.com`.
+    GameRecord,
+    /// Zenless calendars and records on `sg-act-*-api.hoyolab.com`.
+    ZenlessRecord,
+    /// News and event lists on `bbs-api-os.hoyolab.com`.
+    Community,
+}
+
+impl HeaderPreset {
+    fn headers(&self, lang: &str) -> Vec<(&'static str, String)> {
+        match self {
+            Self::GameRecord => vec![
+                ("x-rpc-app_version", "1.5.0".to_string()),
+                ("x-rpc-client_type", "5".to_string()),
+                ("x-rpc-language", lang.to_string()),
+            ],
+            Self::ZenlessRecord => vec![
+                ("x-rpc-lang", lang.to_string()),
+                ("x-rpc-language", lang.to_string()),
+            ],
+            Self::Community => vec![("x-rpc-language", lang.to_string())],
+        }
+    }
+}
+
+/// A HoYoLab API request. `endpoint` names it in logs and in the
+/// `hoyolab_requests_total` metric.
+pub struct HoyolabRequest<'a> {
+    client: &'a reqwest::Client,
+    endpoint: &'static str,
+    method: Method,
+    url: &'a str,
+    query: Vec<(String, String)>,
+    body: Option<serde_json::Value>,
+    headers: Vec<(&'static str, String)>,
+    salt: Option<DsSalt>,
+}
+
+impl<'a> HoyolabRequest<'a> {
+    pub fn get(client: &'a reqwest::Client, endpoint: &'static str, url: &'a str) -> Self {
+        Self {
+            client,
+            endpoint,
+            method: Method::GET,
+            url,
+            query: Vec::new(),
+            body: None,
+            headers: Vec::new(),
+            salt: None,
+        }
+    }
+
+    pub fn post(
+        client: &'a reqwest::Client,
+        endpoint: &'static str,
+        url: &'a str,
+        body: serde_json::Value,
+    ) -> Self {
+        Self {
+            method: Method::POST,
+            body: Some(body),
+            ..Self::get(client, endpoint, url)
+        }
+    }
+
+    pub fn query(mut self, params: &[(&str, &str)]) -> Self {
+        self.query.extend(
+            params
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        self
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub fn preset(mut self, preset: HeaderPreset, lang: &str) -> Self {
+        self.headers.extend(preset.headers(lang));
+        self
+    }
+
+    pub fn cookie(self, cookie: &str) -> Self {
+        self.header("Cookie", cookie)
+    }
+
+    /// Send `cookie` with its `mi18nLang` replaced by `lang`, which some
+    /// endpoints read instead of the language header.
+    pub fn localized_cookie(self, cookie: &str, lang: &str) -> Self {
+        self.header("Cookie", cookie_with_lang(cookie, lang))
+    }
+
+    /// Sign the request with a DS header when it is sent.
+    pub fn sign(mut self, salt: DsSalt) -> Self {
+        self.salt = Some(salt);
+        self
+    }
+
+    /// Send the request and return the data of a successful response. Any
+    /// non-zero retcode is an error.
+    pub async fn send<T: DeserializeOwned>(self) -> Result<T, HoyolabError> {
+        let endpoint = self.endpoint;
+        let result = self
+            .send_inner::<T>()
+            .await
+            .and_then(HoyolabEnvelope::into_data);
+
+        let outcome = match &result {
+            Ok(_) => "ok",
+            Err(error) => {
+                tracing::error!(endpoint, retcode = error.retcode(), error = %error, "hoyolab request failed");
+                error.label()
+            }
+        };
+        record(endpoint, outcome);
+        result
+    }
+
+    /// Send the request and return the whole envelope, for endpoints whose
+    /// retcodes are answers rather than failures, such as code redemption.
+    pub async fn send_envelope<T: DeserializeOwned>(
+        self,
+    ) -> Result<HoyolabEnvelope<T>, HoyolabError> {
+        let endpoint = self.endpoint;
+        let result = self.send_inner::<T>().await;
+
+        let outcome = match &result {
+            Ok(envelope) if envelope.retcode == 0 => "ok",
+            Ok(_) => "api_error",
+            Err(error) => {
+                tracing::error!(endpoint, retcode = error.retcode(), error = %error, "hoyolab request failed");
+                error.label()
+            }
+        };
+        record(endpoint, outcome);
+        result
+    }
+
+    async fn send_inner<T: DeserializeOwned>(self) -> Result<HoyolabEnvelope<T>, HoyolabError> {
+        let mut request = self
+            .client
+            .request(self.method, self.url)
+            .query(&self.query);
+        for (name, value) in self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(salt) = self.salt {
+            let body = self
+                .body
+                .as_ref()
+                .map(serde_json::Value::to_string)
+                .unwrap_or_default();
+            request = request.header("DS", salt.sign(&body, &sorted_query(&self.query)));
+        }
+        if let Some(body) = &self.body {
+            request = request.json(body);
+        }
+
+        let bytes = request.send().await?.error_for_status()?.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+fn record(endpoint: &'static str, outcome: &'static str) {
+    metrics::counter!("hoyolab_requests_total", "endpoint" => endpoint, "outcome" => outcome)
+        .increment(1);
+}
+
+/// The query string as the DS hash covers it: `key=value` pairs sorted by key.
+fn sorted_query(query: &[(String, String)]) -> String {
+    let mut pairs: Vec<String> = query
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// Replace the `mi18nLang` cookie with `lang`.
+pub fn cookie_with_lang(cookie: &str, lang: &str) -> String {
+    let mut parts: Vec<String> = cookie
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && !part.starts_with("mi18nLang="))
+        .map(ToOwned::to_owned)
+        .collect();
+    parts.insert(0, format!("mi18nLang={lang}"));
+    parts.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localizes_cookie() {
+        assert_eq!(
+            cookie_with_lang("ltuid_v2=1; mi18nLang=en-us; ltoken_v2=abc", "ja-jp"),
+            "mi18nLang=ja-jp; ltuid_v2=1; ltoken_v2=abc"
+        );
+    }
+
+    #[test]
+    fn sorts_query_for_signing() {
+        let query = vec![
+            ("server".to_string(), "os_usa".to_string()),
+            ("role_id".to_string(), "1".to_string()),
+        ];
+        assert_eq!(sorted_query(&query), "role_id=1&server=os_usa");
+    }
+}
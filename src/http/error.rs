@@ -4,6 +4,8 @@ use axum::Json;
 use axum::response::IntoResponse;
 use hyper::StatusCode;
 
+use crate::hoyolab::HoyolabError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
 #[serde(transparent)]
 pub struct ApiErrorCode(pub u16);
@@ -15,12 +17,30 @@ impl ApiErrorCode {
     pub const UNKNOWN_GAME: Self = Self(1000);
     /// The requested language is not supported.
     pub const INVALID_LANGUAGE: Self = Self(1001);
+    /// The requested code is not known for this game.
+    pub const UNKNOWN_CODE: Self = Self(1002);
+    /// The requested server region is not recognised.
+    pub const INVALID_REGION: Self = Self(1003);
+    /// The code already exists for this game.
+    pub const CODE_EXISTS: Self = Self(1004);
+    /// The request body is missing a required value.
+    pub const INVALID_BODY: Self = Self(1005);
+    /// The requested code submission does not exist.
+    pub const UNKNOWN_SUBMISSION: Self = Self(1006);
+    /// The requested webhook subscription does not exist.
+    pub const UNKNOWN_WEBHOOK: Self = Self(1007);
+    /// A query filter names an unknown value.
+    pub const INVALID_FILTER: Self = Self(1008);
+    /// The requested server is not recognised or not tracked for this game.
+    pub const INVALID_SERVER: Self = Self(1009);
     /// A database query failed unexpectedly.
     pub const DATABASE_ERROR: Self = Self(2000);
     /// A required feature is not configured.
     pub const NOT_CONFIGURED: Self = Self(3000);
     /// An upstream API call failed.
     pub const UPSTREAM_ERROR: Self = Self(3001);
+    /// The request is missing valid admin credentials.
+    pub const UNAUTHORIZED: Self = Self(4000);
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -46,15 +66,22 @@ impl ApiError {
         }
     }
 
-    #[allow(dead_code)]
     pub fn bad_request(error_code: ApiErrorCode, error: impl Into<Cow<'static, str>>) -> Self {
         Self::new(StatusCode::BAD_REQUEST, error_code, error)
     }
 
+    pub fn unauthorized(error_code: ApiErrorCode, error: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, error_code, error)
+    }
+
     pub fn not_found(error_code: ApiErrorCode, error: impl Into<Cow<'static, str>>) -> Self {
         Self::new(StatusCode::NOT_FOUND, error_code, error)
     }
 
+    pub fn conflict(error_code: ApiErrorCode, error: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(StatusCode::CONFLICT, error_code, error)
+    }
+
     pub fn internal_server_error(
         error_code: ApiErrorCode,
         error: impl Into<Cow<'static, str>>,
@@ -68,3 +95,15 @@ impl IntoResponse for ApiError {
         (self.status_code, Json(self)).into_response()
     }
 }
+
+impl From<HoyolabError> for ApiError {
+    fn from(error: HoyolabError) -> Self {
+        let message = match error {
+            HoyolabError::Request(_) => "failed to reach upstream API",
+            HoyolabError::Parse(_) => "failed to parse upstream response",
+            HoyolabError::Api { .. } => "upstream API returned an error",
+            HoyolabError::NoData => "upstream API returned no data",
+        };
+        Self::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, message)
+    }
+}
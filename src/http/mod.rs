@@ -79,6 +79,7 @@ fn app(global: Arc<Global>) -> Router {
     Router::new()
         .route("/metrics", get(metrics_handler))
         .nest("/mihoyo", routes::routes(&global))
+        .nest("/admin", routes::admin::routes(&global))
         .with_state(global)
         .fallback(not_found)
         .layer(
@@ -0,0 +1,561 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+
+use crate::database::code_event::{CodeEvent, CodeEventKind};
+use crate::database::redemption_code::{RedemptionCode, RedemptionCodeResponse};
+use crate::database::webhook_delivery::{DeliveryStatus, WebhookDelivery};
+use crate::database::webhook_subscription::{WebhookEvent, WebhookSubscription};
+use crate::games::Game;
+use crate::games::server::Server;
+use crate::global::Global;
+use crate::http::error::{ApiError, ApiErrorCode};
+use crate::http::routes::codes::ServerQuery;
+use crate::rewards::Reward;
+
+/// Source name stored on codes added through the admin API.
+const MANUAL_SOURCE: &str = "manual";
+
+pub fn routes(global: &Arc<Global>) -> Router<Arc<Global>> {
+    Router::new()
+        .route("/:game/codes", post(create_code))
+        .route("/:game/codes/:code", patch(update_code).delete(delete_code))
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/:id",
+            patch(update_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/:id/deliveries", get(list_deliveries))
+        .route_layer(axum::middleware::from_fn_with_state(
+            global.clone(),
+            require_token,
+        ))
+}
+
+/// Reject requests without `Authorization: Bearer <api.admin_token>`.
+async fn require_token(
+    State(global): State<Arc<Global>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let expected = global.config.api.admin_token.as_bytes();
+    if expected.is_empty() {
+        return Err(ApiError::not_found(
+            ApiErrorCode::NOT_CONFIGURED,
+            "admin API is not configured",
+        ));
+    }
+
+    let provided = req
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::as_bytes)
+        .unwrap_or_default();
+
+    if !constant_time_eq(provided, expected) {
+        return Err(ApiError::unauthorized(
+            ApiErrorCode::UNAUTHORIZED,
+            "missing or invalid admin token",
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CreateCodeRequest {
+    code: String,
+    #[serde(default)]
+    rewards: Vec<String>,
+    #[serde(default = "default_true")]
+    active: bool,
+    #[serde(default)]
+    pinned: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct UpdateCodeRequest {
+    active: Option<bool>,
+    rewards: Option<Vec<String>>,
+    pinned: Option<bool>,
+    /// Recorded in the code's history.
+    reason: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct AdminCodeResponse {
+    #[serde(flatten)]
+    code: RedemptionCodeResponse,
+    active: bool,
+    pinned: bool,
+}
+
+impl From<RedemptionCode> for AdminCodeResponse {
+    fn from(code: RedemptionCode) -> Self {
+        let active = code.active;
+        let pinned = code.pinned;
+        Self {
+            code: code.into(),
+            active,
+            pinned,
+        }
+    }
+}
+
+fn resolve_game(slug: &str) -> Result<Game, ApiError> {
+    Game::from_slug(slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))
+}
+
+fn database_error(e: anyhow::Error) -> ApiError {
+    tracing::error!(error = %e, "admin database operation failed");
+    ApiError::internal_server_error(ApiErrorCode::DATABASE_ERROR, "database operation failed")
+}
+
+async fn record_override(global: &Global, game: Game, server: Server, code: &str, message: String) {
+    let mut event = CodeEvent::new(game, server, code, CodeEventKind::ManualOverride);
+    event.message = Some(message);
+    event.record(&global.db).await;
+}
+
+/// POST /admin/:game/codes
+///
+/// Adds a code by hand. `?server=cn` adds it to the CN codes.
+#[tracing::instrument(skip(global))]
+async fn create_code(
+    State(global): State<Arc<Global>>,
+    Path(game_slug): Path<String>,
+    Query(query): Query<ServerQuery>,
+    Json(body): Json<CreateCodeRequest>,
+) -> Result<(StatusCode, Json<AdminCodeResponse>), ApiError> {
+    let game = resolve_game(&game_slug)?;
+    let server = query.resolve(game)?;
+
+    let code = body.code.trim().to_uppercase();
+    if code.is_empty() {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_BODY,
+            "code must not be empty",
+        ));
+    }
+
+    if RedemptionCode::exists(&global.db, game, server, &code)
+        .await
+        .map_err(database_error)?
+    {
+        return Err(ApiError::conflict(
+            ApiErrorCode::CODE_EXISTS,
+            "code already exists",
+        ));
+    }
+
+    let reward_items = Reward::parse_all(game, &body.rewards);
+    let record = RedemptionCode {
+        code: code.clone(),
+        active: body.active,
+        date: bson::DateTime::now(),
+        rewards: reward_items.iter().map(ToString::to_string).collect(),
+        reward_items,
+        source: MANUAL_SOURCE.to_string(),
+        sources: Vec::new(),
+        published_at: None,
+        expires_at: None,
+        regions: server.regions(),
+        pinned: body.pinned,
+        pending: false,
+    };
+
+    RedemptionCode::collection(&global.db, game, server)
+        .insert_one(&record)
+        .await
+        .map_err(|e| database_error(e.into()))?;
+
+    record_override(
+        &global,
+        game,
+        server,
+        &code,
+        format!("added (active={}, pinned={})", record.active, record.pinned),
+    )
+    .await;
+    global.invalidate_codes_cache(game).await;
+
+    Ok((StatusCode::CREATED, Json(record.into())))
+}
+
+/// PATCH /admin/:game/codes/:code
+///
+/// Forces the active flag, replaces the rewards, or pins/unpins a code.
+#[tracing::instrument(skip(global))]
+async fn update_code(
+    State(global): State<Arc<Global>>,
+    Path((game_slug, code)): Path<(String, String)>,
+    Query(query): Query<ServerQuery>,
+    Json(body): Json<UpdateCodeRequest>,
+) -> Result<Json<AdminCodeResponse>, ApiError> {
+    let game = resolve_game(&game_slug)?;
+    let server = query.resolve(game)?;
+    let code = code.to_uppercase();
+
+    let mut update = doc! {};
+    let mut changes = Vec::new();
+    if let Some(active) = body.active {
+        // Setting the flag by hand also settles a code still waiting for
+        // its discovery validation.
+        update.insert("active", active);
+        update.insert("pending", false);
+        changes.push(format!("active={active}"));
+    }
+    if let Some(rewards) = &body.rewards {
+        let reward_items = Reward::parse_all(game, rewards);
+        let display: Vec<String> = reward_items.iter().map(ToString::to_string).collect();
+        update.insert("rewards", &display);
+        update.insert(
+            "reward_items",
+            bson::to_bson(&reward_items).map_err(|e| database_error(e.into()))?,
+        );
+        changes.push(format!("rewards=[{}]", display.join(", ")));
+    }
+    if let Some(pinned) = body.pinned {
+        update.insert("pinned", pinned);
+        changes.push(format!("pinned={pinned}"));
+    }
+
+    if changes.is_empty() {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_BODY,
+            "nothing to update",
+        ));
+    }
+
+    let updated = RedemptionCode::admin_update(&global.db, game, server, &code, update)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_CODE, "unknown code"))?;
+
+    let mut message = changes.join(", ");
+    if let Some(reason) = body.reason {
+        message = format!("{message}: {reason}");
+    }
+    record_override(&global, game, server, &code, message).await;
+    global.invalidate_codes_cache(game).await;
+
+    Ok(Json(updated.into()))
+}
+
+/// DELETE /admin/:game/codes/:code
+///
+/// Removes a code. Its history is kept.
+#[tracing::instrument(skip(global))]
+async fn delete_code(
+    State(global): State<Arc<Global>>,
+    Path((game_slug, code)): Path<(String, String)>,
+    Query(query): Query<ServerQuery>,
+) -> Result<StatusCode, ApiError> {
+    let game = resolve_game(&game_slug)?;
+    let server = query.resolve(game)?;
+    let code = code.to_uppercase();
+
+    if !RedemptionCode::delete(&global.db, game, server, &code)
+        .await
+        .map_err(database_error)?
+    {
+        return Err(ApiError::not_found(
+            ApiErrorCode::UNKNOWN_CODE,
+            "unknown code",
+        ));
+    }
+
+    record_override(&global, game, server, &code, "deleted".to_string()).await;
+    global.invalidate_codes_cache(game).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CreateWebhookRequest {
+    url: String,
+    #[serde(default)]
+    games: Vec<Game>,
+    #[serde(default)]
+    events: Vec<WebhookEvent>,
+    secret: Option<String>,
+    #[serde(default = "default_true")]
+    enabled: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct UpdateWebhookRequest {
+    url: Option<String>,
+    games: Option<Vec<Game>>,
+    events: Option<Vec<WebhookEvent>>,
+    /// An empty string removes the secret.
+    secret: Option<String>,
+    enabled: Option<bool>,
+}
+
+#[derive(serde::Serialize)]
+struct WebhookResponse {
+    id: String,
+    url: String,
+    games: Vec<Game>,
+    events: Vec<WebhookEvent>,
+    /// Whether deliveries carry an `X-Webhook-Signature`. The secret itself
+    /// is never returned.
+    signed: bool,
+    enabled: bool,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id.map(|id| id.to_hex()).unwrap_or_default(),
+            url: subscription.url,
+            games: subscription.games,
+            events: subscription.events,
+            signed: subscription.secret.is_some_and(|s| !s.is_empty()),
+            enabled: subscription.enabled,
+            created_at: subscription.created_at.to_chrono(),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct DeliveryResponse {
+    id: String,
+    game: Game,
+    event: WebhookEvent,
+    status: DeliveryStatus,
+    attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+    /// When the next attempt is due, for pending deliveries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    payload: serde_json::Value,
+}
+
+impl From<WebhookDelivery> for DeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id.map(|id| id.to_hex()).unwrap_or_default(),
+            game: delivery.game,
+            event: delivery.event,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            next_attempt_at: (delivery.status == DeliveryStatus::Pending)
+                .then(|| delivery.run_after.to_chrono()),
+            created_at: delivery.created_at.to_chrono(),
+            updated_at: delivery.updated_at.to_chrono(),
+            payload: serde_json::from_str(&delivery.body).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct DeliveriesQuery {
+    limit: Option<i64>,
+}
+
+fn validate_url(url: &str) -> Result<String, ApiError> {
+    let url = url.trim();
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(url.to_string()),
+        _ => Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_BODY,
+            "url must be an http(s) URL",
+        )),
+    }
+}
+
+fn parse_webhook_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id)
+        .map_err(|_| ApiError::not_found(ApiErrorCode::UNKNOWN_WEBHOOK, "unknown webhook"))
+}
+
+/// GET /admin/webhooks
+#[tracing::instrument(skip(global))]
+async fn list_webhooks(
+    State(global): State<Arc<Global>>,
+) -> Result<Json<Vec<WebhookResponse>>, ApiError> {
+    let subscriptions = WebhookSubscription::find_all(&global.db)
+        .await
+        .map_err(database_error)?;
+
+    Ok(Json(subscriptions.into_iter().map(Into::into).collect()))
+}
+
+/// POST /admin/webhooks
+///
+/// Subscribes a URL to code notifications.
+#[tracing::instrument(skip(global, body), fields(url = body.url))]
+async fn create_webhook(
+    State(global): State<Arc<Global>>,
+    Json(body): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<WebhookResponse>), ApiError> {
+    let mut subscription = WebhookSubscription {
+        id: None,
+        url: validate_url(&body.url)?,
+        games: body.games,
+        events: body.events,
+        secret: body.secret.filter(|s| !s.is_empty()),
+        enabled: body.enabled,
+        created_at: bson::DateTime::now(),
+    };
+
+    let result = WebhookSubscription::collection(&global.db)
+        .insert_one(&subscription)
+        .await
+        .map_err(|e| database_error(e.into()))?;
+    subscription.id = result.inserted_id.as_object_id();
+
+    tracing::info!(url = subscription.url, "webhook subscription added");
+
+    Ok((StatusCode::CREATED, Json(subscription.into())))
+}
+
+/// PATCH /admin/webhooks/:id
+#[tracing::instrument(skip(global, body))]
+async fn update_webhook(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateWebhookRequest>,
+) -> Result<Json<WebhookResponse>, ApiError> {
+    let id = parse_webhook_id(&id)?;
+
+    let mut update = doc! {};
+    if let Some(url) = &body.url {
+        update.insert("url", validate_url(url)?);
+    }
+    if let Some(games) = &body.games {
+        update.insert(
+            "games",
+            bson::to_bson(games).map_err(|e| database_error(e.into()))?,
+        );
+    }
+    if let Some(events) = &body.events {
+        update.insert(
+            "events",
+            bson::to_bson(events).map_err(|e| database_error(e.into()))?,
+        );
+    }
+    if let Some(secret) = &body.secret {
+        let secret = Some(secret.as_str()).filter(|s| !s.is_empty());
+        update.insert("secret", secret);
+    }
+    if let Some(enabled) = body.enabled {
+        update.insert("enabled", enabled);
+    }
+
+    if update.is_empty() {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_BODY,
+            "nothing to update",
+        ));
+    }
+
+    let updated = WebhookSubscription::update(&global.db, id, update)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_WEBHOOK, "unknown webhook"))?;
+
+    Ok(Json(updated.into()))
+}
+
+/// DELETE /admin/webhooks/:id
+///
+/// Removes a subscription. Its delivery log is kept until it ages out.
+#[tracing::instrument(skip(global))]
+async fn delete_webhook(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let id = parse_webhook_id(&id)?;
+
+    if !WebhookSubscription::delete(&global.db, id)
+        .await
+        .map_err(database_error)?
+    {
+        return Err(ApiError::not_found(
+            ApiErrorCode::UNKNOWN_WEBHOOK,
+            "unknown webhook",
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /admin/webhooks/:id/deliveries
+///
+/// The subscription's most recent deliveries, newest first.
+#[tracing::instrument(skip(global))]
+async fn list_deliveries(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<String>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<DeliveryResponse>>, ApiError> {
+    let id = parse_webhook_id(&id)?;
+
+    if WebhookSubscription::find_by_id(&global.db, id)
+        .await
+        .map_err(database_error)?
+        .is_none()
+    {
+        return Err(ApiError::not_found(
+            ApiErrorCode::UNKNOWN_WEBHOOK,
+            "unknown webhook",
+        ));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let deliveries = WebhookDelivery::find_recent(&global.db, id, limit)
+        .await
+        .map_err(database_error)?;
+
+    Ok(Json(deliveries.into_iter().map(Into::into).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn accepts_only_http_webhook_urls() {
+        assert!(validate_url("https://example.com/hook").is_ok());
+        assert!(validate_url("http://10.0.0.2:8080/hook").is_ok());
+        assert!(validate_url("ftp://example.com/hook").is_err());
+        assert!(validate_url("not a url").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+/// A blessing, buff or disorder active during an endgame challenge.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) struct ChallengeBuff {
+    pub name: String,
+    pub description: String,
+    pub icon: Option<String>,
+}
+
+/// An enemy from an endgame challenge lineup.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) struct ChallengeEnemy {
+    pub name: String,
+    pub icon: Option<String>,
+    pub level: Option<u32>,
+    pub description: Option<String>,
+}
+
+/// Buffs and enemies of one endgame challenge period, as far as HoYoLab
+/// returns them for the configured account.
+#[derive(Debug, Default)]
+pub(super) struct ChallengeDetail {
+    pub buffs: Vec<ChallengeBuff>,
+    pub enemies: Vec<ChallengeEnemy>,
+}
+
+impl ChallengeDetail {
+    /// Add a buff unless one with the same name is already listed.
+    pub fn push_buff(&mut self, buff: ChallengeBuff) {
+        if !buff.name.is_empty() && !self.buffs.iter().any(|b| b.name == buff.name) {
+            self.buffs.push(buff);
+        }
+    }
+
+    /// Add an enemy unless one with the same name is already listed.
+    pub fn push_enemy(&mut self, enemy: ChallengeEnemy) {
+        if !enemy.name.is_empty() && !self.enemies.iter().any(|e| e.name == enemy.name) {
+            self.enemies.push(enemy);
+        }
+    }
+}
+
+/// Plain-text summary of the buffs and enemies for the unified calendar.
+pub(super) fn describe(buffs: &[ChallengeBuff], enemies: &[ChallengeEnemy]) -> Option<String> {
+    let mut lines: Vec<String> = buffs
+        .iter()
+        .map(|buff| {
+            if buff.description.is_empty() {
+                buff.name.clone()
+            } else {
+                format!("{}: {}", buff.name, buff.description)
+            }
+        })
+        .collect();
+    if !enemies.is_empty() {
+        let names: Vec<&str> = enemies.iter().map(|e| e.name.as_str()).collect();
+        lines.push(format!("Enemies: {}", names.join(", ")));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_buffs_and_enemies() {
+        let mut detail = ChallengeDetail::default();
+        assert_eq!(describe(&detail.buffs, &detail.enemies), None);
+
+        for name in ["Ley Line Disorder", "Ley Line Disorder"] {
+            detail.push_buff(ChallengeBuff {
+                name: name.to_string(),
+                description: "Pyro DMG +75%".to_string(),
+                icon: None,
+            });
+        }
+        for name in ["Lord of the Hidden Depths", "", "Gluttonous Yumkasaur"] {
+            detail.push_enemy(ChallengeEnemy {
+                name: name.to_string(),
+                icon: None,
+                level: Some(95),
+                description: None,
+            });
+        }
+
+        assert_eq!(
+            describe(&detail.buffs, &detail.enemies).as_deref(),
+            Some(
+                "Ley Line Disorder: Pyro DMG +75%\nEnemies: Lord of the Hidden Depths, Gluttonous Yumkasaur"
+            )
+        );
+    }
+}
@@ -5,29 +5,15 @@ use axum::body::{Body, Bytes};
 use axum::extract::{Query, State};
 use axum::http::Response;
 
-use crate::games::genshin;
+use crate::games::{Game, genshin};
 use crate::global::Global;
+use crate::hoyolab::{DsSalt, HeaderPreset, HoyolabRequest};
 use crate::http::error::{ApiError, ApiErrorCode};
 use crate::http::routes::json_response;
 
-use super::{LangQuery, cookie_with_lang, random_r, resolve_lang, try_fetch_fandom_images};
-
-const DS_SALT: &str = "xV8v4Qu54lUKrEYFZkJhB8cuOh9Asafs";
-
-fn generate_ds(body: &str) -> String {
-    let t = chrono::Utc::now().timestamp();
-    let r = random_r();
-    let raw = format!("salt={DS_SALT}&t={t}&r={r}&b={body}&q=");
-    let hash = format!("{:x}", md5::compute(raw.as_bytes()));
-    format!("{t},{r},{hash}")
-}
-
-#[derive(serde::Deserialize)]
-struct HyvResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HyvCalendarData>,
-}
+use super::endgame::{ChallengeBuff, ChallengeDetail, ChallengeEnemy, describe};
+use super::unified::{CalendarEntry, EntryKind, FeaturedItem, FeaturedKind, banner_image};
+use super::{LangQuery, calendar_snapshot, check_server, resolve_lang, try_fetch_fandom_images};
 
 #[derive(serde::Deserialize)]
 struct HyvCalendarData {
@@ -88,14 +74,110 @@ struct HyvWeapon {
     rarity: u8,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Deserialize)]
+struct HyvSpiralAbyss {
+    schedule_id: u64,
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    floors: Vec<HyvAbyssFloor>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvAbyssFloor {
+    #[serde(default)]
+    ley_line_disorder: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvSchedule {
+    schedule_id: u64,
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    is_now: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterData {
+    #[serde(default)]
+    data: Vec<HyvTheaterSeason>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterSeason {
+    schedule: HyvSchedule,
+    detail: Option<HyvTheaterDetail>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterDetail {
+    #[serde(default)]
+    rounds_data: Vec<HyvTheaterRound>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterRound {
+    #[serde(default)]
+    buffs: Vec<HyvTheaterBuff>,
+    #[serde(default)]
+    enemies: Vec<HyvTheaterEnemy>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterBuff {
+    name: String,
+    desc: String,
+    icon: String,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvTheaterEnemy {
+    name: String,
+    icon: String,
+    level: u32,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvOnslaughtData {
+    #[serde(default)]
+    data: Vec<HyvOnslaughtSeason>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvOnslaughtSeason {
+    schedule: HyvSchedule,
+    single: Option<HyvOnslaughtRecord>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvOnslaughtRecord {
+    #[serde(default)]
+    challenge: Vec<HyvOnslaughtChallenge>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvOnslaughtChallenge {
+    monster: HyvOnslaughtMonster,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvOnslaughtMonster {
+    name: String,
+    icon: String,
+    level: u32,
+    #[serde(default)]
+    desc: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
 struct CalendarResponse {
     events: Vec<Event>,
     banners: Vec<Banner>,
     challenges: Vec<Challenge>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Reward {
     id: u64,
     name: String,
@@ -104,7 +186,7 @@ struct Reward {
     amount: u64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Event {
     id: u64,
     name: String,
@@ -117,7 +199,7 @@ struct Event {
     special_reward: Option<Reward>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Banner {
     id: u64,
     name: String,
@@ -128,7 +210,7 @@ struct Banner {
     end_time: i64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Character {
     id: u64,
     name: String,
@@ -137,7 +219,7 @@ struct Character {
     rarity: u8,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Weapon {
     id: u64,
     name: String,
@@ -145,7 +227,7 @@ struct Weapon {
     rarity: u8,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Challenge {
     id: u64,
     name: String,
@@ -154,6 +236,17 @@ struct Challenge {
     end_time: i64,
     rewards: Vec<Reward>,
     special_reward: Option<Reward>,
+    buffs: Vec<ChallengeBuff>,
+    enemies: Vec<ChallengeEnemy>,
+}
+
+/// The current period of one endgame mode from its record endpoint.
+struct EndgamePeriod {
+    id: u64,
+    type_name: &'static str,
+    start_time: i64,
+    end_time: i64,
+    detail: ChallengeDetail,
 }
 
 fn map_rewards(reward_list: Vec<HyvReward>) -> (Vec<Reward>, Option<Reward>) {
@@ -209,6 +302,168 @@ fn transform_challenge(act: HyvActivity) -> Challenge {
         end_time,
         rewards,
         special_reward,
+        buffs: Vec::new(),
+        enemies: Vec::new(),
+    }
+}
+
+fn abyss_period(abyss: HyvSpiralAbyss) -> EndgamePeriod {
+    let mut detail = ChallengeDetail::default();
+    for disorder in abyss.floors.into_iter().flat_map(|f| f.ley_line_disorder) {
+        detail.push_buff(ChallengeBuff {
+            name: disorder,
+            description: String::new(),
+            icon: None,
+        });
+    }
+
+    EndgamePeriod {
+        id: abyss.schedule_id,
+        type_name: "spiral_abyss",
+        start_time: abyss.start_time.parse().unwrap_or(0),
+        end_time: abyss.end_time.parse().unwrap_or(0),
+        detail,
+    }
+}
+
+fn schedule_period(
+    schedule: HyvSchedule,
+    type_name: &'static str,
+    detail: ChallengeDetail,
+) -> EndgamePeriod {
+    EndgamePeriod {
+        id: schedule.schedule_id,
+        type_name,
+        start_time: schedule.start_time.parse().unwrap_or(0),
+        end_time: schedule.end_time.parse().unwrap_or(0),
+        detail,
+    }
+}
+
+/// The running season, or the latest one between seasons.
+fn current_season<T>(seasons: Vec<T>, schedule: impl Fn(&T) -> &HyvSchedule) -> Option<T> {
+    let index = seasons.iter().position(|s| schedule(s).is_now).unwrap_or(0);
+    seasons.into_iter().nth(index)
+}
+
+fn theater_period(theater: HyvTheaterData) -> Option<EndgamePeriod> {
+    let season = current_season(theater.data, |s| &s.schedule)?;
+    let mut detail = ChallengeDetail::default();
+    for round in season.detail.into_iter().flat_map(|d| d.rounds_data) {
+        for buff in round.buffs {
+            detail.push_buff(ChallengeBuff {
+                name: buff.name,
+                description: buff.desc,
+                icon: Some(buff.icon).filter(|i| !i.is_empty()),
+            });
+        }
+        for enemy in round.enemies {
+            detail.push_enemy(ChallengeEnemy {
+                name: enemy.name,
+                icon: Some(enemy.icon).filter(|i| !i.is_empty()),
+                level: Some(enemy.level),
+                description: None,
+            });
+        }
+    }
+    Some(schedule_period(
+        season.schedule,
+        "imaginarium_theater",
+        detail,
+    ))
+}
+
+fn onslaught_period(onslaught: HyvOnslaughtData) -> Option<EndgamePeriod> {
+    let season = current_season(onslaught.data, |s| &s.schedule)?;
+    let mut detail = ChallengeDetail::default();
+    for challenge in season.single.into_iter().flat_map(|r| r.challenge) {
+        let monster = challenge.monster;
+        detail.push_enemy(ChallengeEnemy {
+            name: monster.name,
+            icon: Some(monster.icon).filter(|i| !i.is_empty()),
+            level: Some(monster.level),
+            description: Some(monster.desc.join("\n")).filter(|d| !d.is_empty()),
+        });
+    }
+    Some(schedule_period(
+        season.schedule,
+        "stygian_onslaught",
+        detail,
+    ))
+}
+
+fn endgame_name(type_name: &str, lang: &str) -> &'static str {
+    let names = match lang {
+        "zh-cn" => ["深境螺旋", "幻想真境剧诗", "幽境危战"],
+        "zh-tw" => ["深境螺旋", "幻想真境劇詩", "幽境危戰"],
+        "ja-jp" => ["深境螺旋", "幻想シアター", "幽境の激戦"],
+        _ => ["Spiral Abyss", "Imaginarium Theater", "Stygian Onslaught"],
+    };
+    match type_name {
+        "spiral_abyss" => names[0],
+        "imaginarium_theater" => names[1],
+        _ => names[2],
+    }
+}
+
+/// Calendar challenge types that list each endgame mode.
+const CALENDAR_MODES: &[(&str, &str)] = &[
+    ("Abyss", "spiral_abyss"),
+    ("Role Combat", "imaginarium_theater"),
+    ("RoleCombat", "imaginarium_theater"),
+    ("Hard Challenge", "stygian_onslaught"),
+    ("HardChallenge", "stygian_onslaught"),
+];
+
+/// Whether a calendar challenge lists the endgame mode `type_name`, either
+/// by its upstream type or by the mode's localized name.
+fn same_mode(challenge: &Challenge, type_name: &str, lang: &str) -> bool {
+    challenge.type_name == type_name
+        || CALENDAR_MODES
+            .iter()
+            .any(|&(calendar, mode)| mode == type_name && challenge.type_name == calendar)
+        || challenge.name == endgame_name(type_name, lang)
+}
+
+/// Ids of challenges added for an endgame mode are namespaced by the mode,
+/// since schedule ids are only unique within one mode.
+fn challenge_entry_id(challenge: &Challenge) -> String {
+    match challenge.type_name.as_str() {
+        mode @ ("spiral_abyss" | "imaginarium_theater" | "stygian_onslaught") => {
+            format!("{mode}-{}", challenge.id)
+        }
+        _ => challenge.id.to_string(),
+    }
+}
+
+/// Attach endgame details to the calendar challenge of the same mode and
+/// period, or add a dedicated challenge when the calendar does not list the mode.
+fn merge_endgame(challenges: &mut Vec<Challenge>, periods: Vec<EndgamePeriod>, lang: &str) {
+    for period in periods {
+        let existing = challenges.iter_mut().find(|c| {
+            same_mode(c, period.type_name, lang)
+                && c.start_time == period.start_time
+                && c.end_time == period.end_time
+                && c.buffs.is_empty()
+                && c.enemies.is_empty()
+        });
+        match existing {
+            Some(challenge) => {
+                challenge.buffs = period.detail.buffs;
+                challenge.enemies = period.detail.enemies;
+            }
+            None => challenges.push(Challenge {
+                id: period.id,
+                name: endgame_name(period.type_name, lang).to_string(),
+                type_name: period.type_name.to_string(),
+                start_time: period.start_time,
+                end_time: period.end_time,
+                rewards: Vec::new(),
+                special_reward: None,
+                buffs: period.detail.buffs,
+                enemies: period.detail.enemies,
+            }),
+        }
     }
 }
 
@@ -289,7 +544,12 @@ fn map_activity_images(
         .collect()
 }
 
-fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -> CalendarResponse {
+fn transform_calendar(
+    data: HyvCalendarData,
+    image_map: &HashMap<u64, String>,
+    endgame: Vec<EndgamePeriod>,
+    lang: &str,
+) -> CalendarResponse {
     let events = data
         .act_list
         .into_iter()
@@ -299,11 +559,12 @@ fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -
         })
         .collect();
 
-    let challenges = data
+    let mut challenges = data
         .fixed_act_list
         .into_iter()
         .map(transform_challenge)
         .collect();
+    merge_endgame(&mut challenges, endgame, lang);
 
     let banners = data
         .avatar_card_pool_list
@@ -320,59 +581,96 @@ fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -
     }
 }
 
-fn calendar_request(
-    client: &reqwest::Client,
+fn calendar_request<'a>(
+    client: &'a reqwest::Client,
     uid: &str,
     region: &str,
     cookie: &str,
     lang: &str,
-) -> reqwest::RequestBuilder {
+) -> HoyolabRequest<'a> {
     let body = serde_json::json!({
         "role_id": uid,
         "server": region,
     });
-    let body_str = body.to_string();
-
-    client
-        .post(genshin::CALENDAR_API)
-        .header("Cookie", cookie)
-        .header("DS", generate_ds(&body_str))
-        .header("x-rpc-app_version", "1.5.0")
-        .header("x-rpc-client_type", "5")
-        .header("x-rpc-language", lang)
-        .json(&body)
-}
-
-async fn fetch_calendar_data(
-    request: reqwest::RequestBuilder,
-    request_lang: &str,
-) -> Result<HyvCalendarData, ApiError> {
-    let response = request.send().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to fetch genshin calendar");
-        ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to fetch calendar")
-    })?;
-    let response: HyvResponse = response.json().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to parse genshin calendar response");
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "failed to parse calendar response",
-        )
-    })?;
-
-    if response.retcode != 0 {
-        tracing::error!(retcode = response.retcode, message = %response.message, request_lang, "hoyoverse genshin calendar API error");
-        return Err(ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned an error",
-        ));
-    }
 
-    response.data.ok_or_else(|| {
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned no data",
-        )
-    })
+    HoyolabRequest::post(client, "genshin_calendar", genshin::CALENDAR_API, body)
+        .localized_cookie(cookie, lang)
+        .preset(HeaderPreset::GameRecord, lang)
+        .sign(DsSalt::X4)
+}
+
+fn record_request<'a>(
+    client: &'a reqwest::Client,
+    endpoint: &'static str,
+    url: &'a str,
+    params: &[(&str, &str)],
+    cookie: &str,
+    lang: &str,
+) -> HoyolabRequest<'a> {
+    HoyolabRequest::get(client, endpoint, url)
+        .query(params)
+        .localized_cookie(cookie, lang)
+        .preset(HeaderPreset::GameRecord, lang)
+        .sign(DsSalt::X4)
+}
+
+/// Fetch the current Spiral Abyss, Imaginarium Theater and Stygian Onslaught
+/// periods. Modes that cannot be fetched are left out.
+async fn fetch_endgame_periods(
+    client: &reqwest::Client,
+    uid: &str,
+    region: &str,
+    cookie: &str,
+    lang: &str,
+) -> Vec<EndgamePeriod> {
+    let abyss_request = record_request(
+        client,
+        "genshin_spiral_abyss",
+        genshin::SPIRAL_ABYSS_API,
+        &[("role_id", uid), ("schedule_type", "1"), ("server", region)],
+        cookie,
+        lang,
+    );
+    let theater_request = record_request(
+        client,
+        "genshin_imaginarium_theater",
+        genshin::IMAGINARIUM_THEATER_API,
+        &[
+            ("need_detail", "true"),
+            ("role_id", uid),
+            ("server", region),
+        ],
+        cookie,
+        lang,
+    );
+    let onslaught_request = record_request(
+        client,
+        "genshin_stygian_onslaught",
+        genshin::STYGIAN_ONSLAUGHT_API,
+        &[
+            ("need_detail", "true"),
+            ("role_id", uid),
+            ("server", region),
+        ],
+        cookie,
+        lang,
+    );
+
+    let (abyss, theater, onslaught) = tokio::join!(
+        abyss_request.send::<HyvSpiralAbyss>(),
+        theater_request.send::<HyvTheaterData>(),
+        onslaught_request.send::<HyvOnslaughtData>(),
+    );
+
+    [
+        abyss.ok().map(abyss_period),
+        theater.ok().and_then(theater_period),
+        onslaught.ok().and_then(onslaught_period),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|period| period.start_time > 0)
+    .collect()
 }
 
 /// GET /genshin/calendar
@@ -383,110 +681,187 @@ pub(super) async fn get_genshin_calendar(
     Query(query): Query<LangQuery>,
     State(global): State<Arc<Global>>,
 ) -> Result<Response<Body>, ApiError> {
+    check_server(query.server.as_deref())?;
     let lang = resolve_lang(query.lang)?;
-    let cache_key = format!("/mihoyo/genshin/calendar/{lang}");
-
-    let bytes = global
-        .response_cache
-        .get_or_try_insert(cache_key, async {
-            let game_config = global
-                .config
-                .validator
-                .game_config(crate::games::Game::Genshin)
-                .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
-                .ok_or_else(|| {
+    let snapshot = calendar_snapshot(&global, Game::Genshin, lang).await?;
+    Ok(json_response(snapshot.json()))
+}
+
+/// Fetch the calendar JSON for `lang` from HoYoLab.
+pub(super) async fn fetch_calendar(
+    global: &Arc<Global>,
+    lang: &'static str,
+) -> Result<Bytes, ApiError> {
+    let game_config = global
+        .config
+        .validator
+        .game_config(Game::Genshin)
+        .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
+        .ok_or_else(|| {
+            ApiError::internal_server_error(
+                ApiErrorCode::NOT_CONFIGURED,
+                "genshin calendar credentials not configured",
+            )
+        })?;
+
+    let data: HyvCalendarData = calendar_request(
+        &global.http_client,
+        &game_config.uid,
+        &game_config.region,
+        &game_config.cookie,
+        lang,
+    )
+    .send()
+    .await?;
+    let endgame = fetch_endgame_periods(
+        &global.http_client,
+        &game_config.uid,
+        &game_config.region,
+        &game_config.cookie,
+        lang,
+    )
+    .await;
+    let eligible_ids = eligible_activity_ids(&data);
+
+    let canonical_activities = if lang == "en-us" {
+        canonical_activities(&data, &eligible_ids)
+    } else {
+        let english_request = calendar_request(
+            &global.http_client,
+            &game_config.uid,
+            &game_config.region,
+            &game_config.cookie,
+            "en-us",
+        );
+        match english_request.send::<HyvCalendarData>().await {
+            Ok(english_data) => canonical_activities(&english_data, &eligible_ids),
+            Err(error) => {
+                tracing::warn!(error = ?error, "failed to fetch canonical genshin activity names");
+                Vec::new()
+            }
+        }
+    };
+
+    let image_map = if canonical_activities.is_empty() {
+        HashMap::new()
+    } else {
+        let names: Vec<String> = canonical_activities
+            .iter()
+            .map(|(_, name)| name.clone())
+            .collect();
+        let cache_key = fandom_cache_key(&canonical_activities);
+        let fandom_bytes = global
+            .fandom_image_cache
+            .get_or_try_insert(cache_key, async {
+                let map = try_fetch_fandom_images(
+                    &global.http_client,
+                    "https://genshin-impact.fandom.com/api.php",
+                    "File:",
+                    ".png",
+                    &names,
+                )
+                .await
+                .map_err(|error| {
+                    tracing::warn!(error = %error, "failed to fetch genshin fandom images");
                     ApiError::internal_server_error(
-                        ApiErrorCode::NOT_CONFIGURED,
-                        "genshin calendar credentials not configured",
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to fetch fandom images",
                     )
                 })?;
-
-            let localized_cookie = cookie_with_lang(&game_config.cookie, lang);
-            let localized_request = calendar_request(
-                &global.http_client,
-                &game_config.uid,
-                &game_config.region,
-                &localized_cookie,
-                lang,
-            );
-            let data = fetch_calendar_data(localized_request, lang).await?;
-            let eligible_ids = eligible_activity_ids(&data);
-
-            let canonical_activities = if lang == "en-us" {
-                canonical_activities(&data, &eligible_ids)
-            } else {
-                let english_cookie = cookie_with_lang(&game_config.cookie, "en-us");
-                let english_request = calendar_request(
-                    &global.http_client,
-                    &game_config.uid,
-                    &game_config.region,
-                    &english_cookie,
-                    "en-us",
-                );
-                match fetch_calendar_data(english_request, "en-us").await {
-                    Ok(english_data) => canonical_activities(&english_data, &eligible_ids),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "failed to fetch canonical genshin activity names");
-                        Vec::new()
-                    }
-                }
-            };
-
-            let image_map = if canonical_activities.is_empty() {
+                let bytes = serde_json::to_vec(&map).map_err(|error| {
+                    tracing::warn!(error = %error, "failed to serialize genshin fandom images");
+                    ApiError::internal_server_error(
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to cache fandom images",
+                    )
+                })?;
+                Ok(Bytes::from(bytes))
+            })
+            .await;
+        let fandom_images: HashMap<String, String> = match fandom_bytes {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(error) => {
+                tracing::warn!(error = ?error, "genshin fandom images unavailable");
                 HashMap::new()
-            } else {
-                let names: Vec<String> = canonical_activities
-                    .iter()
-                    .map(|(_, name)| name.clone())
-                    .collect();
-                let cache_key = fandom_cache_key(&canonical_activities);
-                let fandom_bytes = global
-                    .fandom_image_cache
-                    .get_or_try_insert(cache_key, async {
-                        let map = try_fetch_fandom_images(
-                            &global.http_client,
-                            "https://genshin-impact.fandom.com/api.php",
-                            "File:",
-                            ".png",
-                            &names,
-                        )
-                        .await
-                        .map_err(|error| {
-                            tracing::warn!(error = %error, "failed to fetch genshin fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to fetch fandom images",
-                            )
-                        })?;
-                        let bytes = serde_json::to_vec(&map).map_err(|error| {
-                            tracing::warn!(error = %error, "failed to serialize genshin fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to cache fandom images",
-                            )
-                        })?;
-                        Ok(Bytes::from(bytes))
-                    })
-                    .await;
-                let fandom_images: HashMap<String, String> = match fandom_bytes {
-                    Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "genshin fandom images unavailable");
-                        HashMap::new()
-                    }
-                };
-                map_activity_images(&canonical_activities, &fandom_images)
-            };
-
-            let calendar = transform_calendar(data, &image_map);
-            Ok(Bytes::from(
-                serde_json::to_vec(&calendar)
-                    .expect("CalendarResponse is always serializable"),
-            ))
-        })
-        .await?;
+            }
+        };
+        map_activity_images(&canonical_activities, &fandom_images)
+    };
+
+    let calendar = transform_calendar(data, &image_map, endgame, lang);
+    Ok(Bytes::from(
+        serde_json::to_vec(&calendar).expect("CalendarResponse is always serializable"),
+    ))
+}
+
+/// Map the cached calendar JSON onto the cross-game schema.
+pub(super) fn unified_entries(bytes: &[u8]) -> serde_json::Result<Vec<CalendarEntry>> {
+    let calendar: CalendarResponse = serde_json::from_slice(bytes)?;
+    let game = Game::Genshin;
+
+    let events = calendar.events.into_iter().map(|event| CalendarEntry {
+        kind: EntryKind::Event,
+        game,
+        id: event.id.to_string(),
+        name: event.name,
+        description: Some(event.description).filter(|d| !d.is_empty()),
+        version: None,
+        image: event.image_url,
+        start: Some(event.start_time),
+        end: Some(event.end_time),
+        featured: Vec::new(),
+    });
+
+    let banners = calendar.banners.into_iter().map(|banner| {
+        let featured: Vec<_> = banner
+            .characters
+            .into_iter()
+            .map(|c| FeaturedItem {
+                kind: FeaturedKind::Character,
+                id: c.id,
+                name: c.name,
+                image: c.icon,
+                rarity: c.rarity.to_string(),
+            })
+            .chain(banner.weapons.into_iter().map(|w| FeaturedItem {
+                kind: FeaturedKind::Weapon,
+                id: w.id,
+                name: w.name,
+                image: w.icon,
+                rarity: w.rarity.to_string(),
+            }))
+            .collect();
+        CalendarEntry {
+            kind: EntryKind::Banner,
+            game,
+            id: banner.id.to_string(),
+            name: banner.name,
+            description: None,
+            version: Some(banner.version),
+            image: banner_image(&featured),
+            start: Some(banner.start_time),
+            end: Some(banner.end_time),
+            featured,
+        }
+    });
 
-    Ok(json_response(bytes))
+    let challenges = calendar
+        .challenges
+        .into_iter()
+        .map(|challenge| CalendarEntry {
+            kind: EntryKind::Challenge,
+            game,
+            id: challenge_entry_id(&challenge),
+            description: describe(&challenge.buffs, &challenge.enemies),
+            name: challenge.name,
+            version: None,
+            image: None,
+            start: Some(challenge.start_time),
+            end: Some(challenge.end_time),
+            featured: Vec::new(),
+        });
+
+    Ok(events.chain(banners).chain(challenges).collect())
 }
 
 #[cfg(test)]
@@ -574,4 +949,60 @@ mod tests {
         assert_eq!(fandom_cache_key(&first), fandom_cache_key(&reordered));
         assert_ne!(fandom_cache_key(&first), fandom_cache_key(&changed));
     }
+
+    #[test]
+    fn merges_endgame_periods_into_challenges() {
+        let abyss: HyvSpiralAbyss = serde_json::from_value(serde_json::json!({
+            "schedule_id": 92,
+            "start_time": "1",
+            "end_time": "2",
+            "floors": [
+                { "ley_line_disorder": ["Pyro DMG +75%"] },
+                { "ley_line_disorder": ["Pyro DMG +75%", "Shields are 25% stronger"] },
+            ],
+        }))
+        .unwrap();
+        let onslaught: HyvOnslaughtData = serde_json::from_value(serde_json::json!({
+            "data": [
+                {
+                    "schedule": { "schedule_id": 3, "start_time": "0", "end_time": "0", "is_now": false },
+                    "single": null,
+                },
+                {
+                    "schedule": { "schedule_id": 4, "start_time": "10", "end_time": "20", "is_now": true },
+                    "single": {
+                        "challenge": [{
+                            "monster": { "name": "Lord of the Hidden Depths", "icon": "", "level": 95, "desc": [] },
+                        }],
+                    },
+                },
+            ],
+        }))
+        .unwrap();
+
+        let mut challenges = vec![
+            transform_challenge(activity(6, "Domain Challenge", "Event")),
+            transform_challenge(activity(7, "Spiral Abyss", "Abyss")),
+        ];
+        let periods = vec![abyss_period(abyss), onslaught_period(onslaught).unwrap()];
+        merge_endgame(&mut challenges, periods, "en-us");
+
+        assert_eq!(challenges.len(), 3);
+        assert!(challenges[0].buffs.is_empty());
+        assert_eq!(challenges[1].id, 7);
+        assert_eq!(challenge_entry_id(&challenges[1]), "7");
+        let buffs: Vec<_> = challenges[1]
+            .buffs
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(buffs, ["Pyro DMG +75%", "Shields are 25% stronger"]);
+
+        assert_eq!(challenges[2].id, 4);
+        assert_eq!(challenge_entry_id(&challenges[2]), "stygian_onslaught-4");
+        assert_eq!(challenges[2].name, "Stygian Onslaught");
+        assert_eq!(challenges[2].type_name, "stygian_onslaught");
+        assert_eq!(challenges[2].start_time, 10);
+        assert_eq!(challenges[2].enemies[0].level, Some(95));
+    }
 }
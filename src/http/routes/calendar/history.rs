@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+
+use crate::database::calendar_history::CalendarHistoryEntry;
+use crate::games::Game;
+use crate::global::Global;
+use crate::http::error::{ApiError, ApiErrorCode};
+
+use super::CALENDAR_GAMES;
+use super::unified::FeaturedItem;
+
+#[derive(Debug, serde::Deserialize)]
+pub(super) struct BannerHistoryQuery {
+    character: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub(super) struct BannerHistoryResponse {
+    id: String,
+    name: String,
+    version: Option<String>,
+    image: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+    featured: Vec<FeaturedItem>,
+    first_seen: chrono::DateTime<chrono::Utc>,
+    last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<CalendarHistoryEntry> for BannerHistoryResponse {
+    fn from(entry: CalendarHistoryEntry) -> Self {
+        Self {
+            id: entry.entry_id,
+            name: entry.name,
+            version: entry.version,
+            image: entry.image,
+            start: entry.start,
+            end: entry.end,
+            featured: entry.featured,
+            first_seen: entry.first_seen.to_chrono(),
+            last_seen: entry.last_seen.to_chrono(),
+        }
+    }
+}
+
+/// GET /mihoyo/:game/banners/history
+///
+/// Every stored banner, newest first. `?character=` keeps the banners that
+/// featured that character or weapon.
+#[tracing::instrument(skip(global))]
+pub(super) async fn get_banner_history(
+    State(global): State<Arc<Global>>,
+    Path(game_slug): Path<String>,
+    Query(query): Query<BannerHistoryQuery>,
+) -> Result<Json<Vec<BannerHistoryResponse>>, ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .filter(|game| CALENDAR_GAMES.contains(game))
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+    let character = query
+        .character
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let banners = CalendarHistoryEntry::find_banners(&global.db, game, character, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "failed to query banner history");
+            ApiError::internal_server_error(
+                ApiErrorCode::DATABASE_ERROR,
+                "failed to query banner history",
+            )
+        })?;
+
+    Ok(Json(banners.into_iter().map(Into::into).collect()))
+}
@@ -0,0 +1,555 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Query, State};
+use axum::http::Response;
+
+use crate::games::{Game, honkai};
+use crate::global::Global;
+use crate::hoyolab::{DsSalt, HeaderPreset, HoyolabRequest};
+use crate::http::error::{ApiError, ApiErrorCode};
+use crate::http::routes::json_response;
+
+use super::unified::{CalendarEntry, EntryKind, FeaturedItem, FeaturedKind, banner_image};
+use super::{LangQuery, calendar_snapshot, check_server, resolve_lang, try_fetch_fandom_images};
+
+#[derive(serde::Deserialize)]
+struct HyvCalendarData {
+    #[serde(default)]
+    act_list: Vec<HyvActivity>,
+    #[serde(default)]
+    supply_list: Vec<HyvSupply>,
+    #[serde(default)]
+    challenge_list: Vec<HyvChallenge>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvActivity {
+    id: u64,
+    name: String,
+    #[serde(rename = "type", default)]
+    type_name: String,
+    #[serde(default)]
+    desc: String,
+    start_timestamp: String,
+    end_timestamp: String,
+    #[serde(default)]
+    reward_list: Vec<HyvReward>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvReward {
+    item_id: u64,
+    name: String,
+    icon: String,
+    #[serde(default)]
+    rarity: String,
+    num: u64,
+    #[serde(default)]
+    homepage_show: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvSupply {
+    pool_id: u64,
+    pool_name: String,
+    #[serde(default)]
+    version_name: String,
+    #[serde(default)]
+    valkyries: Vec<HyvValkyrie>,
+    #[serde(default)]
+    equipment: Vec<HyvEquipment>,
+    start_timestamp: String,
+    end_timestamp: String,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvValkyrie {
+    id: u64,
+    name: String,
+    icon: String,
+    /// Initial rank: `S`, `A` or `B`.
+    #[serde(default)]
+    rank: String,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEquipment {
+    id: u64,
+    name: String,
+    icon: String,
+    #[serde(default)]
+    rarity: u8,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvChallenge {
+    id: u64,
+    name: String,
+    /// `abyss`, `memorial_arena` or `elysian_realm`.
+    #[serde(rename = "type", default)]
+    type_name: String,
+    start_timestamp: String,
+    end_timestamp: String,
+    #[serde(default)]
+    reward_list: Vec<HyvReward>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CalendarResponse {
+    events: Vec<Event>,
+    banners: Vec<Banner>,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Reward {
+    id: u64,
+    name: String,
+    icon: String,
+    rarity: String,
+    amount: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Event {
+    id: u64,
+    name: String,
+    description: String,
+    image_url: Option<String>,
+    type_name: String,
+    start_time: i64,
+    end_time: i64,
+    rewards: Vec<Reward>,
+    special_reward: Option<Reward>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Banner {
+    id: u64,
+    name: String,
+    version: String,
+    valkyries: Vec<Valkyrie>,
+    equipment: Vec<Equipment>,
+    start_time: i64,
+    end_time: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Valkyrie {
+    id: u64,
+    name: String,
+    icon: String,
+    rank: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Equipment {
+    id: u64,
+    name: String,
+    icon: String,
+    rarity: u8,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Challenge {
+    id: u64,
+    name: String,
+    type_name: String,
+    start_time: i64,
+    end_time: i64,
+    rewards: Vec<Reward>,
+    special_reward: Option<Reward>,
+}
+
+fn map_rewards(reward_list: Vec<HyvReward>) -> (Vec<Reward>, Option<Reward>) {
+    let mut special_reward = None;
+    let mut rewards = Vec::new();
+
+    for r in reward_list {
+        let reward = Reward {
+            id: r.item_id,
+            name: r.name,
+            icon: r.icon,
+            rarity: r.rarity,
+            amount: r.num,
+        };
+        if r.homepage_show {
+            special_reward = Some(reward);
+        } else {
+            rewards.push(reward);
+        }
+    }
+
+    (rewards, special_reward)
+}
+
+fn transform_event(act: HyvActivity, image_url: Option<String>) -> Event {
+    let (rewards, special_reward) = map_rewards(act.reward_list);
+
+    Event {
+        id: act.id,
+        name: act.name,
+        description: act.desc,
+        image_url,
+        type_name: act.type_name,
+        start_time: act.start_timestamp.parse().unwrap_or(0),
+        end_time: act.end_timestamp.parse().unwrap_or(0),
+        rewards,
+        special_reward,
+    }
+}
+
+fn transform_challenge(challenge: HyvChallenge) -> Challenge {
+    let (rewards, special_reward) = map_rewards(challenge.reward_list);
+
+    Challenge {
+        id: challenge.id,
+        name: challenge.name,
+        type_name: challenge.type_name,
+        start_time: challenge.start_timestamp.parse().unwrap_or(0),
+        end_time: challenge.end_timestamp.parse().unwrap_or(0),
+        rewards,
+        special_reward,
+    }
+}
+
+fn transform_banner(supply: HyvSupply) -> Banner {
+    Banner {
+        id: supply.pool_id,
+        name: supply.pool_name,
+        version: supply.version_name,
+        valkyries: supply
+            .valkyries
+            .into_iter()
+            .map(|v| Valkyrie {
+                id: v.id,
+                name: v.name,
+                icon: v.icon,
+                rank: v.rank,
+            })
+            .collect(),
+        equipment: supply
+            .equipment
+            .into_iter()
+            .map(|e| Equipment {
+                id: e.id,
+                name: e.name,
+                icon: e.icon,
+                rarity: e.rarity,
+            })
+            .collect(),
+        start_time: supply.start_timestamp.parse().unwrap_or(0),
+        end_time: supply.end_timestamp.parse().unwrap_or(0),
+    }
+}
+
+fn eligible_activity_ids(data: &HyvCalendarData) -> Vec<u64> {
+    data.act_list
+        .iter()
+        .filter(|activity| activity.start_timestamp != "0" && activity.end_timestamp != "0")
+        .map(|activity| activity.id)
+        .collect()
+}
+
+fn canonical_activities(data: &HyvCalendarData, eligible_ids: &[u64]) -> Vec<(u64, String)> {
+    let mut activities: Vec<_> = data
+        .act_list
+        .iter()
+        .filter(|activity| eligible_ids.contains(&activity.id))
+        .map(|activity| (activity.id, activity.name.clone()))
+        .collect();
+    activities.sort_unstable_by_key(|(id, _)| *id);
+    activities.dedup_by_key(|(id, _)| *id);
+    activities
+}
+
+fn fandom_cache_key(activities: &[(u64, String)]) -> String {
+    let ids = activities
+        .iter()
+        .map(|(id, _)| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("/fandom/honkai/event-images/{ids}")
+}
+
+fn map_activity_images(
+    activities: &[(u64, String)],
+    fandom_images: &HashMap<String, String>,
+) -> HashMap<u64, String> {
+    activities
+        .iter()
+        .filter_map(|(id, name)| {
+            fandom_images
+                .get(name)
+                .cloned()
+                .map(|image_url| (*id, image_url))
+        })
+        .collect()
+}
+
+fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -> CalendarResponse {
+    let events = data
+        .act_list
+        .into_iter()
+        .filter(|act| act.start_timestamp != "0" && act.end_timestamp != "0")
+        .map(|act| {
+            let image_url = image_map.get(&act.id).cloned();
+            transform_event(act, image_url)
+        })
+        .collect();
+
+    let banners = data.supply_list.into_iter().map(transform_banner).collect();
+
+    let challenges = data
+        .challenge_list
+        .into_iter()
+        .map(transform_challenge)
+        .collect();
+
+    CalendarResponse {
+        events,
+        banners,
+        challenges,
+    }
+}
+
+fn calendar_request<'a>(
+    client: &'a reqwest::Client,
+    uid: &str,
+    region: &str,
+    cookie: &str,
+    lang: &str,
+) -> HoyolabRequest<'a> {
+    HoyolabRequest::get(client, "honkai_calendar", honkai::CALENDAR_API)
+        .query(&[("server", region), ("role_id", uid)])
+        .localized_cookie(cookie, lang)
+        .preset(HeaderPreset::GameRecord, lang)
+        .sign(DsSalt::Lk2)
+}
+
+/// GET /honkai/calendar
+///
+/// Returns current events, supply banners, and Abyss, Memorial Arena and
+/// Elysian Realm periods for Honkai Impact 3rd.
+#[tracing::instrument(skip(global))]
+pub(super) async fn get_honkai_calendar(
+    Query(query): Query<LangQuery>,
+    State(global): State<Arc<Global>>,
+) -> Result<Response<Body>, ApiError> {
+    check_server(query.server.as_deref())?;
+    let lang = resolve_lang(query.lang)?;
+    let snapshot = calendar_snapshot(&global, Game::Honkai, lang).await?;
+    Ok(json_response(snapshot.json()))
+}
+
+/// Fetch the calendar JSON for `lang` from HoYoLab.
+pub(super) async fn fetch_calendar(
+    global: &Arc<Global>,
+    lang: &'static str,
+) -> Result<Bytes, ApiError> {
+    let account = Some(&global.config.calendar.honkai)
+        .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
+        .ok_or_else(|| {
+            ApiError::internal_server_error(
+                ApiErrorCode::NOT_CONFIGURED,
+                "honkai calendar credentials not configured",
+            )
+        })?;
+
+    let data: HyvCalendarData = calendar_request(
+        &global.http_client,
+        &account.uid,
+        &account.region,
+        &account.cookie,
+        lang,
+    )
+    .send()
+    .await?;
+    let eligible_ids = eligible_activity_ids(&data);
+
+    let canonical_activities = if lang == "en-us" {
+        canonical_activities(&data, &eligible_ids)
+    } else {
+        let english_request = calendar_request(
+            &global.http_client,
+            &account.uid,
+            &account.region,
+            &account.cookie,
+            "en-us",
+        );
+        match english_request.send::<HyvCalendarData>().await {
+            Ok(english_data) => canonical_activities(&english_data, &eligible_ids),
+            Err(error) => {
+                tracing::warn!(error = ?error, "failed to fetch canonical honkai activity names");
+                Vec::new()
+            }
+        }
+    };
+
+    let image_map = if canonical_activities.is_empty() {
+        HashMap::new()
+    } else {
+        let names: Vec<String> = canonical_activities
+            .iter()
+            .map(|(_, name)| name.clone())
+            .collect();
+        let cache_key = fandom_cache_key(&canonical_activities);
+        let fandom_bytes = global
+            .fandom_image_cache
+            .get_or_try_insert(cache_key, async {
+                let map = try_fetch_fandom_images(
+                    &global.http_client,
+                    "https://honkaiimpact3.fandom.com/api.php",
+                    "File:",
+                    ".png",
+                    &names,
+                )
+                .await
+                .map_err(|error| {
+                    tracing::warn!(error = %error, "failed to fetch honkai fandom images");
+                    ApiError::internal_server_error(
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to fetch fandom images",
+                    )
+                })?;
+                let bytes = serde_json::to_vec(&map).map_err(|error| {
+                    tracing::warn!(error = %error, "failed to serialize honkai fandom images");
+                    ApiError::internal_server_error(
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to cache fandom images",
+                    )
+                })?;
+                Ok(Bytes::from(bytes))
+            })
+            .await;
+        let fandom_images: HashMap<String, String> = match fandom_bytes {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(error) => {
+                tracing::warn!(error = ?error, "honkai fandom images unavailable");
+                HashMap::new()
+            }
+        };
+        map_activity_images(&canonical_activities, &fandom_images)
+    };
+
+    let calendar = transform_calendar(data, &image_map);
+    Ok(Bytes::from(
+        serde_json::to_vec(&calendar).expect("CalendarResponse is always serializable"),
+    ))
+}
+
+/// Map the cached calendar JSON onto the cross-game schema.
+pub(super) fn unified_entries(bytes: &[u8]) -> serde_json::Result<Vec<CalendarEntry>> {
+    let calendar: CalendarResponse = serde_json::from_slice(bytes)?;
+    let game = Game::Honkai;
+
+    let events = calendar.events.into_iter().map(|event| CalendarEntry {
+        kind: EntryKind::Event,
+        game,
+        id: event.id.to_string(),
+        name: event.name,
+        description: Some(event.description).filter(|d| !d.is_empty()),
+        version: None,
+        image: event.image_url,
+        start: Some(event.start_time),
+        end: Some(event.end_time),
+        featured: Vec::new(),
+    });
+
+    let banners = calendar.banners.into_iter().map(|banner| {
+        let featured: Vec<_> = banner
+            .valkyries
+            .into_iter()
+            .map(|v| FeaturedItem {
+                kind: FeaturedKind::Character,
+                id: v.id,
+                name: v.name,
+                image: v.icon,
+                rarity: v.rank,
+            })
+            .chain(banner.equipment.into_iter().map(|e| FeaturedItem {
+                kind: FeaturedKind::Weapon,
+                id: e.id,
+                name: e.name,
+                image: e.icon,
+                rarity: e.rarity.to_string(),
+            }))
+            .collect();
+        CalendarEntry {
+            kind: EntryKind::Banner,
+            game,
+            id: banner.id.to_string(),
+            name: banner.name,
+            description: None,
+            version: Some(banner.version).filter(|v| !v.is_empty()),
+            image: banner_image(&featured),
+            start: Some(banner.start_time),
+            end: Some(banner.end_time),
+            featured,
+        }
+    });
+
+    let challenges = calendar
+        .challenges
+        .into_iter()
+        .map(|challenge| CalendarEntry {
+            kind: EntryKind::Challenge,
+            game,
+            id: challenge.id.to_string(),
+            name: challenge.name,
+            description: None,
+            version: None,
+            image: None,
+            start: Some(challenge.start_time),
+            end: Some(challenge.end_time),
+            featured: Vec::new(),
+        });
+
+    Ok(events.chain(banners).chain(challenges).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_supply_banners_and_challenges() {
+        let data: HyvCalendarData = serde_json::from_value(serde_json::json!({
+            "act_list": [
+                { "id": 1, "name": "Summer Event", "type": "Event", "desc": "",
+                  "start_timestamp": "1700000000", "end_timestamp": "1700100000",
+                  "reward_list": [] },
+                { "id": 2, "name": "Permanent", "start_timestamp": "0", "end_timestamp": "0" }
+            ],
+            "supply_list": [{
+                "pool_id": 10, "pool_name": "Expansion Supply", "version_name": "7.5",
+                "valkyries": [{ "id": 101, "name": "Herrscher of Finality", "icon": "v.png", "rank": "S" }],
+                "equipment": [{ "id": 201, "name": "Key of Castigation", "icon": "e.png", "rarity": 5 }],
+                "start_timestamp": "1700000000", "end_timestamp": "1700100000"
+            }],
+            "challenge_list": [{
+                "id": 30, "name": "Elysian Realm", "type": "elysian_realm",
+                "start_timestamp": "1700000000", "end_timestamp": "1700100000"
+            }]
+        }))
+        .unwrap();
+
+        let calendar = transform_calendar(data, &HashMap::new());
+        assert_eq!(calendar.events.len(), 1);
+        assert_eq!(calendar.challenges[0].type_name, "elysian_realm");
+
+        let bytes = serde_json::to_vec(&calendar).unwrap();
+        let entries = unified_entries(&bytes).unwrap();
+        let banner = entries
+            .iter()
+            .find(|e| e.kind == EntryKind::Banner)
+            .unwrap();
+        assert_eq!(banner.featured.len(), 2);
+        assert_eq!(banner.featured[0].kind, FeaturedKind::Character);
+        assert_eq!(banner.featured[0].rarity, "S");
+        assert_eq!(banner.featured[1].kind, FeaturedKind::Weapon);
+        assert_eq!(banner.image.as_deref(), Some("v.png"));
+    }
+}
@@ -0,0 +1,344 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::Response;
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+
+use crate::games::Game;
+use crate::global::Global;
+use crate::http::error::{ApiError, ApiErrorCode};
+
+use super::unified::{CalendarEntry, EntryKind};
+use super::{calendar_snapshot, check_server, resolve_lang};
+
+/// Lines longer than this many octets are folded (RFC 5545 section 3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, serde::Deserialize)]
+pub(super) struct IcsQuery {
+    lang: Option<String>,
+    include: Option<String>,
+    server: Option<String>,
+}
+
+impl EntryKind {
+    fn from_section(name: &str) -> Option<Self> {
+        match name {
+            "events" => Some(Self::Event),
+            "banners" => Some(Self::Banner),
+            "challenges" => Some(Self::Challenge),
+            _ => None,
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            Self::Event => "Event",
+            Self::Banner => "Banner",
+            Self::Challenge => "Challenge",
+        }
+    }
+}
+
+impl CalendarEntry {
+    fn summary(&self) -> String {
+        if self.kind == EntryKind::Banner && !self.featured.is_empty() {
+            format!("{}: {}", self.name, self.featured_names().join(", "))
+        } else {
+            self.name.clone()
+        }
+    }
+
+    fn ics_description(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(description) = self.description.as_deref().map(plain_text)
+            && !description.is_empty()
+        {
+            lines.push(description);
+        }
+        if let Some(version) = self.version.as_deref().filter(|v| !v.is_empty()) {
+            lines.push(format!("Version: {version}"));
+        }
+        if !self.featured.is_empty() {
+            lines.push(format!("Featured: {}", self.featured_names().join(", ")));
+        }
+        lines.join("\n")
+    }
+
+    fn featured_names(&self) -> Vec<&str> {
+        self.featured.iter().map(|f| f.name.as_str()).collect()
+    }
+}
+
+/// GET /:game/calendar.ics
+///
+/// The game calendar as an iCalendar feed, one VEVENT per event, banner and
+/// challenge.
+#[tracing::instrument(skip(global))]
+pub(super) async fn get_calendar_ics(
+    Path(game_slug): Path<String>,
+    Query(query): Query<IcsQuery>,
+    State(global): State<Arc<Global>>,
+) -> Result<Response<Body>, ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+    check_server(query.server.as_deref())?;
+    let lang = resolve_lang(query.lang)?;
+    let kinds = parse_include(query.include.as_deref())?;
+    let entries = calendar_snapshot(&global, game, lang).await?.entries()?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/calendar; charset=utf-8")
+        .header(
+            "content-disposition",
+            format!("inline; filename=\"{}.ics\"", game.slug()),
+        )
+        .body(Body::from(render(game, &entries, &kinds, Utc::now())))
+        .unwrap())
+}
+
+/// Parse `?include=events,banners,challenges`. Everything is included when
+/// the parameter is missing or empty.
+fn parse_include(include: Option<&str>) -> Result<Vec<EntryKind>, ApiError> {
+    let Some(include) = include.filter(|s| !s.trim().is_empty()) else {
+        return Ok(EntryKind::ALL.to_vec());
+    };
+
+    let mut kinds = Vec::new();
+    for name in include.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let kind = EntryKind::from_section(name).ok_or_else(|| {
+            ApiError::bad_request(
+                ApiErrorCode::INVALID_FILTER,
+                format!("unknown calendar section: {name}"),
+            )
+        })?;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    Ok(kinds)
+}
+
+fn render(
+    game: Game,
+    entries: &[CalendarEntry],
+    kinds: &[EntryKind],
+    now: DateTime<Utc>,
+) -> String {
+    let mut out = String::new();
+    let dtstamp = format_timestamp(now);
+
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//hoyoverse-api//Game Calendar//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(
+        &mut out,
+        &format!("X-WR-CALNAME:{}", escape_text(game.display_name())),
+    );
+
+    for &kind in kinds {
+        for entry in entries.iter().filter(|e| e.kind == kind) {
+            // Entries without a known start cannot be placed on a calendar.
+            let Some(start) = entry
+                .start
+                .filter(|t| *t > 0)
+                .and_then(|t| DateTime::from_timestamp(t, 0))
+            else {
+                continue;
+            };
+            let end = entry
+                .end
+                .filter(|t| *t > start.timestamp())
+                .and_then(|t| DateTime::from_timestamp(t, 0));
+
+            push_line(&mut out, "BEGIN:VEVENT");
+            // Built from the activity ID, so it must never change.
+            push_line(
+                &mut out,
+                &format!(
+                    "UID:{}-{}-{}@hoyoverse-api",
+                    game.slug(),
+                    kind.name(),
+                    entry.id
+                ),
+            );
+            push_line(&mut out, &format!("DTSTAMP:{dtstamp}"));
+            push_line(&mut out, &format!("DTSTART:{}", format_timestamp(start)));
+            if let Some(end) = end {
+                push_line(&mut out, &format!("DTEND:{}", format_timestamp(end)));
+            }
+            push_line(
+                &mut out,
+                &format!("SUMMARY:{}", escape_text(&entry.summary())),
+            );
+            let description = entry.ics_description();
+            if !description.is_empty() {
+                push_line(
+                    &mut out,
+                    &format!("DESCRIPTION:{}", escape_text(&description)),
+                );
+            }
+            push_line(&mut out, &format!("CATEGORIES:{}", kind.category()));
+            push_line(&mut out, "TRANSP:TRANSPARENT");
+            push_line(&mut out, "END:VEVENT");
+        }
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT property value (RFC 5545 section 3.3.11).
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded at 75 octets without splitting characters.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Drop HTML tags from an activity description, keeping line breaks.
+fn plain_text(html: &str) -> String {
+    let html = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p>", "\n");
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::super::unified::{FeaturedItem, FeaturedKind};
+    use super::*;
+
+    fn entry(kind: EntryKind, id: &str, name: &str, start: Option<i64>) -> CalendarEntry {
+        CalendarEntry {
+            kind,
+            game: Game::Zenless,
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            version: None,
+            image: None,
+            start,
+            end: start.map(|t| t + 1_000_000),
+            featured: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_include_filter() {
+        assert_eq!(parse_include(None).unwrap(), EntryKind::ALL.to_vec());
+        assert_eq!(
+            parse_include(Some("banners, events,banners")).unwrap(),
+            vec![EntryKind::Banner, EntryKind::Event]
+        );
+        assert!(parse_include(Some("events,quests")).is_err());
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let mut out = String::new();
+        push_line(&mut out, &format!("SUMMARY:{}", "é".repeat(40)));
+
+        let lines: Vec<&str> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            out.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "é".repeat(40))
+        );
+    }
+
+    #[test]
+    fn renders_stable_events() {
+        let mut event = entry(
+            EntryKind::Event,
+            "2001",
+            "Lantern Rite; Part 1",
+            Some(1718000000),
+        );
+        event.description = Some("<p>Join the festival,</p><p>in Liyue</p>".to_string());
+        let mut banner = entry(
+            EntryKind::Banner,
+            "1718000000-1331",
+            "Exclusive Channel",
+            Some(1718000000),
+        );
+        banner.featured.push(FeaturedItem {
+            kind: FeaturedKind::Character,
+            id: 1331,
+            name: "Vivian".to_string(),
+            image: String::new(),
+            rarity: "S".to_string(),
+        });
+        let entries = [
+            event,
+            banner,
+            entry(EntryKind::Challenge, "7", "Shiyu Defense", None),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 0, 0, 0).unwrap();
+
+        let ics = render(Game::Zenless, &entries, &EntryKind::ALL, now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:zenless-event-2001@hoyoverse-api\r\n"));
+        assert!(ics.contains("DTSTART:20240610T061320Z\r\n"));
+        assert!(ics.contains("SUMMARY:Lantern Rite\\; Part 1\r\n"));
+        assert!(ics.contains("DESCRIPTION:Join the festival\\,\\nin Liyue\r\n"));
+        assert!(ics.contains("UID:zenless-banner-1718000000-1331@hoyoverse-api\r\n"));
+        assert!(ics.contains("SUMMARY:Exclusive Channel: Vivian\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+
+        let banners_only = render(Game::Zenless, &entries, &[EntryKind::Banner], now);
+        assert_eq!(banners_only.matches("BEGIN:VEVENT").count(), 1);
+    }
+}
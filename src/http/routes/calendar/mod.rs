@@ -3,36 +3,108 @@ use std::sync::Arc;
 
 use anyhow::Context as _;
 use axum::Router;
+use axum::body::Bytes;
 use axum::routing::get;
 
+use crate::games::Game;
+use crate::games::server::Server;
 use crate::global::Global;
 use crate::http::error::{ApiError, ApiErrorCode};
 
+mod endgame;
 mod genshin;
+mod history;
+mod honkai;
+mod ics;
 mod starrail;
+mod store;
+pub mod unified;
 mod zenless;
 
+pub use store::{CalendarSnapshot, CalendarStore};
+
 pub fn routes() -> Router<Arc<Global>> {
     Router::new()
         .route("/genshin/calendar", get(genshin::get_genshin_calendar))
         .route("/starrail/calendar", get(starrail::get_starrail_calendar))
         .route("/zenless/calendar", get(zenless::get_zenless_calendar))
+        .route("/honkai/calendar", get(honkai::get_honkai_calendar))
+        .route("/:game/calendar.ics", get(ics::get_calendar_ics))
+        .route("/calendar", get(unified::get_calendar))
+        .route("/:game/banners/history", get(history::get_banner_history))
+}
+
+/// Games with an event calendar.
+pub const CALENDAR_GAMES: [Game; 4] = [Game::Genshin, Game::Starrail, Game::Zenless, Game::Honkai];
+
+/// Fetch `game`'s calendar JSON from upstream.
+async fn fetch_calendar(
+    global: &Arc<Global>,
+    game: Game,
+    lang: &'static str,
+) -> Result<Bytes, ApiError> {
+    match game {
+        Game::Genshin => genshin::fetch_calendar(global, lang).await,
+        Game::Starrail => starrail::fetch_calendar(global, lang).await,
+        Game::Zenless => zenless::fetch_calendar(global, lang).await,
+        Game::Honkai => honkai::fetch_calendar(global, lang).await,
+        Game::Themis => Err(ApiError::not_found(
+            ApiErrorCode::UNKNOWN_GAME,
+            "no calendar for this game",
+        )),
+    }
+}
+
+/// The last good calendar for `game`. Requests only wait on upstream when
+/// nothing has been fetched for this language yet; otherwise the refresh job
+/// keeps the snapshot current.
+pub async fn calendar_snapshot(
+    global: &Arc<Global>,
+    game: Game,
+    lang: &'static str,
+) -> Result<Arc<CalendarSnapshot>, ApiError> {
+    global
+        .calendars
+        .get_or_try_insert(game, lang, async {
+            let body = fetch_calendar(global, game, lang).await?;
+            Ok(CalendarSnapshot::new(game, body))
+        })
+        .await
 }
 
-fn random_r() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-    let nanos = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .subsec_nanos() as usize;
-    (0..6)
-        .map(|i| CHARSET[(nanos.wrapping_add(i * 7919)) % CHARSET.len()] as char)
-        .collect()
+/// Re-fetch `game`'s calendar. On failure the previous snapshot stays in
+/// place, marked stale.
+pub async fn refresh_calendar(
+    global: &Arc<Global>,
+    game: Game,
+    lang: &'static str,
+) -> Result<(), ApiError> {
+    match fetch_calendar(global, game, lang).await {
+        Ok(body) => {
+            global
+                .calendars
+                .insert(game, lang, CalendarSnapshot::new(game, body))
+                .await;
+            Ok(())
+        }
+        Err(error) => {
+            if let Some(previous) = global.calendars.get(game, lang).await
+                && !previous.stale
+            {
+                let stale = CalendarSnapshot {
+                    stale: true,
+                    ..(*previous).clone()
+                };
+                global.calendars.insert(game, lang, stale).await;
+            }
+            Err(error)
+        }
+    }
 }
 
 const DEFAULT_LANG: &str = "en-us";
 
-const SUPPORTED_LANGS: &[&str] = &[
+pub const SUPPORTED_LANGS: &[&str] = &[
     "en-us", "zh-cn", "zh-tw", "de-de", "es-es", "fr-fr", "id-id", "it-it", "ja-jp", "ko-kr",
     "pt-pt", "ru-ru", "th-th", "tr-tr", "vi-vn",
 ];
@@ -62,6 +134,23 @@ const LANG_ALIASES: &[(&str, &str)] = &[
 #[derive(Debug, serde::Deserialize)]
 struct LangQuery {
     lang: Option<String>,
+    server: Option<String>,
+}
+
+/// Calendars are only fetched from the overseas HoYoLab endpoints, so
+/// `server=cn` is refused rather than answered with the global calendar.
+fn check_server(server: Option<&str>) -> Result<(), ApiError> {
+    match server.map(Server::parse) {
+        None | Some(Some(Server::Global)) => Ok(()),
+        Some(Some(Server::Cn)) => Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_SERVER,
+            "CN calendars are not supported yet",
+        )),
+        Some(None) => Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_SERVER,
+            "unknown server",
+        )),
+    }
 }
 
 fn resolve_lang(lang: Option<String>) -> Result<&'static str, ApiError> {
@@ -80,17 +169,6 @@ fn resolve_lang(lang: Option<String>) -> Result<&'static str, ApiError> {
         })
 }
 
-fn cookie_with_lang(cookie: &str, lang: &str) -> String {
-    let mut parts: Vec<String> = cookie
-        .split(';')
-        .map(str::trim)
-        .filter(|part| !part.is_empty() && !part.starts_with("mi18nLang="))
-        .map(ToOwned::to_owned)
-        .collect();
-    parts.insert(0, format!("mi18nLang={lang}"));
-    parts.join("; ")
-}
-
 #[derive(serde::Deserialize)]
 struct FandomResponse {
     query: Option<FandomQuery>,
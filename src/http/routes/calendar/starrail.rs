@@ -5,29 +5,15 @@ use axum::body::{Body, Bytes};
 use axum::extract::{Query, State};
 use axum::http::Response;
 
-use crate::games::starrail;
+use crate::games::{Game, starrail};
 use crate::global::Global;
+use crate::hoyolab::{DsSalt, HeaderPreset, HoyolabRequest};
 use crate::http::error::{ApiError, ApiErrorCode};
 use crate::http::routes::json_response;
 
-use super::{LangQuery, cookie_with_lang, random_r, resolve_lang, try_fetch_fandom_images};
-
-const DS_SALT: &str = "6s25p5ox5y14umn1p61aqyyvbvvl3lrt";
-
-fn generate_ds() -> String {
-    let t = chrono::Utc::now().timestamp();
-    let r = random_r();
-    let raw = format!("salt={DS_SALT}&t={t}&r={r}");
-    let hash = format!("{:x}", md5::compute(raw.as_bytes()));
-    format!("{t},{r},{hash}")
-}
-
-#[derive(serde::Deserialize)]
-struct HyvResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HyvCalendarData>,
-}
+use super::endgame::{ChallengeBuff, ChallengeDetail, ChallengeEnemy, describe};
+use super::unified::{CalendarEntry, EntryKind, FeaturedItem, FeaturedKind, banner_image};
+use super::{LangQuery, calendar_snapshot, check_server, resolve_lang, try_fetch_fandom_images};
 
 #[derive(serde::Deserialize)]
 struct HyvCalendarData {
@@ -110,14 +96,60 @@ struct HyvReward {
     num: u64,
 }
 
-#[derive(serde::Serialize)]
+/// Pure Fiction and Apocalyptic Shadow records share this shape.
+#[derive(serde::Deserialize)]
+struct HyvEndgameData {
+    #[serde(default)]
+    groups: Vec<HyvEndgameGroup>,
+    #[serde(default)]
+    all_floor_detail: Vec<HyvEndgameFloor>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEndgameGroup {
+    schedule_id: u64,
+    #[serde(default)]
+    status: String,
+    upper_boss: Option<HyvEndgameBoss>,
+    lower_boss: Option<HyvEndgameBoss>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEndgameBoss {
+    name_mi18n: String,
+    #[serde(default)]
+    icon: String,
+    #[serde(default)]
+    desc: String,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEndgameFloor {
+    node_1: Option<HyvEndgameNode>,
+    node_2: Option<HyvEndgameNode>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEndgameNode {
+    buff: Option<HyvEndgameBuff>,
+}
+
+#[derive(serde::Deserialize)]
+struct HyvEndgameBuff {
+    name_mi18n: String,
+    desc_mi18n: String,
+    #[serde(default)]
+    icon: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
 struct CalendarResponse {
     events: Vec<Event>,
     banners: Vec<Banner>,
     challenges: Vec<Challenge>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Reward {
     id: u64,
     name: String,
@@ -126,7 +158,7 @@ struct Reward {
     amount: u64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Event {
     id: u64,
     name: String,
@@ -139,7 +171,7 @@ struct Event {
     special_reward: Option<Reward>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Banner {
     id: u64,
     name: String,
@@ -150,7 +182,7 @@ struct Banner {
     end_time: i64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Character {
     id: u64,
     name: String,
@@ -160,7 +192,7 @@ struct Character {
     rarity: u8,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct LightCone {
     id: u64,
     name: String,
@@ -169,7 +201,7 @@ struct LightCone {
     rarity: u8,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Challenge {
     id: u64,
     name: String,
@@ -178,8 +210,21 @@ struct Challenge {
     end_time: i64,
     rewards: Vec<Reward>,
     special_reward: Option<Reward>,
+    buffs: Vec<ChallengeBuff>,
+    enemies: Vec<ChallengeEnemy>,
 }
 
+/// Record details of the current period of one endgame mode, keyed by the
+/// `challenge_type` the calendar uses for it.
+struct EndgameDetail {
+    challenge_type: &'static str,
+    schedule_id: u64,
+    detail: ChallengeDetail,
+}
+
+const PURE_FICTION_TYPE: &str = "ChallengeTypeStory";
+const APOCALYPTIC_SHADOW_TYPE: &str = "ChallengeTypeBoss";
+
 fn map_reward(r: HyvReward) -> Reward {
     Reward {
         id: r.item_id,
@@ -217,6 +262,60 @@ fn transform_challenge(challenge: HyvChallenge) -> Challenge {
         end_time: challenge.time_info.end_ts.parse().unwrap_or(0),
         rewards: challenge.reward_list.into_iter().map(map_reward).collect(),
         special_reward: map_special_reward(challenge.special_reward),
+        buffs: Vec::new(),
+        enemies: Vec::new(),
+    }
+}
+
+fn endgame_detail(data: HyvEndgameData, challenge_type: &'static str) -> Option<EndgameDetail> {
+    let index = data
+        .groups
+        .iter()
+        .position(|g| g.status == "challenge_status_in_progress")
+        .unwrap_or(0);
+    let group = data.groups.into_iter().nth(index)?;
+
+    let mut detail = ChallengeDetail::default();
+    let nodes = data
+        .all_floor_detail
+        .into_iter()
+        .flat_map(|floor| [floor.node_1, floor.node_2]);
+    for buff in nodes.flatten().filter_map(|node| node.buff) {
+        detail.push_buff(ChallengeBuff {
+            name: buff.name_mi18n,
+            description: buff.desc_mi18n,
+            icon: Some(buff.icon).filter(|i| !i.is_empty()),
+        });
+    }
+    for boss in [group.upper_boss, group.lower_boss].into_iter().flatten() {
+        detail.push_enemy(ChallengeEnemy {
+            name: boss.name_mi18n,
+            icon: Some(boss.icon).filter(|i| !i.is_empty()),
+            level: None,
+            description: Some(boss.desc).filter(|d| !d.is_empty()),
+        });
+    }
+
+    Some(EndgameDetail {
+        challenge_type,
+        schedule_id: group.schedule_id,
+        detail,
+    })
+}
+
+/// Attach record details to the calendar challenge of the same mode,
+/// preferring the one whose group is the recorded schedule.
+fn merge_endgame(challenges: &mut [Challenge], details: Vec<EndgameDetail>) {
+    for endgame in details {
+        let same_mode = |c: &Challenge| c.type_name == endgame.challenge_type;
+        let index = challenges
+            .iter()
+            .position(|c| same_mode(c) && c.id == endgame.schedule_id)
+            .or_else(|| challenges.iter().position(same_mode));
+        if let Some(challenge) = index.map(|i| &mut challenges[i]) {
+            challenge.buffs = endgame.detail.buffs;
+            challenge.enemies = endgame.detail.enemies;
+        }
     }
 }
 
@@ -309,7 +408,11 @@ fn map_activity_images(
         .collect()
 }
 
-fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -> CalendarResponse {
+fn transform_calendar(
+    data: HyvCalendarData,
+    image_map: &HashMap<u64, String>,
+    endgame: Vec<EndgameDetail>,
+) -> CalendarResponse {
     let banners = data
         .avatar_card_pool_list
         .into_iter()
@@ -331,11 +434,12 @@ fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -
         })
         .collect();
 
-    let challenges = data
+    let mut challenges: Vec<_> = data
         .challenge_list
         .into_iter()
         .map(transform_challenge)
         .collect();
+    merge_endgame(&mut challenges, endgame);
 
     CalendarResponse {
         events,
@@ -344,53 +448,62 @@ fn transform_calendar(data: HyvCalendarData, image_map: &HashMap<u64, String>) -
     }
 }
 
-fn calendar_request(
-    client: &reqwest::Client,
+fn calendar_request<'a>(
+    client: &'a reqwest::Client,
     uid: &str,
     region: &str,
     cookie: &str,
     lang: &str,
-) -> reqwest::RequestBuilder {
-    client
-        .get(starrail::CALENDAR_API)
+) -> HoyolabRequest<'a> {
+    HoyolabRequest::get(client, "starrail_calendar", starrail::CALENDAR_API)
         .query(&[("server", region), ("role_id", uid)])
-        .header("Cookie", cookie)
-        .header("DS", generate_ds())
-        .header("x-rpc-app_version", "1.5.0")
-        .header("x-rpc-client_type", "5")
-        .header("x-rpc-language", lang)
-}
-
-async fn fetch_calendar_data(
-    request: reqwest::RequestBuilder,
-    request_lang: &str,
-) -> Result<HyvCalendarData, ApiError> {
-    let response = request.send().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to fetch starrail calendar");
-        ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to fetch calendar")
-    })?;
-    let response: HyvResponse = response.json().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to parse starrail calendar response");
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "failed to parse calendar response",
-        )
-    })?;
-
-    if response.retcode != 0 {
-        tracing::error!(retcode = response.retcode, message = %response.message, request_lang, "hoyoverse starrail calendar API error");
-        return Err(ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned an error",
-        ));
-    }
+        .localized_cookie(cookie, lang)
+        .preset(HeaderPreset::GameRecord, lang)
+        .sign(DsSalt::Lk2)
+}
 
-    response.data.ok_or_else(|| {
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned no data",
+/// Fetch Pure Fiction and Apocalyptic Shadow buffs and bosses. Memory of
+/// Chaos records carry neither, so its calendar entry is left as is.
+async fn fetch_endgame_details(
+    client: &reqwest::Client,
+    uid: &str,
+    region: &str,
+    cookie: &str,
+    lang: &str,
+) -> Vec<EndgameDetail> {
+    let request = |endpoint: &'static str, url: &'static str| {
+        HoyolabRequest::get(client, endpoint, url)
+            .query(&[
+                ("server", region),
+                ("role_id", uid),
+                ("schedule_type", "1"),
+                ("need_all", "true"),
+            ])
+            .localized_cookie(cookie, lang)
+            .preset(HeaderPreset::GameRecord, lang)
+            .sign(DsSalt::Lk2)
+    };
+
+    let (fiction, shadow) = tokio::join!(
+        request("starrail_pure_fiction", starrail::PURE_FICTION_API).send::<HyvEndgameData>(),
+        request(
+            "starrail_apocalyptic_shadow",
+            starrail::APOCALYPTIC_SHADOW_API
         )
-    })
+        .send::<HyvEndgameData>(),
+    );
+
+    [
+        fiction
+            .ok()
+            .and_then(|data| endgame_detail(data, PURE_FICTION_TYPE)),
+        shadow
+            .ok()
+            .and_then(|data| endgame_detail(data, APOCALYPTIC_SHADOW_TYPE)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
 }
 
 /// GET /starrail/calendar
@@ -401,110 +514,187 @@ pub(super) async fn get_starrail_calendar(
     Query(query): Query<LangQuery>,
     State(global): State<Arc<Global>>,
 ) -> Result<Response<Body>, ApiError> {
+    check_server(query.server.as_deref())?;
     let lang = resolve_lang(query.lang)?;
-    let cache_key = format!("/mihoyo/starrail/calendar/{lang}");
-
-    let bytes = global
-        .response_cache
-        .get_or_try_insert(cache_key, async {
-            let game_config = global
-                .config
-                .validator
-                .game_config(crate::games::Game::Starrail)
-                .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
-                .ok_or_else(|| {
+    let snapshot = calendar_snapshot(&global, Game::Starrail, lang).await?;
+    Ok(json_response(snapshot.json()))
+}
+
+/// Fetch the calendar JSON for `lang` from HoYoLab.
+pub(super) async fn fetch_calendar(
+    global: &Arc<Global>,
+    lang: &'static str,
+) -> Result<Bytes, ApiError> {
+    let game_config = global
+        .config
+        .validator
+        .game_config(Game::Starrail)
+        .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
+        .ok_or_else(|| {
+            ApiError::internal_server_error(
+                ApiErrorCode::NOT_CONFIGURED,
+                "starrail calendar credentials not configured",
+            )
+        })?;
+
+    let data: HyvCalendarData = calendar_request(
+        &global.http_client,
+        &game_config.uid,
+        &game_config.region,
+        &game_config.cookie,
+        lang,
+    )
+    .send()
+    .await?;
+    let endgame = fetch_endgame_details(
+        &global.http_client,
+        &game_config.uid,
+        &game_config.region,
+        &game_config.cookie,
+        lang,
+    )
+    .await;
+    let eligible_ids = eligible_activity_ids(&data);
+
+    let canonical_activities = if lang == "en-us" {
+        canonical_activities(&data, &eligible_ids)
+    } else {
+        let english_request = calendar_request(
+            &global.http_client,
+            &game_config.uid,
+            &game_config.region,
+            &game_config.cookie,
+            "en-us",
+        );
+        match english_request.send::<HyvCalendarData>().await {
+            Ok(english_data) => canonical_activities(&english_data, &eligible_ids),
+            Err(error) => {
+                tracing::warn!(error = ?error, "failed to fetch canonical starrail activity names");
+                Vec::new()
+            }
+        }
+    };
+
+    let image_map = if canonical_activities.is_empty() {
+        HashMap::new()
+    } else {
+        let names: Vec<String> = canonical_activities
+            .iter()
+            .map(|(_, name)| name.clone())
+            .collect();
+        let cache_key = fandom_cache_key(&canonical_activities);
+        let fandom_bytes = global
+            .fandom_image_cache
+            .get_or_try_insert(cache_key, async {
+                let map = try_fetch_fandom_images(
+                    &global.http_client,
+                    "https://honkai-star-rail.fandom.com/api.php",
+                    "File:Event ",
+                    ".png",
+                    &names,
+                )
+                .await
+                .map_err(|error| {
+                    tracing::warn!(error = %error, "failed to fetch starrail fandom images");
                     ApiError::internal_server_error(
-                        ApiErrorCode::NOT_CONFIGURED,
-                        "starrail calendar credentials not configured",
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to fetch fandom images",
                     )
                 })?;
-
-            let localized_cookie = cookie_with_lang(&game_config.cookie, lang);
-            let localized_request = calendar_request(
-                &global.http_client,
-                &game_config.uid,
-                &game_config.region,
-                &localized_cookie,
-                lang,
-            );
-            let data = fetch_calendar_data(localized_request, lang).await?;
-            let eligible_ids = eligible_activity_ids(&data);
-
-            let canonical_activities = if lang == "en-us" {
-                canonical_activities(&data, &eligible_ids)
-            } else {
-                let english_cookie = cookie_with_lang(&game_config.cookie, "en-us");
-                let english_request = calendar_request(
-                    &global.http_client,
-                    &game_config.uid,
-                    &game_config.region,
-                    &english_cookie,
-                    "en-us",
-                );
-                match fetch_calendar_data(english_request, "en-us").await {
-                    Ok(english_data) => canonical_activities(&english_data, &eligible_ids),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "failed to fetch canonical starrail activity names");
-                        Vec::new()
-                    }
-                }
-            };
-
-            let image_map = if canonical_activities.is_empty() {
+                let bytes = serde_json::to_vec(&map).map_err(|error| {
+                    tracing::warn!(error = %error, "failed to serialize starrail fandom images");
+                    ApiError::internal_server_error(
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to cache fandom images",
+                    )
+                })?;
+                Ok(Bytes::from(bytes))
+            })
+            .await;
+        let fandom_images: HashMap<String, String> = match fandom_bytes {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(error) => {
+                tracing::warn!(error = ?error, "starrail fandom images unavailable");
                 HashMap::new()
-            } else {
-                let names: Vec<String> = canonical_activities
-                    .iter()
-                    .map(|(_, name)| name.clone())
-                    .collect();
-                let cache_key = fandom_cache_key(&canonical_activities);
-                let fandom_bytes = global
-                    .fandom_image_cache
-                    .get_or_try_insert(cache_key, async {
-                        let map = try_fetch_fandom_images(
-                            &global.http_client,
-                            "https://honkai-star-rail.fandom.com/api.php",
-                            "File:Event ",
-                            ".png",
-                            &names,
-                        )
-                        .await
-                        .map_err(|error| {
-                            tracing::warn!(error = %error, "failed to fetch starrail fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to fetch fandom images",
-                            )
-                        })?;
-                        let bytes = serde_json::to_vec(&map).map_err(|error| {
-                            tracing::warn!(error = %error, "failed to serialize starrail fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to cache fandom images",
-                            )
-                        })?;
-                        Ok(Bytes::from(bytes))
-                    })
-                    .await;
-                let fandom_images: HashMap<String, String> = match fandom_bytes {
-                    Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "starrail fandom images unavailable");
-                        HashMap::new()
-                    }
-                };
-                map_activity_images(&canonical_activities, &fandom_images)
-            };
-
-            let calendar = transform_calendar(data, &image_map);
-            Ok(Bytes::from(
-                serde_json::to_vec(&calendar)
-                    .expect("CalendarResponse is always serializable"),
-            ))
-        })
-        .await?;
+            }
+        };
+        map_activity_images(&canonical_activities, &fandom_images)
+    };
+
+    let calendar = transform_calendar(data, &image_map, endgame);
+    Ok(Bytes::from(
+        serde_json::to_vec(&calendar).expect("CalendarResponse is always serializable"),
+    ))
+}
+
+/// Map the cached calendar JSON onto the cross-game schema.
+pub(super) fn unified_entries(bytes: &[u8]) -> serde_json::Result<Vec<CalendarEntry>> {
+    let calendar: CalendarResponse = serde_json::from_slice(bytes)?;
+    let game = Game::Starrail;
+
+    let events = calendar.events.into_iter().map(|event| CalendarEntry {
+        kind: EntryKind::Event,
+        game,
+        id: event.id.to_string(),
+        name: event.name,
+        description: Some(event.description).filter(|d| !d.is_empty()),
+        version: None,
+        image: event.image_url,
+        start: Some(event.start_time),
+        end: Some(event.end_time),
+        featured: Vec::new(),
+    });
+
+    let banners = calendar.banners.into_iter().map(|banner| {
+        let featured: Vec<_> = banner
+            .characters
+            .into_iter()
+            .map(|c| FeaturedItem {
+                kind: FeaturedKind::Character,
+                id: c.id,
+                name: c.name,
+                image: c.icon,
+                rarity: c.rarity.to_string(),
+            })
+            .chain(banner.light_cones.into_iter().map(|w| FeaturedItem {
+                kind: FeaturedKind::Weapon,
+                id: w.id,
+                name: w.name,
+                image: w.icon,
+                rarity: w.rarity.to_string(),
+            }))
+            .collect();
+        CalendarEntry {
+            kind: EntryKind::Banner,
+            game,
+            id: banner.id.to_string(),
+            name: banner.name,
+            description: None,
+            version: Some(banner.version),
+            image: banner_image(&featured),
+            start: Some(banner.start_time),
+            end: Some(banner.end_time),
+            featured,
+        }
+    });
 
-    Ok(json_response(bytes))
+    let challenges = calendar
+        .challenges
+        .into_iter()
+        .map(|challenge| CalendarEntry {
+            kind: EntryKind::Challenge,
+            game,
+            id: challenge.id.to_string(),
+            description: describe(&challenge.buffs, &challenge.enemies),
+            name: challenge.name,
+            version: None,
+            image: None,
+            start: Some(challenge.start_time),
+            end: Some(challenge.end_time),
+            featured: Vec::new(),
+        });
+
+    Ok(events.chain(banners).chain(challenges).collect())
 }
 
 #[cfg(test)]
@@ -594,4 +784,57 @@ mod tests {
         assert_eq!(fandom_cache_key(&first), fandom_cache_key(&reordered));
         assert_ne!(fandom_cache_key(&first), fandom_cache_key(&changed));
     }
+
+    #[test]
+    fn attaches_endgame_detail_to_matching_challenge() {
+        let shadow: HyvEndgameData = serde_json::from_value(serde_json::json!({
+            "groups": [{
+                "schedule_id": 2010,
+                "status": "challenge_status_in_progress",
+                "upper_boss": { "name_mi18n": "Phantylia", "icon": "https://example.com/boss.png", "desc": "" },
+                "lower_boss": { "name_mi18n": "Sam", "icon": "", "desc": "" },
+            }],
+            "all_floor_detail": [{
+                "node_1": { "buff": { "name_mi18n": "Stalwart Heart", "desc_mi18n": "Toughness +1", "icon": "" } },
+                "node_2": { "buff": null },
+            }],
+        }))
+        .unwrap();
+        let challenge = |group_id: u64, challenge_type: &str| HyvChallenge {
+            group_id,
+            name_mi18n: "Challenge".to_string(),
+            challenge_type: challenge_type.to_string(),
+            reward_list: Vec::new(),
+            special_reward: None,
+            time_info: HyvTimeInfo {
+                start_ts: "1".to_string(),
+                end_ts: "2".to_string(),
+            },
+        };
+        let mut challenges: Vec<_> = [
+            challenge(1010, "ChallengeTypeChasm"),
+            challenge(2009, APOCALYPTIC_SHADOW_TYPE),
+            challenge(2010, APOCALYPTIC_SHADOW_TYPE),
+        ]
+        .into_iter()
+        .map(transform_challenge)
+        .collect();
+
+        let details = vec![endgame_detail(shadow, APOCALYPTIC_SHADOW_TYPE).unwrap()];
+        merge_endgame(&mut challenges, details);
+
+        assert!(challenges[0].enemies.is_empty());
+        assert!(challenges[1].enemies.is_empty());
+        let enemies: Vec<_> = challenges[2]
+            .enemies
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(enemies, ["Phantylia", "Sam"]);
+        assert_eq!(challenges[2].buffs[0].description, "Toughness +1");
+        assert_eq!(
+            describe(&challenges[2].buffs, &challenges[2].enemies).as_deref(),
+            Some("Stalwart Heart: Toughness +1\nEnemies: Phantylia, Sam")
+        );
+    }
 }
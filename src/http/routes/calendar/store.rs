@@ -0,0 +1,146 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+
+use crate::games::Game;
+use crate::http::error::{ApiError, ApiErrorCode};
+
+use super::unified::CalendarEntry;
+use super::{genshin, honkai, starrail, zenless};
+
+/// The last good calendar per (game, language). Entries never expire: when
+/// a refresh fails the previous snapshot keeps being served, marked stale.
+pub struct CalendarStore {
+    snapshots: Cache<(Game, &'static str), Arc<CalendarSnapshot>>,
+}
+
+impl Default for CalendarStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalendarStore {
+    pub fn new() -> Self {
+        Self {
+            snapshots: Cache::builder().build(),
+        }
+    }
+
+    pub async fn get(&self, game: Game, lang: &'static str) -> Option<Arc<CalendarSnapshot>> {
+        self.snapshots.get(&(game, lang)).await
+    }
+
+    pub async fn insert(&self, game: Game, lang: &'static str, snapshot: CalendarSnapshot) {
+        self.snapshots
+            .insert((game, lang), Arc::new(snapshot))
+            .await;
+    }
+
+    /// The stored snapshot, or the result of `init` if there is none yet.
+    /// Concurrent callers for the same key share one `init`.
+    pub async fn get_or_try_insert<F>(
+        &self,
+        game: Game,
+        lang: &'static str,
+        init: F,
+    ) -> Result<Arc<CalendarSnapshot>, ApiError>
+    where
+        F: Future<Output = Result<CalendarSnapshot, ApiError>>,
+    {
+        self.snapshots
+            .try_get_with((game, lang), async { init.await.map(Arc::new) })
+            .await
+            .map_err(|e| (*e).clone())
+    }
+}
+
+/// A game calendar as served by the JSON endpoints.
+#[derive(Debug, Clone)]
+pub struct CalendarSnapshot {
+    pub game: Game,
+    /// The per-game calendar JSON object.
+    pub body: Bytes,
+    pub fetched_at: DateTime<Utc>,
+    /// The latest refresh failed, so this is older than it should be.
+    pub stale: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Freshness {
+    stale: bool,
+    fetched_at: DateTime<Utc>,
+}
+
+impl CalendarSnapshot {
+    pub fn new(game: Game, body: Bytes) -> Self {
+        Self {
+            game,
+            body,
+            fetched_at: Utc::now(),
+            stale: false,
+        }
+    }
+
+    /// The calendar JSON with `stale` and `fetched_at` added in front.
+    pub fn json(&self) -> Bytes {
+        let mut json = serde_json::to_vec(&Freshness {
+            stale: self.stale,
+            fetched_at: self.fetched_at,
+        })
+        .expect("Freshness is always serializable");
+
+        // Both are non-empty objects: drop the closing brace of one and the
+        // opening brace of the other.
+        debug_assert!(self.body.starts_with(b"{") && self.body.len() > 2);
+        json.pop();
+        json.push(b',');
+        json.extend_from_slice(&self.body[1..]);
+        Bytes::from(json)
+    }
+
+    /// The calendar in the cross-game schema.
+    pub fn entries(&self) -> Result<Vec<CalendarEntry>, ApiError> {
+        let entries = match self.game {
+            Game::Genshin => genshin::unified_entries(&self.body),
+            Game::Starrail => starrail::unified_entries(&self.body),
+            Game::Zenless => zenless::unified_entries(&self.body),
+            Game::Honkai => honkai::unified_entries(&self.body),
+            Game::Themis => Ok(Vec::new()),
+        };
+
+        entries.map_err(|error| {
+            tracing::error!(error = %error, game = self.game.slug(), "failed to read stored calendar");
+            ApiError::internal_server_error(
+                ApiErrorCode::UPSTREAM_ERROR,
+                "failed to build calendar",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_freshness_to_json() {
+        let mut snapshot = CalendarSnapshot::new(
+            Game::Genshin,
+            Bytes::from_static(br#"{"events":[],"banners":[],"challenges":[]}"#),
+        );
+        snapshot.stale = true;
+
+        let json: serde_json::Value = serde_json::from_slice(&snapshot.json()).unwrap();
+        assert_eq!(json["stale"], true);
+        assert_eq!(
+            json["fetched_at"],
+            serde_json::to_value(snapshot.fetched_at).unwrap()
+        );
+        assert_eq!(json["events"], serde_json::json!([]));
+        assert_eq!(json.as_object().unwrap().len(), 5);
+    }
+}
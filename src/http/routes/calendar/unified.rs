@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+
+use crate::games::Game;
+use crate::global::Global;
+use crate::http::error::{ApiError, ApiErrorCode};
+
+use super::{CALENDAR_GAMES, calendar_snapshot, check_server, resolve_lang};
+
+/// One event, banner or challenge in the cross-game calendar schema.
+#[derive(Debug, serde::Serialize)]
+pub struct CalendarEntry {
+    pub kind: EntryKind,
+    pub game: Game,
+    /// The activity ID. Zenless banners have none, so theirs is built from
+    /// the start time and featured item IDs.
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub image: Option<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub featured: Vec<FeaturedItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Event,
+    Banner,
+    Challenge,
+}
+
+impl EntryKind {
+    pub const ALL: [Self; 3] = [Self::Event, Self::Banner, Self::Challenge];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Event => "event",
+            Self::Banner => "banner",
+            Self::Challenge => "challenge",
+        }
+    }
+}
+
+/// A character or weapon on a banner. Star Rail light cones and Zenless
+/// W-Engines count as weapons, Zenless agents as characters.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FeaturedItem {
+    pub kind: FeaturedKind,
+    pub id: u64,
+    pub name: String,
+    pub image: String,
+    pub rarity: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeaturedKind {
+    Character,
+    Weapon,
+}
+
+impl CalendarEntry {
+    /// Whether the entry runs at `at` (Unix seconds). Entries without a known
+    /// start never match; a missing end means open-ended.
+    fn is_active_at(&self, at: i64) -> bool {
+        self.start.is_some_and(|start| start <= at) && self.end.is_none_or(|end| at < end)
+    }
+}
+
+/// The banner's thumbnail: its first featured item.
+pub(super) fn banner_image(featured: &[FeaturedItem]) -> Option<String> {
+    featured.first().map(|item| item.image.clone())
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(super) struct UnifiedQuery {
+    lang: Option<String>,
+    games: Option<String>,
+    active_at: Option<i64>,
+    server: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub(super) struct UnifiedResponse {
+    entries: Vec<CalendarEntry>,
+    /// Requested games whose calendar could not be fetched.
+    unavailable: Vec<Game>,
+    /// Whether any game's calendar is from before a failed refresh.
+    stale: bool,
+    /// When the oldest of the included calendars was fetched.
+    fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// GET /calendar
+///
+/// Events, banners and challenges of all calendar games in one schema.
+#[tracing::instrument(skip(global))]
+pub(super) async fn get_calendar(
+    Query(query): Query<UnifiedQuery>,
+    State(global): State<Arc<Global>>,
+) -> Result<Json<UnifiedResponse>, ApiError> {
+    check_server(query.server.as_deref())?;
+    let lang = resolve_lang(query.lang)?;
+    let games = parse_games(query.games.as_deref())?;
+
+    let global = &global;
+    let results = futures::future::join_all(games.iter().map(|&game| async move {
+        let snapshot = calendar_snapshot(global, game, lang).await?;
+        let entries = snapshot.entries()?;
+        Ok::<_, ApiError>((snapshot, entries))
+    }))
+    .await;
+
+    let mut entries = Vec::new();
+    let mut unavailable = Vec::new();
+    let mut stale = false;
+    let mut fetched_at = None;
+    for (game, result) in games.into_iter().zip(results) {
+        match result {
+            Ok((snapshot, game_entries)) => {
+                entries.extend(game_entries);
+                stale |= snapshot.stale;
+                fetched_at = Some(fetched_at.map_or(snapshot.fetched_at, |at| {
+                    std::cmp::min(at, snapshot.fetched_at)
+                }));
+            }
+            Err(error) => {
+                tracing::warn!(game = game.slug(), error = ?error, "calendar unavailable");
+                unavailable.push(game);
+            }
+        }
+    }
+
+    if let Some(at) = query.active_at {
+        entries.retain(|entry| entry.is_active_at(at));
+    }
+    entries.sort_by_key(|entry| (entry.start.is_none(), entry.start, entry.game.slug()));
+
+    Ok(Json(UnifiedResponse {
+        entries,
+        unavailable,
+        stale,
+        fetched_at,
+    }))
+}
+
+/// Parse `?games=genshin,zenless`. All calendar games when missing or empty.
+fn parse_games(games: Option<&str>) -> Result<Vec<Game>, ApiError> {
+    let Some(games) = games.filter(|s| !s.trim().is_empty()) else {
+        return Ok(CALENDAR_GAMES.to_vec());
+    };
+
+    let mut parsed = Vec::new();
+    for slug in games.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let game = Game::from_slug(slug)
+            .filter(|game| CALENDAR_GAMES.contains(game))
+            .ok_or_else(|| {
+                ApiError::bad_request(
+                    ApiErrorCode::INVALID_FILTER,
+                    format!("no calendar for game: {slug}"),
+                )
+            })?;
+        if !parsed.contains(&game) {
+            parsed.push(game);
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(start: Option<i64>, end: Option<i64>) -> CalendarEntry {
+        CalendarEntry {
+            kind: EntryKind::Event,
+            game: Game::Genshin,
+            id: "1".to_string(),
+            name: "Event".to_string(),
+            description: None,
+            version: None,
+            image: None,
+            start,
+            end,
+            featured: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn filters_active_entries() {
+        assert!(entry(Some(100), Some(200)).is_active_at(100));
+        assert!(!entry(Some(100), Some(200)).is_active_at(200));
+        assert!(entry(Some(100), None).is_active_at(1_000));
+        assert!(!entry(None, Some(200)).is_active_at(150));
+    }
+
+    #[test]
+    fn parses_games_filter() {
+        assert_eq!(parse_games(None).unwrap(), CALENDAR_GAMES.to_vec());
+        assert_eq!(
+            parse_games(Some("zenless, genshin,zenless")).unwrap(),
+            vec![Game::Zenless, Game::Genshin]
+        );
+        assert!(parse_games(Some("themis")).is_err());
+        assert!(parse_games(Some("unknown")).is_err());
+    }
+}
@@ -5,21 +5,15 @@ use axum::body::{Body, Bytes};
 use axum::extract::{Query, State};
 use axum::http::Response;
 use chrono::{FixedOffset, TimeZone, Utc};
-use serde::de::DeserializeOwned;
 
-use crate::games::zenless;
+use crate::games::{Game, zenless};
 use crate::global::Global;
+use crate::hoyolab::{HeaderPreset, HoyolabRequest};
 use crate::http::error::{ApiError, ApiErrorCode};
 use crate::http::routes::json_response;
 
-use super::{LangQuery, cookie_with_lang, resolve_lang, try_fetch_fandom_images};
-
-#[derive(serde::Deserialize)]
-struct HyvActivityResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HyvActivityData>,
-}
+use super::unified::{CalendarEntry, EntryKind, FeaturedItem, FeaturedKind, banner_image};
+use super::{LangQuery, calendar_snapshot, check_server, resolve_lang, try_fetch_fandom_images};
 
 #[derive(serde::Deserialize)]
 struct HyvActivityData {
@@ -36,13 +30,6 @@ struct HyvActivity {
     end_ts: i64,
 }
 
-#[derive(serde::Deserialize)]
-struct HyvGachaResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HyvGachaData>,
-}
-
 #[derive(serde::Deserialize)]
 struct HyvGachaData {
     avatar_gacha_schedule_list: Vec<HyvAvatarGacha>,
@@ -89,13 +76,6 @@ struct HyvWeapon {
     profession: u8,
 }
 
-#[derive(serde::Deserialize)]
-struct HyvChallengeResponse<T> {
-    retcode: i32,
-    message: String,
-    data: Option<T>,
-}
-
 #[derive(serde::Deserialize)]
 struct HyvDateTime {
     year: i32,
@@ -130,14 +110,14 @@ struct HyvShiyuPeriod {
     hadal_end_time: Option<HyvDateTime>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct CalendarResponse {
     events: Vec<Event>,
     banners: Vec<Banner>,
     challenges: Vec<Challenge>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Event {
     id: u64,
     name: String,
@@ -148,7 +128,7 @@ struct Event {
     polychrome: u64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Banner {
     banner_type: String,
     state: String,
@@ -159,7 +139,7 @@ struct Banner {
     end_time: i64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Agent {
     id: u64,
     name: String,
@@ -170,7 +150,7 @@ struct Agent {
     element: String,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct WEngine {
     id: u64,
     name: String,
@@ -179,7 +159,7 @@ struct WEngine {
     profession: String,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, serde::Deserialize)]
 struct Challenge {
     id: u64,
     name: String,
@@ -406,69 +386,6 @@ fn transform_challenges(
         .collect()
 }
 
-async fn fetch_challenge<T>(request: reqwest::RequestBuilder, challenge: &'static str) -> Option<T>
-where
-    T: DeserializeOwned,
-{
-    let response = match request.send().await {
-        Ok(response) => response,
-        Err(error) => {
-            tracing::error!(error = %error, challenge, "failed to fetch zenless challenge");
-            return None;
-        }
-    };
-    let response: HyvChallengeResponse<T> = match response.json().await {
-        Ok(response) => response,
-        Err(error) => {
-            tracing::error!(error = %error, challenge, "failed to parse zenless challenge response");
-            return None;
-        }
-    };
-    if response.retcode != 0 {
-        tracing::error!(retcode = response.retcode, message = %response.message, challenge, "hoyoverse zenless challenge API error");
-        return None;
-    }
-    if response.data.is_none() {
-        tracing::error!(
-            challenge,
-            "hoyoverse zenless challenge API returned no data"
-        );
-    }
-    response.data
-}
-
-async fn fetch_activity_data(
-    request: reqwest::RequestBuilder,
-    request_lang: &str,
-) -> Result<HyvActivityData, ApiError> {
-    let response = request.send().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to fetch zenless activity calendar");
-        ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to fetch calendar")
-    })?;
-    let response: HyvActivityResponse = response.json().await.map_err(|error| {
-        tracing::error!(error = %error, request_lang, "failed to parse zenless activity calendar response");
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "failed to parse calendar response",
-        )
-    })?;
-
-    if response.retcode != 0 {
-        tracing::error!(retcode = response.retcode, message = %response.message, request_lang, "hoyoverse zenless activity calendar API error");
-        return Err(ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned an error",
-        ));
-    }
-
-    response.data.ok_or_else(|| {
-        ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "calendar API returned no data",
-        )
-    })
-}
-
 fn canonical_activities(data: &HyvActivityData) -> Vec<(u64, String)> {
     let mut activities: Vec<_> = data
         .activity_list
@@ -630,227 +547,246 @@ pub(super) async fn get_zenless_calendar(
     Query(query): Query<LangQuery>,
     State(global): State<Arc<Global>>,
 ) -> Result<Response<Body>, ApiError> {
+    check_server(query.server.as_deref())?;
     let lang = resolve_lang(query.lang)?;
-    let cache_key = format!("/mihoyo/zenless/calendar/{lang}");
-
-    let bytes = global
-        .response_cache
-        .get_or_try_insert(cache_key, async {
-            let game_config = global
-                .config
-                .validator
-                .game_config(crate::games::Game::Zenless)
-                .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
-                .ok_or_else(|| {
+    let snapshot = calendar_snapshot(&global, Game::Zenless, lang).await?;
+    Ok(json_response(snapshot.json()))
+}
+
+/// Fetch the calendar JSON for `lang` from HoYoLab.
+pub(super) async fn fetch_calendar(
+    global: &Arc<Global>,
+    lang: &'static str,
+) -> Result<Bytes, ApiError> {
+    let game_config = global
+        .config
+        .validator
+        .game_config(Game::Zenless)
+        .filter(|c| !c.cookie.is_empty() && !c.uid.is_empty())
+        .ok_or_else(|| {
+            ApiError::internal_server_error(
+                ApiErrorCode::NOT_CONFIGURED,
+                "zenless calendar credentials not configured",
+            )
+        })?;
+
+    let uid = game_config.uid.as_str();
+    let region = game_config.region.as_str();
+    let request = |endpoint: &'static str, url: &'static str, lang: &str| {
+        HoyolabRequest::get(&global.http_client, endpoint, url)
+            .localized_cookie(&game_config.cookie, lang)
+            .preset(HeaderPreset::ZenlessRecord, lang)
+    };
+
+    let activity_data: HyvActivityData = request(
+        "zenless_activity_calendar",
+        zenless::ACTIVITY_CALENDAR_API,
+        lang,
+    )
+    .query(&[("uid", uid), ("region", region), ("lang", lang)])
+    .send()
+    .await?;
+    let gacha_data: HyvGachaData =
+        request("zenless_gacha_calendar", zenless::GACHA_CALENDAR_API, lang)
+            .query(&[("uid", uid), ("region", region), ("lang", lang)])
+            .send()
+            .await?;
+
+    let deadly_request = request("zenless_deadly_assault", zenless::DEADLY_ASSAULT_API, lang)
+        .query(&[
+            ("uid", uid),
+            ("region", region),
+            ("schedule_type", "1"),
+            ("lang", lang),
+        ]);
+    let threshold_request = request(
+        "zenless_threshold_simulation",
+        zenless::THRESHOLD_SIMULATION_API,
+        lang,
+    )
+    .query(&[
+        ("region", region),
+        ("uid", uid),
+        ("schedule_type", "1"),
+        ("lang", lang),
+    ]);
+    let shiyu_request =
+        request("zenless_shiyu_defense", zenless::SHIYU_DEFENSE_API, lang).query(&[
+            ("server", region),
+            ("role_id", uid),
+            ("schedule_type", "1"),
+            ("without_v2_detail", "true"),
+            ("lang", lang),
+        ]);
+    let annihilation_request = request(
+        "zenless_annihilation_simulacrum",
+        zenless::ANNIHILATION_SIMULACRUM_API,
+        lang,
+    )
+    .query(&[
+        ("region", region),
+        ("uid", uid),
+        ("schedule_type", "1"),
+        ("lang", lang),
+    ]);
+
+    let (deadly, threshold, shiyu, annihilation) = tokio::join!(
+        deadly_request.send::<HyvChallengePeriod>(),
+        threshold_request.send::<HyvThresholdData>(),
+        shiyu_request.send::<HyvShiyuData>(),
+        annihilation_request.send::<HyvChallengePeriod>(),
+    );
+    let challenges = transform_challenges(
+        deadly.ok(),
+        threshold.ok(),
+        shiyu.ok(),
+        annihilation.ok(),
+        region,
+        lang,
+    );
+
+    let canonical_activities = if lang == "en-us" {
+        canonical_activities(&activity_data)
+    } else {
+        let english_request = request(
+            "zenless_activity_calendar",
+            zenless::ACTIVITY_CALENDAR_API,
+            "en-us",
+        )
+        .query(&[("uid", uid), ("region", region), ("lang", "en-us")]);
+        match english_request.send::<HyvActivityData>().await {
+            Ok(data) => canonical_activities(&data),
+            Err(error) => {
+                tracing::warn!(error = ?error, "failed to fetch canonical zenless activity names");
+                Vec::new()
+            }
+        }
+    };
+
+    let image_map = if canonical_activities.is_empty() {
+        HashMap::new()
+    } else {
+        let names: Vec<String> = canonical_activities
+            .iter()
+            .map(|(_, name)| name.clone())
+            .collect();
+        let cache_key = fandom_cache_key(&canonical_activities);
+        let fandom_bytes = global
+            .fandom_image_cache
+            .get_or_try_insert(cache_key, async {
+                let map = try_fetch_fandom_images(
+                    &global.http_client,
+                    "https://zenless-zone-zero.fandom.com/api.php",
+                    "File:Event ",
+                    ".png",
+                    &names,
+                )
+                .await
+                .map_err(|error| {
+                    tracing::warn!(error = %error, "failed to fetch zenless fandom images");
                     ApiError::internal_server_error(
-                        ApiErrorCode::NOT_CONFIGURED,
-                        "zenless calendar credentials not configured",
+                        ApiErrorCode::UPSTREAM_ERROR,
+                        "failed to fetch fandom images",
                     )
                 })?;
-
-            let cookie = cookie_with_lang(&game_config.cookie, lang);
-
-            let activity_request = global
-                .http_client
-                .get(zenless::ACTIVITY_CALENDAR_API)
-                .query(&[
-                    ("uid", game_config.uid.as_str()),
-                    ("region", game_config.region.as_str()),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie.clone())
-                .header("x-rpc-language", lang);
-            let activity_data = fetch_activity_data(activity_request, lang).await?;
-
-            let gacha_resp = global
-                .http_client
-                .get(zenless::GACHA_CALENDAR_API)
-                .query(&[
-                    ("uid", game_config.uid.as_str()),
-                    ("region", game_config.region.as_str()),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie.clone())
-                .header("x-rpc-language", lang)
-                .send()
-                .await
-                .map_err(|e| {
-                    tracing::error!(error = %e, "failed to fetch zenless gacha calendar");
+                let bytes = serde_json::to_vec(&map).map_err(|error| {
+                    tracing::warn!(error = %error, "failed to serialize zenless fandom images");
                     ApiError::internal_server_error(
                         ApiErrorCode::UPSTREAM_ERROR,
-                        "failed to fetch calendar",
+                        "failed to cache fandom images",
                     )
                 })?;
-
-            let gacha_resp: HyvGachaResponse = gacha_resp.json().await.map_err(|e| {
-                tracing::error!(error = %e, "failed to parse zenless gacha calendar response");
-                ApiError::internal_server_error(
-                    ApiErrorCode::UPSTREAM_ERROR,
-                    "failed to parse calendar response",
-                )
-            })?;
-
-            if gacha_resp.retcode != 0 {
-                tracing::error!(retcode = gacha_resp.retcode, message = %gacha_resp.message, "hoyoverse zenless gacha calendar API error");
-                return Err(ApiError::internal_server_error(
-                    ApiErrorCode::UPSTREAM_ERROR,
-                    "calendar API returned an error",
-                ));
+                Ok(Bytes::from(bytes))
+            })
+            .await;
+        let fandom_images: HashMap<String, String> = match fandom_bytes {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(error) => {
+                tracing::warn!(error = ?error, "zenless fandom images unavailable");
+                HashMap::new()
             }
+        };
+        map_activity_images(&canonical_activities, &fandom_images)
+    };
 
-            let gacha_data = gacha_resp.data.ok_or_else(|| {
-                ApiError::internal_server_error(
-                    ApiErrorCode::UPSTREAM_ERROR,
-                    "calendar API returned no data",
-                )
-            })?;
-
-            let deadly_request = global
-                .http_client
-                .get(zenless::DEADLY_ASSAULT_API)
-                .query(&[
-                    ("uid", game_config.uid.as_str()),
-                    ("region", game_config.region.as_str()),
-                    ("schedule_type", "1"),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie.clone())
-                .header("x-rpc-lang", lang)
-                .header("x-rpc-language", lang);
-            let threshold_request = global
-                .http_client
-                .get(zenless::THRESHOLD_SIMULATION_API)
-                .query(&[
-                    ("region", game_config.region.as_str()),
-                    ("uid", game_config.uid.as_str()),
-                    ("schedule_type", "1"),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie.clone())
-                .header("x-rpc-lang", lang)
-                .header("x-rpc-language", lang);
-            let shiyu_request = global
-                .http_client
-                .get(zenless::SHIYU_DEFENSE_API)
-                .query(&[
-                    ("server", game_config.region.as_str()),
-                    ("role_id", game_config.uid.as_str()),
-                    ("schedule_type", "1"),
-                    ("without_v2_detail", "true"),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie.clone())
-                .header("x-rpc-lang", lang)
-                .header("x-rpc-language", lang);
-            let annihilation_request = global
-                .http_client
-                .get(zenless::ANNIHILATION_SIMULACRUM_API)
-                .query(&[
-                    ("region", game_config.region.as_str()),
-                    ("uid", game_config.uid.as_str()),
-                    ("schedule_type", "1"),
-                    ("lang", lang),
-                ])
-                .header("Cookie", cookie)
-                .header("x-rpc-lang", lang)
-                .header("x-rpc-language", lang);
-
-            let (deadly, threshold, shiyu, annihilation) = tokio::join!(
-                fetch_challenge::<HyvChallengePeriod>(deadly_request, "deadly assault"),
-                fetch_challenge::<HyvThresholdData>(threshold_request, "threshold simulation"),
-                fetch_challenge::<HyvShiyuData>(shiyu_request, "shiyu defense"),
-                fetch_challenge::<HyvChallengePeriod>(
-                    annihilation_request,
-                    "annihilation simulacrum"
-                ),
-            );
-            let challenges = transform_challenges(
-                deadly,
-                threshold,
-                shiyu,
-                annihilation,
-                &game_config.region,
-                lang,
-            );
-
-            let canonical_activities = if lang == "en-us" {
-                canonical_activities(&activity_data)
-            } else {
-                let english_cookie = cookie_with_lang(&game_config.cookie, "en-us");
-                let english_request = global
-                    .http_client
-                    .get(zenless::ACTIVITY_CALENDAR_API)
-                    .query(&[
-                        ("uid", game_config.uid.as_str()),
-                        ("region", game_config.region.as_str()),
-                        ("lang", "en-us"),
-                    ])
-                    .header("Cookie", english_cookie)
-                    .header("x-rpc-language", "en-us");
-                match fetch_activity_data(english_request, "en-us").await {
-                    Ok(data) => canonical_activities(&data),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "failed to fetch canonical zenless activity names");
-                        Vec::new()
-                    }
-                }
-            };
-
-            let image_map = if canonical_activities.is_empty() {
-                HashMap::new()
-            } else {
-                let names: Vec<String> = canonical_activities
-                    .iter()
-                    .map(|(_, name)| name.clone())
-                    .collect();
-                let cache_key = fandom_cache_key(&canonical_activities);
-                let fandom_bytes = global
-                    .fandom_image_cache
-                    .get_or_try_insert(cache_key, async {
-                        let map = try_fetch_fandom_images(
-                            &global.http_client,
-                            "https://zenless-zone-zero.fandom.com/api.php",
-                            "File:Event ",
-                            ".png",
-                            &names,
-                        )
-                        .await
-                        .map_err(|error| {
-                            tracing::warn!(error = %error, "failed to fetch zenless fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to fetch fandom images",
-                            )
-                        })?;
-                        let bytes = serde_json::to_vec(&map).map_err(|error| {
-                            tracing::warn!(error = %error, "failed to serialize zenless fandom images");
-                            ApiError::internal_server_error(
-                                ApiErrorCode::UPSTREAM_ERROR,
-                                "failed to cache fandom images",
-                            )
-                        })?;
-                        Ok(Bytes::from(bytes))
-                    })
-                    .await;
-                let fandom_images: HashMap<String, String> = match fandom_bytes {
-                    Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
-                    Err(error) => {
-                        tracing::warn!(error = ?error, "zenless fandom images unavailable");
-                        HashMap::new()
-                    }
-                };
-                map_activity_images(&canonical_activities, &fandom_images)
-            };
-
-            let calendar =
-                transform_calendar(activity_data, gacha_data, challenges, &image_map);
-            Ok(Bytes::from(
-                serde_json::to_vec(&calendar).expect("CalendarResponse is always serializable"),
-            ))
-        })
-        .await?;
+    let calendar = transform_calendar(activity_data, gacha_data, challenges, &image_map);
+    Ok(Bytes::from(
+        serde_json::to_vec(&calendar).expect("CalendarResponse is always serializable"),
+    ))
+}
+
+/// Map the cached calendar JSON onto the cross-game schema.
+pub(super) fn unified_entries(bytes: &[u8]) -> serde_json::Result<Vec<CalendarEntry>> {
+    let calendar: CalendarResponse = serde_json::from_slice(bytes)?;
+    let game = Game::Zenless;
+
+    let events = calendar.events.into_iter().map(|event| CalendarEntry {
+        kind: EntryKind::Event,
+        game,
+        id: event.id.to_string(),
+        name: event.name,
+        description: None,
+        version: None,
+        image: event.image_url,
+        start: Some(event.start_time),
+        end: Some(event.end_time),
+        featured: Vec::new(),
+    });
+
+    let banners = calendar.banners.into_iter().map(|banner| {
+        let featured: Vec<_> = banner
+            .agents
+            .into_iter()
+            .map(|a| FeaturedItem {
+                kind: FeaturedKind::Character,
+                id: a.id,
+                name: a.name,
+                image: a.icon,
+                rarity: a.rarity,
+            })
+            .chain(banner.w_engines.into_iter().map(|w| FeaturedItem {
+                kind: FeaturedKind::Weapon,
+                id: w.id,
+                name: w.name,
+                image: w.icon,
+                rarity: w.rarity,
+            }))
+            .collect();
+        let id = std::iter::once(banner.start_time.to_string())
+            .chain(featured.iter().map(|item| item.id.to_string()))
+            .collect::<Vec<_>>()
+            .join("-");
+        CalendarEntry {
+            kind: EntryKind::Banner,
+            game,
+            id,
+            name: banner.banner_type,
+            description: None,
+            version: Some(banner.version),
+            image: banner_image(&featured),
+            start: Some(banner.start_time),
+            end: Some(banner.end_time),
+            featured,
+        }
+    });
 
-    Ok(json_response(bytes))
+    let challenges = calendar
+        .challenges
+        .into_iter()
+        .map(|challenge| CalendarEntry {
+            kind: EntryKind::Challenge,
+            game,
+            id: challenge.id.to_string(),
+            name: challenge.name,
+            description: None,
+            version: None,
+            image: None,
+            start: challenge.start_time,
+            end: challenge.end_time,
+            featured: Vec::new(),
+        });
+
+    Ok(events.chain(banners).chain(challenges).collect())
 }
 
 #[cfg(test)]
@@ -1,22 +1,34 @@
+use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::Router;
 use axum::body::{Body, Bytes};
-use axum::extract::{ConnectInfo, Path, State};
-use axum::http::Response;
-use axum::routing::get;
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{HeaderMap, Response, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::{Stream, StreamExt as _};
+use tokio::sync::broadcast::error::RecvError;
 use tower_governor::GovernorLayer;
 use tower_governor::errors::GovernorError;
 use tower_governor::governor::GovernorConfigBuilder;
 use tower_governor::key_extractor::KeyExtractor;
 
 use crate::config::RateLimitConfig;
+use crate::database::code_event::{CodeEvent, CodeEventResponse};
 use crate::database::redemption_code::{RedemptionCode, RedemptionCodeResponse};
+use crate::database::validation_job::{JobKind, JobStatus, ValidationJob};
+use crate::feed::FeedEvent;
 use crate::games::Game;
-use crate::global::Global;
+use crate::games::region::Region;
+use crate::games::server::Server;
+use crate::global::{Global, codes_cache_key};
 use crate::http::error::{ApiError, ApiErrorCode};
 use crate::http::routes::json_response;
+use crate::rewards::Reward;
+use crate::validator;
+use crate::validator::hoyoverse_api::RedeemOutcome;
 
 #[derive(Clone)]
 struct CloudflareIp;
@@ -49,19 +61,66 @@ impl KeyExtractor for CloudflareIp {
     }
 }
 
-pub fn routes(rate_limit: &RateLimitConfig) -> Router<Arc<Global>> {
-    let governor = Arc::new(
-        GovernorConfigBuilder::default()
-            .per_second(rate_limit.per_second)
-            .burst_size(rate_limit.burst_size)
-            .key_extractor(CloudflareIp)
-            .finish()
-            .unwrap(),
-    );
+pub fn routes(
+    rate_limit: &RateLimitConfig,
+    submit_rate_limit: &RateLimitConfig,
+) -> Router<Arc<Global>> {
+    let governor_layer = |rate_limit: &RateLimitConfig| {
+        let governor = Arc::new(
+            GovernorConfigBuilder::default()
+                .per_second(rate_limit.per_second)
+                .burst_size(rate_limit.burst_size)
+                .key_extractor(CloudflareIp)
+                .finish()
+                .unwrap(),
+        );
+        GovernorLayer { config: governor }
+    };
+
+    let submissions = Router::new()
+        .route("/:game/codes/submit", post(submit_code))
+        .layer(governor_layer(submit_rate_limit));
 
     Router::new()
         .route("/:game/codes", get(get_codes))
-        .layer(GovernorLayer { config: governor })
+        .route("/:game/codes/stream", get(stream_game_codes))
+        .route("/codes/stream", get(stream_all_codes))
+        .route("/:game/codes/submit/:id", get(get_submission))
+        .route("/:game/codes/:code/history", get(get_code_history))
+        .layer(governor_layer(rate_limit))
+        .merge(submissions)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CodesQuery {
+    region: Option<String>,
+    server: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ServerQuery {
+    server: Option<String>,
+}
+
+impl ServerQuery {
+    /// The requested server, global when none is given.
+    pub fn resolve(&self, game: Game) -> Result<Server, ApiError> {
+        resolve_server(game, self.server.as_deref())
+    }
+}
+
+fn resolve_server(game: Game, server: Option<&str>) -> Result<Server, ApiError> {
+    let Some(server) = server else {
+        return Ok(Server::Global);
+    };
+    Server::parse(server)
+        .filter(|s| game.servers().contains(s))
+        .ok_or_else(|| {
+            ApiError::bad_request(
+                ApiErrorCode::INVALID_SERVER,
+                "unknown server or server not tracked for this game",
+            )
+        })
 }
 
 #[derive(serde::Serialize)]
@@ -73,20 +132,34 @@ struct CodesResponse {
 /// GET /mihoyo/:game/codes
 ///
 /// Returns all redemption codes for the given game, split by active/inactive.
+/// `?server=cn` returns the mainland China codes instead of the overseas ones.
+/// With `?region=`, codes locked to other regions are left out.
 #[tracing::instrument(skip(global))]
 async fn get_codes(
     State(global): State<Arc<Global>>,
     Path(game_slug): Path<String>,
+    Query(query): Query<CodesQuery>,
 ) -> Result<Response<Body>, ApiError> {
     let game = Game::from_slug(&game_slug)
         .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
 
-    let cache_key = format!("/mihoyo/{game_slug}/codes");
+    let server = resolve_server(game, query.server.as_deref())?;
+    let region = query
+        .region
+        .as_deref()
+        .map(|r| {
+            Region::parse(r).ok_or_else(|| {
+                ApiError::bad_request(ApiErrorCode::INVALID_REGION, "unknown region")
+            })
+        })
+        .transpose()?;
+
+    let cache_key = codes_cache_key(game, server, region);
 
     let bytes = global
         .response_cache
         .get_or_try_insert(cache_key, async {
-            let all_codes = RedemptionCode::find_all(&global.db, game)
+            let all_codes = RedemptionCode::find_all(&global.db, game, server)
                 .await
                 .map_err(|e| {
                     tracing::error!(error = %e, "failed to query codes");
@@ -96,11 +169,23 @@ async fn get_codes(
                     )
                 })?;
 
-            let (active, inactive): (Vec<_>, Vec<_>) =
-                all_codes.into_iter().partition(|c| c.active);
+            // Codes not touched by a scrape since structured rewards were
+            // introduced only carry the legacy strings.
+            let to_response = |mut code: RedemptionCode| {
+                if code.reward_items.is_empty() {
+                    code.reward_items = Reward::parse_all(game, &code.rewards);
+                }
+                RedemptionCodeResponse::from(code)
+            };
+
+            let (active, inactive): (Vec<_>, Vec<_>) = all_codes
+                .into_iter()
+                .filter(|c| !c.pending)
+                .filter(|c| region.is_none_or(|r| c.available_in(r)))
+                .partition(|c| c.active);
             let response = CodesResponse {
-                active: active.into_iter().map(Into::into).collect(),
-                inactive: inactive.into_iter().map(Into::into).collect(),
+                active: active.into_iter().map(to_response).collect(),
+                inactive: inactive.into_iter().map(to_response).collect(),
             };
 
             Ok(Bytes::from(
@@ -111,3 +196,318 @@ async fn get_codes(
 
     Ok(json_response(bytes))
 }
+
+/// GET /mihoyo/:game/codes/:code/history
+///
+/// Returns every recorded lifecycle event for a code, oldest first.
+/// `?server=cn` returns the history of the CN code.
+#[tracing::instrument(skip(global))]
+async fn get_code_history(
+    State(global): State<Arc<Global>>,
+    Path((game_slug, code)): Path<(String, String)>,
+    Query(query): Query<ServerQuery>,
+) -> Result<Json<Vec<CodeEventResponse>>, ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+    let server = query.resolve(game)?;
+
+    let events = CodeEvent::find_for_code(&global.db, game, server, &code.to_uppercase())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "failed to query code history");
+            ApiError::internal_server_error(
+                ApiErrorCode::DATABASE_ERROR,
+                "failed to query code history",
+            )
+        })?;
+
+    if events.is_empty() {
+        return Err(ApiError::not_found(
+            ApiErrorCode::UNKNOWN_CODE,
+            "no history recorded for this code",
+        ));
+    }
+
+    Ok(Json(events.into_iter().map(Into::into).collect()))
+}
+
+/// GET /mihoyo/:game/codes/stream
+///
+/// Server-Sent Events stream of codes being added or deactivated.
+#[tracing::instrument(skip(global, headers))]
+async fn stream_game_codes(
+    State(global): State<Arc<Global>>,
+    Path(game_slug): Path<String>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+
+    Ok(code_stream(&global, Some(game), &headers))
+}
+
+/// GET /mihoyo/codes/stream
+///
+/// Same as the per-game stream, for every game.
+#[tracing::instrument(skip(global, headers))]
+async fn stream_all_codes(
+    State(global): State<Arc<Global>>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    code_stream(&global, None, &headers)
+}
+
+/// Build the event stream, replaying buffered events after the client's
+/// `Last-Event-ID` first. A client that falls too far behind is
+/// disconnected so that it reconnects and resumes from the replay buffer.
+fn code_stream(
+    global: &Global,
+    game: Option<Game>,
+    headers: &HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>> + use<>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let (replay, receiver) = global.code_feed.subscribe(last_event_id);
+
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((event, receiver)),
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::debug!(skipped, "code stream client lagged, disconnecting");
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    });
+
+    let stream = futures::stream::iter(replay)
+        .chain(live)
+        .filter(move |event| std::future::ready(game.is_none_or(|g| event.game == g)))
+        .map(|event| Ok(sse_event(&event)));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn sse_event(event: &FeedEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.kind.name())
+        .data(serde_json::to_string(event).expect("FeedEvent is always serializable"))
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SubmitCodeRequest {
+    code: String,
+}
+
+#[derive(serde::Serialize)]
+struct SubmissionResponse {
+    /// `None` when the code was already known and nothing was queued.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    status: SubmissionStatus,
+    /// Whether the code was stored; `None` until validation has finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accepted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<RedeemOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poll: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SubmissionStatus {
+    /// The code is already tracked.
+    Exists,
+    Pending,
+    Running,
+    Done,
+    /// Validation could not be completed; the code was not stored.
+    Failed,
+}
+
+impl SubmissionResponse {
+    fn from_job(game: Game, id: String, job: &ValidationJob) -> Self {
+        let status = match job.status {
+            JobStatus::Pending => SubmissionStatus::Pending,
+            JobStatus::Running => SubmissionStatus::Running,
+            JobStatus::Done => SubmissionStatus::Done,
+            JobStatus::Failed => SubmissionStatus::Failed,
+        };
+        let accepted = match job.status {
+            JobStatus::Done => Some(job.stored),
+            JobStatus::Failed => Some(false),
+            JobStatus::Pending | JobStatus::Running => None,
+        };
+
+        Self {
+            poll: Some(submission_url(game, &id)),
+            id: Some(id),
+            status,
+            accepted,
+            outcome: job.outcome,
+        }
+    }
+}
+
+fn submission_url(game: Game, id: &str) -> String {
+    format!("/mihoyo/{}/codes/submit/{id}", game.slug())
+}
+
+fn normalize_submission(code: &str) -> Result<String, ApiError> {
+    let code = code.trim().to_uppercase();
+    if !(4..=32).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::INVALID_BODY,
+            "code must be 4-32 letters or digits",
+        ));
+    }
+    Ok(code)
+}
+
+/// POST /mihoyo/:game/codes/submit
+///
+/// Queues a user-submitted code for validation. It is only stored if the
+/// redemption API accepts it; poll the returned URL for the result. CN codes
+/// are submitted with `?server=cn`.
+#[tracing::instrument(skip(global))]
+async fn submit_code(
+    State(global): State<Arc<Global>>,
+    Path(game_slug): Path<String>,
+    Query(query): Query<ServerQuery>,
+    Json(body): Json<SubmitCodeRequest>,
+) -> Result<(StatusCode, Json<SubmissionResponse>), ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+    let server = query.resolve(game)?;
+
+    if !validator::validation_enabled(&global, game, server) {
+        return Err(ApiError::not_found(
+            ApiErrorCode::NOT_CONFIGURED,
+            "code submission is not available for this game",
+        ));
+    }
+
+    let code = normalize_submission(&body.code)?;
+
+    let database_error = |e: anyhow::Error| {
+        tracing::error!(error = %e, "failed to queue code submission");
+        ApiError::internal_server_error(ApiErrorCode::DATABASE_ERROR, "failed to queue code")
+    };
+
+    if RedemptionCode::exists(&global.db, game, server, &code)
+        .await
+        .map_err(database_error)?
+    {
+        return Ok((
+            StatusCode::OK,
+            Json(SubmissionResponse {
+                id: None,
+                status: SubmissionStatus::Exists,
+                accepted: None,
+                outcome: None,
+                poll: None,
+            }),
+        ));
+    }
+
+    let (id, _) = ValidationJob::new(game, server, &code, JobKind::Submission, server.regions())
+        .enqueue(&global.db)
+        .await
+        .map_err(database_error)?;
+    metrics::counter!("submissions_received_total", "game" => game.slug()).increment(1);
+
+    let id = id.to_hex();
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SubmissionResponse {
+            poll: Some(submission_url(game, &id)),
+            id: Some(id),
+            status: SubmissionStatus::Pending,
+            accepted: None,
+            outcome: None,
+        }),
+    ))
+}
+
+/// GET /mihoyo/:game/codes/submit/:id
+///
+/// Returns the validation status of a submitted code.
+#[tracing::instrument(skip(global))]
+async fn get_submission(
+    State(global): State<Arc<Global>>,
+    Path((game_slug, id)): Path<(String, String)>,
+) -> Result<Json<SubmissionResponse>, ApiError> {
+    let game = Game::from_slug(&game_slug)
+        .ok_or_else(|| ApiError::not_found(ApiErrorCode::UNKNOWN_GAME, "unknown game"))?;
+
+    let unknown = || ApiError::not_found(ApiErrorCode::UNKNOWN_SUBMISSION, "unknown submission");
+    let object_id = bson::oid::ObjectId::parse_str(&id).map_err(|_| unknown())?;
+
+    let job = ValidationJob::find_by_id(&global.db, game, object_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "failed to query code submission");
+            ApiError::internal_server_error(
+                ApiErrorCode::DATABASE_ERROR,
+                "failed to query submission",
+            )
+        })?
+        .ok_or_else(unknown)?;
+
+    Ok(Json(SubmissionResponse::from_job(game, id, &job)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_cache_by_server_and_region() {
+        assert_eq!(
+            codes_cache_key(Game::Genshin, Server::Global, None),
+            "/mihoyo/genshin/codes"
+        );
+        assert_eq!(
+            codes_cache_key(Game::Genshin, Server::Cn, Some(Region::Cn)),
+            "/mihoyo/genshin/codes?server=cn&region=cn"
+        );
+        assert!(resolve_server(Game::Honkai, Some("cn")).is_err());
+    }
+
+    #[test]
+    fn reports_whether_submission_was_stored() {
+        let mut job = ValidationJob::new(
+            Game::Genshin,
+            Server::Global,
+            "GENSHINGIFT",
+            JobKind::Submission,
+            Vec::new(),
+        );
+        job.status = JobStatus::Done;
+        job.outcome = Some(RedeemOutcome::AlreadyRedeemed);
+        job.stored = true;
+        let response = SubmissionResponse::from_job(Game::Genshin, "id".to_string(), &job);
+        assert_eq!(response.accepted, Some(true));
+
+        job.outcome = Some(RedeemOutcome::Valid);
+        job.stored = false;
+        let response = SubmissionResponse::from_job(Game::Genshin, "id".to_string(), &job);
+        assert_eq!(response.accepted, Some(false));
+    }
+
+    #[test]
+    fn normalizes_submitted_codes() {
+        assert_eq!(
+            normalize_submission(" genshingift ").unwrap(),
+            "GENSHINGIFT"
+        );
+        assert!(normalize_submission("abc").is_err());
+        assert!(normalize_submission("NOT A CODE").is_err());
+        assert!(normalize_submission(&"A".repeat(33)).is_err());
+    }
+}
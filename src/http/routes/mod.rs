@@ -10,6 +10,7 @@ use hyper::StatusCode;
 use crate::games::Game;
 use crate::global::Global;
 
+pub mod admin;
 pub mod calendar;
 pub mod codes;
 pub mod news;
@@ -25,7 +26,10 @@ pub(super) fn json_response(bytes: Bytes) -> Response<Body> {
 pub fn routes(global: &Arc<Global>) -> Router<Arc<Global>> {
     Router::new()
         .route("/", get(root))
-        .merge(codes::routes(&global.config.api.rate_limit))
+        .merge(codes::routes(
+            &global.config.api.rate_limit,
+            &global.config.api.submit_rate_limit,
+        ))
         .merge(calendar::routes())
         .merge(news::routes())
         .route_layer(axum::middleware::from_fn(super::track_client))
@@ -8,6 +8,7 @@ use axum::routing::get;
 
 use crate::games::Game;
 use crate::global::Global;
+use crate::hoyolab::{HeaderPreset, HoyolabRequest};
 use crate::http::error::{ApiError, ApiErrorCode};
 use crate::http::routes::json_response;
 use serde::Deserialize;
@@ -78,13 +79,6 @@ pub fn routes() -> Router<Arc<Global>> {
         .route("/:game/news/info", get(get_info))
 }
 
-#[derive(serde::Deserialize)]
-struct HylEventResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HylEventData>,
-}
-
 #[derive(serde::Deserialize)]
 struct HylEventData {
     list: Vec<HylEvent>,
@@ -101,13 +95,6 @@ struct HylEvent {
     web_path: String,
 }
 
-#[derive(serde::Deserialize)]
-struct HylNewsResponse {
-    retcode: i32,
-    message: String,
-    data: Option<HylNewsData>,
-}
-
 #[derive(serde::Deserialize)]
 struct HylNewsData {
     list: Vec<HylNewsItem>,
@@ -149,43 +136,20 @@ async fn fetch_events(
     gid: u32,
     lang: &str,
 ) -> Result<Vec<NewsItem>, ApiError> {
-    let resp = client
-        .get(EVENTS_API)
-        .header("x-rpc-language", lang)
+    let page_size = PAGE_SIZE.to_string();
+    let gid = gid.to_string();
+    let data: HylEventData = HoyolabRequest::get(client, "news_events", EVENTS_API)
         .query(&[
-            ("page_size", PAGE_SIZE.to_string()),
-            ("size", PAGE_SIZE.to_string()),
-            ("gids", gid.to_string()),
-            ("is_all", "1".to_string()),
+            ("page_size", page_size.as_str()),
+            ("size", page_size.as_str()),
+            ("gids", gid.as_str()),
+            ("is_all", "1"),
         ])
+        .preset(HeaderPreset::Community, lang)
         .send()
-        .await
-        .map_err(|e| {
-            tracing::error!(error = %e, gid, "failed to fetch hoyolab events");
-            ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to fetch events")
-        })?;
-
-    let hyl: HylEventResponse = resp.json().await.map_err(|e| {
-        tracing::error!(error = %e, gid, "failed to parse hoyolab events response");
-        ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to parse events")
-    })?;
-
-    if hyl.retcode != 0 {
-        tracing::error!(retcode = hyl.retcode, message = %hyl.message, gid, "hoyolab events API error");
-        return Err(ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "events API returned an error",
-        ));
-    }
-
-    let items = hyl
-        .data
-        .ok_or_else(|| {
-            ApiError::internal_server_error(
-                ApiErrorCode::UPSTREAM_ERROR,
-                "events API returned no data",
-            )
-        })?
+        .await?;
+
+    let items = data
         .list
         .into_iter()
         .map(|e| NewsItem {
@@ -209,42 +173,20 @@ async fn fetch_news(
     type_name: &'static str,
     lang: &str,
 ) -> Result<Vec<NewsItem>, ApiError> {
-    let resp = client
-        .get(NEWS_API)
-        .header("x-rpc-language", lang)
+    let page_size = PAGE_SIZE.to_string();
+    let gid = gid.to_string();
+    let news_type = news_type.to_string();
+    let data: HylNewsData = HoyolabRequest::get(client, "news_list", NEWS_API)
         .query(&[
-            ("gids", gid.to_string()),
-            ("page_size", PAGE_SIZE.to_string()),
-            ("type", news_type.to_string()),
+            ("gids", gid.as_str()),
+            ("page_size", page_size.as_str()),
+            ("type", news_type.as_str()),
         ])
+        .preset(HeaderPreset::Community, lang)
         .send()
-        .await
-        .map_err(|e| {
-            tracing::error!(error = %e, gid, news_type, "failed to fetch hoyolab news");
-            ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to fetch news")
-        })?;
-
-    let hyl: HylNewsResponse = resp.json().await.map_err(|e| {
-        tracing::error!(error = %e, gid, news_type, "failed to parse hoyolab news response");
-        ApiError::internal_server_error(ApiErrorCode::UPSTREAM_ERROR, "failed to parse news")
-    })?;
-
-    if hyl.retcode != 0 {
-        tracing::error!(retcode = hyl.retcode, message = %hyl.message, gid, news_type, "hoyolab news API error");
-        return Err(ApiError::internal_server_error(
-            ApiErrorCode::UPSTREAM_ERROR,
-            "news API returned an error",
-        ));
-    }
-
-    let items = hyl
-        .data
-        .ok_or_else(|| {
-            ApiError::internal_server_error(
-                ApiErrorCode::UPSTREAM_ERROR,
-                "news API returned no data",
-            )
-        })?
+        .await?;
+
+    let items = data
         .list
         .into_iter()
         .map(|item| {
@@ -1,9 +1,15 @@
+pub mod calendar_refresh;
+pub mod calendar_snapshot;
 pub mod config;
 pub mod database;
+pub mod expiry;
+pub mod feed;
 pub mod games;
 pub mod global;
+pub mod hoyolab;
 pub mod http;
 pub mod notifier;
+pub mod rewards;
 pub mod scraper;
 pub mod util;
 pub mod validator;
@@ -2,12 +2,18 @@ use global::Global;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
+mod calendar_refresh;
+mod calendar_snapshot;
 mod config;
 mod database;
+mod expiry;
+mod feed;
 mod games;
 mod global;
+mod hoyolab;
 mod http;
 mod notifier;
+mod rewards;
 mod scraper;
 mod util;
 mod validator;
@@ -50,6 +56,31 @@ async fn main() -> anyhow::Result<()> {
                 tracing::error!("scraper error: {:#}", e);
             }
         }
+        r = expiry::run(global.clone()) => {
+            if let Err(e) = r {
+                tracing::error!("expiry job error: {:#}", e);
+            }
+        }
+        r = calendar_refresh::run(global.clone()) => {
+            if let Err(e) = r {
+                tracing::error!("calendar refresh error: {:#}", e);
+            }
+        }
+        r = calendar_snapshot::run(global.clone()) => {
+            if let Err(e) = r {
+                tracing::error!("calendar snapshot job error: {:#}", e);
+            }
+        }
+        r = notifier::webhook::run(global.clone()) => {
+            if let Err(e) = r {
+                tracing::error!("webhook worker error: {:#}", e);
+            }
+        }
+        r = notifier::digest::run(global.clone()) => {
+            if let Err(e) = r {
+                tracing::error!("digest error: {:#}", e);
+            }
+        }
         _ = tokio::signal::ctrl_c() => {
             tracing::info!("shutting down");
         }
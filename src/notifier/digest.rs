@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+
+use crate::database::code_event::{self, CodeEvent, CodeEventKind};
+use crate::database::notification_state::NotificationState;
+use crate::games::Game;
+use crate::games::server::Server;
+use crate::global::Global;
+use crate::validator::hoyoverse_api::RedeemOutcome;
+
+/// How often a digest that no backend accepted is retried.
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Codes that were added or went inactive for one game over the last day.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Digest {
+    pub new_codes: Vec<String>,
+    pub expired_codes: Vec<String>,
+    /// Codes deactivated because the redemption API rejected them, rather
+    /// than because they ran out.
+    pub invalid_codes: Vec<String>,
+}
+
+impl Digest {
+    /// Sort deactivations by their reason: codes rejected on discovery, or
+    /// whose last validation called them invalid, are listed as invalid and
+    /// not as new. CN codes are marked as such.
+    pub fn from_events(events: &[CodeEvent]) -> Self {
+        let mut digest = Self::default();
+        let mut last_outcome: HashMap<(Server, &str), RedeemOutcome> = HashMap::new();
+        for event in events {
+            let key = (event.server, event.code.as_str());
+            let list = match event.kind {
+                CodeEventKind::Validated => {
+                    if let Some(outcome) = event.outcome {
+                        last_outcome.insert(key, outcome);
+                    }
+                    continue;
+                }
+                CodeEventKind::Discovered => &mut digest.new_codes,
+                CodeEventKind::Deactivated
+                    if event.message.as_deref() == Some(code_event::REJECTED_ON_DISCOVERY)
+                        || last_outcome.get(&key) == Some(&RedeemOutcome::Invalid) =>
+                {
+                    &mut digest.invalid_codes
+                }
+                CodeEventKind::Deactivated => &mut digest.expired_codes,
+                _ => continue,
+            };
+            let label = match event.server {
+                Server::Global => event.code.clone(),
+                Server::Cn => format!("{} (CN)", event.code),
+            };
+            if !list.contains(&label) {
+                list.push(label);
+            }
+        }
+        digest
+            .new_codes
+            .retain(|code| !digest.invalid_codes.contains(code));
+        digest
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.new_codes.is_empty() && self.expired_codes.is_empty() && self.invalid_codes.is_empty()
+    }
+
+    /// Non-empty sections with their headings, for the backends to format.
+    pub fn sections(&self) -> Vec<(&'static str, &[String])> {
+        [
+            ("New codes", self.new_codes.as_slice()),
+            ("Expired codes", self.expired_codes.as_slice()),
+            ("Invalid codes", self.invalid_codes.as_slice()),
+        ]
+        .into_iter()
+        .filter(|(_, codes)| !codes.is_empty())
+        .collect()
+    }
+}
+
+/// Send each game's digest once a day at `notifications.digest.hour_utc`.
+#[tracing::instrument(name = "Digest", skip_all)]
+pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
+    let config = &global.config.notifications.digest;
+    if !config.enabled {
+        tracing::info!("daily digest is disabled");
+        std::future::pending::<()>().await;
+        return Ok(());
+    }
+
+    let all_games = [
+        Game::Genshin,
+        Game::Starrail,
+        Game::Zenless,
+        Game::Honkai,
+        Game::Themis,
+    ];
+
+    loop {
+        let next = next_run(Utc::now(), config.hour_utc);
+        tracing::debug!(next_run = %next, "sleeping until next digest");
+        tokio::time::sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+
+        // Digests that no backend accepted are retried a few times rather
+        // than dropped until tomorrow.
+        let mut pending = all_games.to_vec();
+        for attempt in 1..=MAX_ATTEMPTS {
+            if attempt > 1 {
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+
+            let mut failed = Vec::new();
+            for game in pending {
+                match send_digest(&global, game, next).await {
+                    Ok(true) => {}
+                    Ok(false) => failed.push(game),
+                    Err(e) => {
+                        tracing::error!(game = game.slug(), "digest failed: {:#}", e);
+                        failed.push(game);
+                    }
+                }
+            }
+            pending = failed;
+            if pending.is_empty() {
+                break;
+            }
+        }
+    }
+}
+
+/// Send the digest for the day up to `scheduled`. Returns `false` if it
+/// should be retried because no backend accepted it.
+async fn send_digest(
+    global: &Arc<Global>,
+    game: Game,
+    scheduled: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    // Guards against a second digest when the process restarts around the
+    // digest hour.
+    let claim_cutoff = bson::DateTime::from_chrono(scheduled - Duration::hours(23));
+    if !NotificationState::claim_digest(&global.db, game, claim_cutoff).await? {
+        return Ok(true);
+    }
+
+    let since = bson::DateTime::from_chrono(scheduled - Duration::days(1));
+    let mut events = Vec::new();
+    for &server in game.servers() {
+        events.extend(CodeEvent::find_since(&global.db, game, server, since).await?);
+    }
+    let digest = Digest::from_events(&events);
+    if digest.is_empty() {
+        return Ok(true);
+    }
+
+    if !super::notify_digest(global, game, &digest).await {
+        NotificationState::release_digest(&global.db, game).await?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// The next `hour_utc`:00 strictly after `now`.
+fn next_run(now: DateTime<Utc>, hour_utc: u32) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour_utc.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+    let today = now.date_naive().and_time(time).and_utc();
+    if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    #[test]
+    fn schedules_next_digest() {
+        let morning = Utc.with_ymd_and_hms(2024, 6, 3, 8, 30, 0).unwrap();
+        let evening = Utc.with_ymd_and_hms(2024, 6, 3, 20, 0, 0).unwrap();
+
+        assert_eq!(
+            next_run(morning, 9),
+            Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            next_run(evening, 9),
+            Utc.with_ymd_and_hms(2024, 6, 4, 9, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn summarizes_events() {
+        let events = [
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "NEWCODE",
+                CodeEventKind::Discovered,
+            ),
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "NEWCODE",
+                CodeEventKind::Validated,
+            ),
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "OLDCODE",
+                CodeEventKind::Deactivated,
+            ),
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "OLDCODE",
+                CodeEventKind::Reactivated,
+            ),
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "OLDCODE",
+                CodeEventKind::Deactivated,
+            ),
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Cn,
+                "OLDCODE",
+                CodeEventKind::Deactivated,
+            ),
+        ];
+
+        let digest = Digest::from_events(&events);
+        assert_eq!(
+            digest,
+            Digest {
+                new_codes: vec!["NEWCODE".to_string()],
+                expired_codes: vec!["OLDCODE".to_string(), "OLDCODE (CN)".to_string()],
+                invalid_codes: Vec::new(),
+            }
+        );
+        assert_eq!(digest.sections().len(), 2);
+    }
+
+    #[test]
+    fn separates_invalid_codes() {
+        let mut rejected = CodeEvent::new(
+            Game::Genshin,
+            Server::Global,
+            "FAKECODE",
+            CodeEventKind::Deactivated,
+        );
+        rejected.message = Some(code_event::REJECTED_ON_DISCOVERY.to_string());
+        let mut validated = CodeEvent::new(
+            Game::Genshin,
+            Server::Global,
+            "OLDCODE",
+            CodeEventKind::Validated,
+        );
+        validated.outcome = Some(RedeemOutcome::Invalid);
+        let events = [
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "FAKECODE",
+                CodeEventKind::Discovered,
+            ),
+            rejected,
+            validated,
+            CodeEvent::new(
+                Game::Genshin,
+                Server::Global,
+                "OLDCODE",
+                CodeEventKind::Deactivated,
+            ),
+        ];
+
+        let digest = Digest::from_events(&events);
+        assert!(digest.new_codes.is_empty());
+        assert!(digest.expired_codes.is_empty());
+        assert_eq!(digest.invalid_codes, vec!["FAKECODE", "OLDCODE"]);
+    }
+}
@@ -1,104 +1,398 @@
-use std::sync::Arc;
+use std::time::Duration;
 
 use serde_json::json;
 
+use crate::config::NotificationsConfig;
 use crate::games::Game;
-use crate::global::Global;
-
-pub async fn notify_validation_error(global: &Arc<Global>, game: Game, code: &str, error: &str) {
-    let Some(webhook_url) = &global.discord_webhook else {
-        return;
-    };
-
-    let payload = json!({
-        "embeds": [{
-            "title": format!("{} Validator Error", game.display_name()),
-            "color": 0xFF0000u32,
-            "fields": [{
-                "name": format!("`{code}`"),
-                "value": format!("```{error}```"),
-                "inline": false,
-            }],
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-        }]
-    });
-
-    match global
-        .http_client
-        .post(webhook_url)
-        .json(&payload)
-        .send()
-        .await
-    {
-        Ok(resp) if resp.status().is_success() => {
-            tracing::info!(
-                game = game.slug(),
-                code,
-                "discord validation error notification sent"
-            );
+use crate::notifier::digest::Digest;
+use crate::notifier::{NewCode, Notifier, ensure_success};
+
+/// Posts embeds to a game's Discord webhooks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiscordNotifier {
+    /// Where new codes go; `None` if only errors are routed to Discord.
+    webhook_url: Option<String>,
+    error_webhook_url: Option<String>,
+    role_id: Option<String>,
+}
+
+impl DiscordNotifier {
+    /// Resolve `[notifications.discord.<game>]`, falling back to the shared
+    /// `discord_webhook`. Returns `None` if the game is disabled or has no
+    /// webhook at all.
+    pub fn from_config(config: &NotificationsConfig, game: Game) -> Option<Self> {
+        let target = config.discord.get(game);
+        if !target.enabled {
+            return None;
         }
-        Ok(resp) => {
-            tracing::warn!(game = game.slug(), status = %resp.status(), "discord validation error notification failed");
+
+        let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+        let webhook_url =
+            non_empty(&target.webhook_url).or_else(|| non_empty(&config.discord_webhook));
+        let error_webhook_url =
+            non_empty(&target.error_webhook_url).or_else(|| webhook_url.clone());
+        if webhook_url.is_none() && error_webhook_url.is_none() {
+            return None;
         }
-        Err(e) => {
-            tracing::warn!(game = game.slug(), error = %e, "discord validation error notification request failed");
+
+        Some(Self {
+            webhook_url,
+            error_webhook_url,
+            role_id: non_empty(&target.role_id),
+        })
+    }
+
+    /// Post a message, waiting out rate limits: Discord answers 429 with
+    /// the number of seconds to wait in `retry_after`.
+    async fn post(
+        &self,
+        http: &reqwest::Client,
+        url: &str,
+        payload: &serde_json::Value,
+    ) -> anyhow::Result<()> {
+        for _ in 0..MAX_RATE_LIMIT_RETRIES {
+            let resp = http.post(url).json(payload).send().await?;
+            if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return ensure_success(resp).await;
+            }
+
+            let header_secs = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<f64>().ok());
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            let retry_after = body["retry_after"]
+                .as_f64()
+                .or(header_secs)
+                .unwrap_or(1.0)
+                .clamp(0.0, MAX_RETRY_AFTER.as_secs_f64());
+
+            tracing::warn!(retry_after, "discord rate limited, waiting");
+            tokio::time::sleep(Duration::from_secs_f64(retry_after)).await;
         }
+
+        anyhow::bail!("still rate limited after {MAX_RATE_LIMIT_RETRIES} attempts")
     }
 }
 
-pub async fn notify_new_codes(
-    global: &Arc<Global>,
+// https://discord.com/developers/docs/resources/message#embed-object-embed-limits
+const MAX_FIELDS: usize = 25;
+const MAX_EMBED_CHARS: usize = 6000;
+const MAX_FIELD_VALUE_CHARS: usize = 1024;
+const MAX_DESCRIPTION_CHARS: usize = 4096;
+
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Build the messages announcing `codes`: one embed per message, each within
+/// Discord's field and character limits. Only the first message mentions
+/// `role_id`.
+fn new_code_messages(
     game: Game,
-    codes: &[(String, Vec<String>, String)],
-) {
-    let Some(webhook_url) = &global.discord_webhook else {
-        return;
-    };
-
-    let fields: Vec<serde_json::Value> = codes
-        .iter()
-        .map(|(code, rewards, source)| {
-            let value = if rewards.is_empty() {
-                format!("Source: {source}")
+    codes: &[NewCode],
+    role_id: Option<&str>,
+    timestamp: &str,
+) -> Vec<serde_json::Value> {
+    let title = format!("New {} Codes", game.display_name());
+    // Leave room for a " (10/10)" page suffix.
+    let budget = MAX_EMBED_CHARS - title.chars().count() - 10;
+
+    let mut pages: Vec<Vec<(String, String)>> = Vec::new();
+    let mut page_chars = 0;
+    for code in codes {
+        let field = code_field(game, code);
+        let chars = field.0.chars().count() + field.1.chars().count();
+        match pages.last_mut() {
+            Some(page) if page.len() < MAX_FIELDS && page_chars + chars <= budget => {
+                page.push(field);
+                page_chars += chars;
+            }
+            _ => {
+                pages.push(vec![field]);
+                page_chars = chars;
+            }
+        }
+    }
+
+    let total = pages.len();
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, fields)| {
+            let title = if total > 1 {
+                format!("{title} ({}/{total})", i + 1)
             } else {
-                format!("{}\nSource: {source}", rewards.join(", "))
+                title.clone()
             };
-            json!({
-                "name": format!("`{code}`"),
-                "value": value,
-                "inline": false,
-            })
+            let fields: Vec<serde_json::Value> = fields
+                .into_iter()
+                .map(|(name, value)| json!({ "name": name, "value": value, "inline": false }))
+                .collect();
+
+            let mut payload = json!({
+                "embeds": [{
+                    "title": title,
+                    "color": game.embed_color(),
+                    "fields": fields,
+                    "timestamp": timestamp,
+                }]
+            });
+            if let Some(role_id) = role_id.filter(|_| i == 0) {
+                payload["content"] = json!(format!("<@&{role_id}>"));
+                payload["allowed_mentions"] = json!({ "roles": [role_id] });
+            }
+            payload
         })
-        .collect();
-
-    let payload = json!({
-        "embeds": [{
-            "title": format!("New {} Codes", game.display_name()),
-            "color": game.embed_color(),
-            "fields": fields,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-        }]
-    });
-
-    match global
-        .http_client
-        .post(webhook_url)
-        .json(&payload)
-        .send()
-        .await
-    {
-        Ok(resp) if resp.status().is_success() => {
-            tracing::info!(
-                game = game.slug(),
-                count = codes.len(),
-                "discord notification sent"
-            );
-        }
-        Ok(resp) => {
-            tracing::warn!(game = game.slug(), status = %resp.status(), "discord notification failed");
+        .collect()
+}
+
+fn code_field(game: Game, code: &NewCode) -> (String, String) {
+    let mut lines = Vec::new();
+    if !code.rewards.is_empty() {
+        lines.push(code.rewards.join(", "));
+    }
+    lines.push(format!("Source: {}", code.source));
+    if let Some(url) = game.redeem_url(&code.code, code.server) {
+        lines.push(format!("[Redeem]({url})"));
+    }
+
+    (
+        format!("`{}`", code.code),
+        truncate(&lines.join("\n"), MAX_FIELD_VALUE_CHARS),
+    )
+}
+
+/// Cut `s` to at most `max` characters, marking the cut with an ellipsis.
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+#[async_trait::async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send_new_codes(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        codes: &[NewCode],
+    ) -> anyhow::Result<()> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(());
+        };
+
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        for payload in new_code_messages(game, codes, self.role_id.as_deref(), &timestamp) {
+            self.post(http, url, &payload).await?;
         }
-        Err(e) => {
-            tracing::warn!(game = game.slug(), error = %e, "discord notification request failed");
+
+        Ok(())
+    }
+
+    async fn send_validation_error(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        code: &str,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        let Some(url) = &self.error_webhook_url else {
+            return Ok(());
+        };
+
+        let payload = json!({
+            "embeds": [{
+                "title": format!("{} Validator Error", game.display_name()),
+                "color": 0xFF0000u32,
+                "fields": [{
+                    "name": format!("`{code}`"),
+                    "value": format!("```{}```", truncate(error, MAX_FIELD_VALUE_CHARS - 6)),
+                    "inline": false,
+                }],
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }]
+        });
+
+        self.post(http, url, &payload).await
+    }
+
+    async fn send_resolved(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let Some(url) = &self.error_webhook_url else {
+            return Ok(());
+        };
+
+        let payload = json!({
+            "embeds": [{
+                "title": format!("{} Validator Error Resolved", game.display_name()),
+                "color": 0x2ECC71u32,
+                "description": truncate(message, MAX_DESCRIPTION_CHARS),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }]
+        });
+
+        self.post(http, url, &payload).await
+    }
+
+    async fn send_digest(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        digest: &Digest,
+    ) -> anyhow::Result<()> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(());
+        };
+
+        let fields: Vec<serde_json::Value> = digest
+            .sections()
+            .into_iter()
+            .map(|(heading, codes)| {
+                let list = codes
+                    .iter()
+                    .map(|c| format!("`{c}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                json!({
+                    "name": format!("{heading} ({})", codes.len()),
+                    "value": truncate(&list, MAX_FIELD_VALUE_CHARS),
+                    "inline": false,
+                })
+            })
+            .collect();
+
+        let payload = json!({
+            "embeds": [{
+                "title": format!("{} Daily Digest", game.display_name()),
+                "color": game.embed_color(),
+                "fields": fields,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }]
+        });
+
+        self.post(http, url, &payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DiscordTarget;
+    use crate::games::server::Server;
+
+    fn codes(n: usize, rewards: &str) -> Vec<NewCode> {
+        (0..n)
+            .map(|i| NewCode {
+                code: format!("CODE{i}"),
+                server: Server::Global,
+                rewards: vec![rewards.to_string()],
+                source: "fandom".to_string(),
+            })
+            .collect()
+    }
+
+    fn fields(message: &serde_json::Value) -> usize {
+        message["embeds"][0]["fields"].as_array().unwrap().len()
+    }
+
+    #[test]
+    fn splits_at_field_limit() {
+        let messages = new_code_messages(Game::Genshin, &codes(30, "Primogem ×60"), Some("42"), "");
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!((fields(&messages[0]), fields(&messages[1])), (25, 5));
+        assert_eq!(
+            messages[0]["embeds"][0]["title"],
+            "New Genshin Impact Codes (1/2)"
+        );
+        assert_eq!(messages[0]["content"], "<@&42>");
+        assert!(messages[1].get("content").is_none());
+    }
+
+    #[test]
+    fn splits_at_character_limit() {
+        let messages = new_code_messages(Game::Starrail, &codes(10, &"x".repeat(1000)), None, "");
+
+        assert!(messages.len() > 1);
+        for message in &messages {
+            let embed = &message["embeds"][0];
+            let chars: usize = embed["title"].as_str().unwrap().chars().count()
+                + embed["fields"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|f| {
+                        f["name"].as_str().unwrap().chars().count()
+                            + f["value"].as_str().unwrap().chars().count()
+                    })
+                    .sum::<usize>();
+            assert!(chars <= MAX_EMBED_CHARS);
         }
+        assert_eq!(messages.iter().map(fields).sum::<usize>(), 10);
+    }
+
+    #[test]
+    fn links_to_redeem_page() {
+        let (_, value) = code_field(Game::Genshin, &codes(1, "Primogem ×60")[0]);
+        assert_eq!(
+            value,
+            "Primogem ×60\nSource: fandom\n[Redeem](https://genshin.hoyoverse.com/en/gift?code=CODE0)"
+        );
+
+        let (_, value) = code_field(Game::Honkai, &codes(1, "Crystal ×60")[0]);
+        assert!(!value.contains("Redeem"));
+
+        let mut cn_code = codes(1, "Primogem ×60").remove(0);
+        cn_code.server = Server::Cn;
+        let (_, value) = code_field(Game::Genshin, &cn_code);
+        assert!(!value.contains("Redeem"));
+    }
+
+    #[test]
+    fn routes_per_game() {
+        let mut config = NotificationsConfig {
+            discord_webhook: "https://discord.test/shared".to_string(),
+            ..Default::default()
+        };
+        config.discord.genshin = DiscordTarget {
+            webhook_url: "https://discord.test/genshin".to_string(),
+            role_id: "42".to_string(),
+            error_webhook_url: "https://discord.test/errors".to_string(),
+            ..Default::default()
+        };
+        config.discord.honkai.enabled = false;
+
+        assert_eq!(
+            DiscordNotifier::from_config(&config, Game::Genshin),
+            Some(DiscordNotifier {
+                webhook_url: Some("https://discord.test/genshin".to_string()),
+                error_webhook_url: Some("https://discord.test/errors".to_string()),
+                role_id: Some("42".to_string()),
+            })
+        );
+        assert_eq!(
+            DiscordNotifier::from_config(&config, Game::Starrail),
+            Some(DiscordNotifier {
+                webhook_url: Some("https://discord.test/shared".to_string()),
+                error_webhook_url: Some("https://discord.test/shared".to_string()),
+                role_id: None,
+            })
+        );
+        assert_eq!(DiscordNotifier::from_config(&config, Game::Honkai), None);
+
+        config.discord_webhook.clear();
+        assert_eq!(DiscordNotifier::from_config(&config, Game::Starrail), None);
     }
 }
@@ -0,0 +1,175 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Context as _;
+use serde_json::json;
+
+use crate::config::MatrixConfig;
+use crate::games::Game;
+use crate::notifier::digest::Digest;
+use crate::notifier::{NewCode, Notifier, ensure_success, escape_html};
+
+/// Sends `m.room.message` events through the Matrix client-server API.
+pub struct MatrixNotifier {
+    homeserver: String,
+    access_token: String,
+    room_id: String,
+    /// Makes transaction ids unique within this process.
+    txn_counter: AtomicU64,
+}
+
+impl MatrixNotifier {
+    pub fn from_config(config: &MatrixConfig, game: Game) -> Option<Self> {
+        let target = config.games.get(game);
+        if !target.enabled || target.room_id.is_empty() || config.access_token.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            homeserver: config.homeserver.trim_end_matches('/').to_string(),
+            access_token: config.access_token.clone(),
+            room_id: target.room_id.clone(),
+            txn_counter: AtomicU64::new(0),
+        })
+    }
+
+    async fn send_message(
+        &self,
+        http: &reqwest::Client,
+        body: String,
+        formatted_body: String,
+    ) -> anyhow::Result<()> {
+        let txn_id = format!(
+            "{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.txn_counter.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut url = reqwest::Url::parse(&self.homeserver).context("invalid homeserver URL")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("homeserver URL cannot have a path"))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        let payload = json!({
+            "msgtype": "m.text",
+            "body": body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": formatted_body,
+        });
+
+        let resp = http
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&payload)
+            .send()
+            .await?;
+        ensure_success(resp).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    async fn send_new_codes(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        codes: &[NewCode],
+    ) -> anyhow::Result<()> {
+        let title = format!("New {} Codes", game.display_name());
+        let mut body = format!("{title}\n");
+        let mut html = format!("<h4>{}</h4>", escape_html(&title));
+
+        for code in codes {
+            let rewards = code.rewards.join(", ");
+            body.push_str(&format!("\n{}\n", code.code));
+            html.push_str(&format!("<p><code>{}</code>", escape_html(&code.code)));
+            if !rewards.is_empty() {
+                body.push_str(&format!("{rewards}\n"));
+                html.push_str(&format!("<br>{}", escape_html(&rewards)));
+            }
+            body.push_str(&format!("Source: {}\n", code.source));
+            html.push_str(&format!("<br>Source: {}</p>", escape_html(&code.source)));
+        }
+
+        self.send_message(http, body, html).await
+    }
+
+    async fn send_validation_error(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        code: &str,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        let title = format!("{} Validator Error", game.display_name());
+        let body = format!("{title}\n{code}\n{error}");
+        let html = format!(
+            "<h4>{}</h4><p><code>{}</code></p><pre>{}</pre>",
+            escape_html(&title),
+            escape_html(code),
+            escape_html(error),
+        );
+
+        self.send_message(http, body, html).await
+    }
+
+    async fn send_resolved(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let title = format!("{} Validator Error Resolved", game.display_name());
+        let body = format!("{title}\n{message}");
+        let html = format!(
+            "<h4>{}</h4><p>{}</p>",
+            escape_html(&title),
+            escape_html(message)
+        );
+
+        self.send_message(http, body, html).await
+    }
+
+    async fn send_digest(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        digest: &Digest,
+    ) -> anyhow::Result<()> {
+        let title = format!("{} Daily Digest", game.display_name());
+        let mut body = format!("{title}\n");
+        let mut html = format!("<h4>{}</h4>", escape_html(&title));
+
+        for (heading, codes) in digest.sections() {
+            body.push_str(&format!(
+                "\n{heading} ({})\n{}\n",
+                codes.len(),
+                codes.join(", ")
+            ));
+            let list = codes
+                .iter()
+                .map(|c| format!("<code>{}</code>", escape_html(c)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            html.push_str(&format!(
+                "<p><b>{heading} ({})</b><br>{list}</p>",
+                codes.len()
+            ));
+        }
+
+        self.send_message(http, body, html).await
+    }
+}
@@ -1 +1,269 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::NotificationsConfig;
+use crate::database::notification_state::NotificationState;
+use crate::games::Game;
+use crate::games::server::Server;
+use crate::global::Global;
+
+pub mod digest;
 pub mod discord;
+pub mod matrix;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+/// A newly found code, as announced to chat backends.
+#[derive(Debug, Clone)]
+pub struct NewCode {
+    pub code: String,
+    /// Server the code is redeemed on.
+    pub server: Server,
+    pub rewards: Vec<String>,
+    /// Comma-separated names of the sources that reported the code.
+    pub source: String,
+}
+
+/// A chat service that announces codes to one channel.
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    /// Backend name used in logs and metrics.
+    fn name(&self) -> &'static str;
+
+    async fn send_new_codes(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        codes: &[NewCode],
+    ) -> anyhow::Result<()>;
+
+    async fn send_validation_error(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        code: &str,
+        error: &str,
+    ) -> anyhow::Result<()>;
+
+    /// Announce that an earlier validator alert has cleared.
+    async fn send_resolved(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        message: &str,
+    ) -> anyhow::Result<()>;
+
+    async fn send_digest(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        digest: &digest::Digest,
+    ) -> anyhow::Result<()>;
+}
+
+/// The notifiers configured for each game.
+#[derive(Default)]
+pub struct Notifiers {
+    by_game: HashMap<Game, Vec<Box<dyn Notifier>>>,
+}
+
+impl Notifiers {
+    pub fn from_config(config: &NotificationsConfig) -> Self {
+        let all_games = [
+            Game::Genshin,
+            Game::Starrail,
+            Game::Zenless,
+            Game::Honkai,
+            Game::Themis,
+        ];
+
+        let by_game = all_games
+            .into_iter()
+            .map(|game| {
+                let notifiers: Vec<Box<dyn Notifier>> = [
+                    discord::DiscordNotifier::from_config(config, game)
+                        .map(|n| Box::new(n) as Box<dyn Notifier>),
+                    telegram::TelegramNotifier::from_config(&config.telegram, game)
+                        .map(|n| Box::new(n) as Box<dyn Notifier>),
+                    slack::SlackNotifier::from_config(&config.slack, game)
+                        .map(|n| Box::new(n) as Box<dyn Notifier>),
+                    matrix::MatrixNotifier::from_config(&config.matrix, game)
+                        .map(|n| Box::new(n) as Box<dyn Notifier>),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                (game, notifiers)
+            })
+            .collect();
+
+        Self { by_game }
+    }
+
+    pub fn for_game(&self, game: Game) -> &[Box<dyn Notifier>] {
+        self.by_game.get(&game).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Announce new codes on every backend configured for `game` and to webhook
+/// subscribers. Failures are logged, never returned.
+pub async fn notify_new_codes(global: &Arc<Global>, game: Game, codes: &[NewCode]) {
+    if codes.is_empty() {
+        return;
+    }
+
+    for notifier in global.notifiers.for_game(game) {
+        let result = notifier
+            .send_new_codes(&global.http_client, game, codes)
+            .await;
+        record(notifier.as_ref(), game, "new_codes", result);
+    }
+
+    webhook::notify_new_codes(global, game, codes).await;
+}
+
+/// Report a validator problem on every backend configured for `game` and to
+/// webhook subscribers. Failures are logged, never returned.
+///
+/// `alert_key` identifies the condition (a dead account, say): repeats are
+/// suppressed for `notifications.alert_window_secs` until
+/// [`resolve_validation_error`] clears it.
+pub async fn notify_validation_error(
+    global: &Arc<Global>,
+    game: Game,
+    alert_key: &str,
+    code: &str,
+    error: &str,
+) {
+    let window = Duration::from_secs(global.config.notifications.alert_window_secs);
+    match NotificationState::raise(&global.db, game, alert_key, error, window).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::debug!(alert_key, "suppressing repeated alert");
+            metrics::counter!("notifications_suppressed_total", "game" => game.slug()).increment(1);
+            return;
+        }
+        // Better a duplicate than a lost alert.
+        Err(e) => tracing::warn!(alert_key, error = %e, "failed to record alert state"),
+    }
+
+    for notifier in global.notifiers.for_game(game) {
+        let result = notifier
+            .send_validation_error(&global.http_client, game, code, error)
+            .await;
+        record(notifier.as_ref(), game, "validation_error", result);
+    }
+
+    webhook::notify_validation_error(global, game, code, error).await;
+}
+
+/// Announce that the condition behind `alert_key` has cleared, if an alert
+/// for it was raised and not yet resolved.
+pub async fn resolve_validation_error(global: &Arc<Global>, game: Game, alert_key: &str) {
+    let alert = match NotificationState::resolve(&global.db, game, alert_key).await {
+        Ok(Some(alert)) => alert,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!(alert_key, error = %e, "failed to resolve alert");
+            return;
+        }
+    };
+
+    let message = format!(
+        "{} (seen {} times since {})",
+        alert.message.as_deref().unwrap_or(alert_key),
+        alert.count,
+        alert.first_seen.to_chrono().format("%Y-%m-%d %H:%M UTC"),
+    );
+
+    for notifier in global.notifiers.for_game(game) {
+        let result = notifier
+            .send_resolved(&global.http_client, game, &message)
+            .await;
+        record(notifier.as_ref(), game, "resolved", result);
+    }
+
+    webhook::notify_validation_resolved(global, game, alert_key, &message).await;
+}
+
+/// Send a daily digest on every backend configured for `game`. Returns
+/// `false` if there are backends and every one of them failed.
+pub async fn notify_digest(global: &Arc<Global>, game: Game, digest: &digest::Digest) -> bool {
+    let notifiers = global.notifiers.for_game(game);
+    let mut delivered = notifiers.is_empty();
+    for notifier in notifiers {
+        let result = notifier
+            .send_digest(&global.http_client, game, digest)
+            .await;
+        delivered |= result.is_ok();
+        record(notifier.as_ref(), game, "digest", result);
+    }
+    delivered
+}
+
+fn record(notifier: &dyn Notifier, game: Game, kind: &'static str, result: anyhow::Result<()>) {
+    let outcome = match result {
+        Ok(()) => {
+            tracing::info!(
+                backend = notifier.name(),
+                game = game.slug(),
+                kind,
+                "notification sent"
+            );
+            "sent"
+        }
+        Err(e) => {
+            tracing::warn!(
+                backend = notifier.name(),
+                game = game.slug(),
+                kind,
+                "notification failed: {:#}",
+                e
+            );
+            "failed"
+        }
+    };
+
+    metrics::counter!(
+        "notifications_total",
+        "backend" => notifier.name(),
+        "game" => game.slug(),
+        "kind" => kind,
+        "outcome" => outcome
+    )
+    .increment(1);
+}
+
+/// Turn a non-2xx response into an error carrying the response body.
+pub async fn ensure_success(resp: reqwest::Response) -> anyhow::Result<()> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = resp.text().await.unwrap_or_default();
+    anyhow::bail!("{status}: {body}")
+}
+
+/// Escape `&`, `<` and `>`, which is all that HTML-formatted messages
+/// (Telegram, Matrix) and Slack mrkdwn need.
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape_html("Primogem <x60> & Mora"),
+            "Primogem &lt;x60&gt; &amp; Mora"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use serde_json::json;
+
+use crate::config::SlackConfig;
+use crate::games::Game;
+use crate::notifier::digest::Digest;
+use crate::notifier::{NewCode, Notifier, ensure_success, escape_html as escape};
+
+/// Posts mrkdwn messages to a Slack incoming webhook.
+pub struct SlackNotifier {
+    webhook_url: String,
+}
+
+impl SlackNotifier {
+    pub fn from_config(config: &SlackConfig, game: Game) -> Option<Self> {
+        let target = config.games.get(game);
+        if !target.enabled || target.webhook_url.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            webhook_url: target.webhook_url.clone(),
+        })
+    }
+
+    async fn post(&self, http: &reqwest::Client, text: String) -> anyhow::Result<()> {
+        let resp = http
+            .post(&self.webhook_url)
+            .json(&json!({ "text": text }))
+            .send()
+            .await?;
+        ensure_success(resp).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send_new_codes(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        codes: &[NewCode],
+    ) -> anyhow::Result<()> {
+        let mut text = format!("*New {} Codes*\n", escape(game.display_name()));
+        for code in codes {
+            text.push_str(&format!("\n`{}`\n", escape(&code.code)));
+            if !code.rewards.is_empty() {
+                text.push_str(&format!("{}\n", escape(&code.rewards.join(", "))));
+            }
+            text.push_str(&format!("Source: {}\n", escape(&code.source)));
+        }
+
+        self.post(http, text).await
+    }
+
+    async fn send_validation_error(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        code: &str,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        let text = format!(
+            "*{} Validator Error*\n`{}`\n```{}```",
+            escape(game.display_name()),
+            escape(code),
+            escape(error),
+        );
+        self.post(http, text).await
+    }
+
+    async fn send_resolved(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let text = format!(
+            "*{} Validator Error Resolved*\n{}",
+            escape(game.display_name()),
+            escape(message),
+        );
+        self.post(http, text).await
+    }
+
+    async fn send_digest(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        digest: &Digest,
+    ) -> anyhow::Result<()> {
+        let mut text = format!("*{} Daily Digest*\n", escape(game.display_name()));
+        for (heading, codes) in digest.sections() {
+            let list = codes
+                .iter()
+                .map(|c| format!("`{}`", escape(c)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            text.push_str(&format!("\n*{heading} ({})*\n{list}\n", codes.len()));
+        }
+        self.post(http, text).await
+    }
+}
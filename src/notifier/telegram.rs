@@ -0,0 +1,260 @@
+use serde_json::json;
+
+use crate::config::TelegramConfig;
+use crate::games::Game;
+use crate::notifier::digest::Digest;
+use crate::notifier::{NewCode, Notifier, ensure_success, escape_html};
+
+/// Telegram rejects messages longer than this.
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Room left in every message for the bold title and a " (10/10)" suffix.
+const TITLE_BUDGET: usize = 160;
+
+/// Sends HTML messages through the Telegram Bot API.
+pub struct TelegramNotifier {
+    api_url: String,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramNotifier {
+    pub fn from_config(config: &TelegramConfig, game: Game) -> Option<Self> {
+        let target = config.games.get(game);
+        if !target.enabled || target.chat_id.is_empty() || config.bot_token.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            api_url: config.api_url.trim_end_matches('/').to_string(),
+            bot_token: config.bot_token.clone(),
+            chat_id: target.chat_id.clone(),
+        })
+    }
+
+    async fn send_message(&self, http: &reqwest::Client, text: String) -> anyhow::Result<()> {
+        let url = format!("{}/bot{}/sendMessage", self.api_url, self.bot_token);
+        let payload = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true,
+        });
+
+        let resp = http.post(url).json(&payload).send().await?;
+        ensure_success(resp).await
+    }
+
+    async fn send_messages(
+        &self,
+        http: &reqwest::Client,
+        texts: Vec<String>,
+    ) -> anyhow::Result<()> {
+        for text in texts {
+            self.send_message(http, text).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Pack `blocks` under a bold `title` into as few messages as fit
+/// Telegram's length limit. Titles get a page suffix when there are several.
+fn paginate(title: &str, blocks: Vec<String>) -> Vec<String> {
+    let budget = MAX_MESSAGE_CHARS - TITLE_BUDGET;
+
+    let mut pages: Vec<String> = Vec::new();
+    for block in blocks {
+        match pages.last_mut() {
+            Some(page) if page.chars().count() + block.chars().count() <= budget => {
+                page.push_str(&block);
+            }
+            _ => pages.push(block),
+        }
+    }
+    if pages.is_empty() {
+        pages.push(String::new());
+    }
+
+    let total = pages.len();
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, body)| {
+            if total > 1 {
+                format!("<b>{title} ({}/{total})</b>\n{body}", i + 1)
+            } else {
+                format!("<b>{title}</b>\n{body}")
+            }
+        })
+        .collect()
+}
+
+/// HTML-escape `s`, cut short with an ellipsis so that the escaped text
+/// stays within `max` characters.
+fn escape_truncated(s: &str, max: usize) -> String {
+    let mut out = String::new();
+    let mut len = 0;
+    for c in s.chars() {
+        let escaped = escape_html(c.encode_utf8(&mut [0; 4]));
+        let chars = escaped.chars().count();
+        if len + chars > max - 1 {
+            out.push('…');
+            return out;
+        }
+        out.push_str(&escaped);
+        len += chars;
+    }
+    out
+}
+
+fn new_codes_messages(game: Game, codes: &[NewCode]) -> Vec<String> {
+    let blocks = codes
+        .iter()
+        .map(|code| {
+            let mut block = format!("\n<code>{}</code>\n", escape_html(&code.code));
+            if !code.rewards.is_empty() {
+                block.push_str(&format!("{}\n", escape_html(&code.rewards.join(", "))));
+            }
+            block.push_str(&format!("Source: {}\n", escape_html(&code.source)));
+            block
+        })
+        .collect();
+    let title = format!("New {} Codes", escape_html(game.display_name()));
+    paginate(&title, blocks)
+}
+
+/// The digest, one block per section; long code lists are split over
+/// several blocks under the same heading.
+fn digest_messages(game: Game, digest: &Digest) -> Vec<String> {
+    let budget = MAX_MESSAGE_CHARS - TITLE_BUDGET;
+    let mut blocks = Vec::new();
+    for (heading, codes) in digest.sections() {
+        let heading = format!("\n<b>{heading} ({})</b>\n", codes.len());
+        let mut list = String::new();
+        for code in codes {
+            let item = format!("<code>{}</code>", escape_html(code));
+            if !list.is_empty() && heading.len() + list.len() + item.len() + 3 > budget {
+                blocks.push(format!("{heading}{list}\n"));
+                list.clear();
+            }
+            if !list.is_empty() {
+                list.push_str(", ");
+            }
+            list.push_str(&item);
+        }
+        blocks.push(format!("{heading}{list}\n"));
+    }
+    let title = format!("{} Daily Digest", escape_html(game.display_name()));
+    paginate(&title, blocks)
+}
+
+#[async_trait::async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send_new_codes(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        codes: &[NewCode],
+    ) -> anyhow::Result<()> {
+        self.send_messages(http, new_codes_messages(game, codes))
+            .await
+    }
+
+    async fn send_validation_error(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        code: &str,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        let text = format!(
+            "<b>{} Validator Error</b>\n<code>{}</code>\n<pre>{}</pre>",
+            escape_html(game.display_name()),
+            escape_html(code),
+            escape_truncated(error, MAX_MESSAGE_CHARS - TITLE_BUDGET),
+        );
+        self.send_message(http, text).await
+    }
+
+    async fn send_resolved(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let text = format!(
+            "<b>{} Validator Error Resolved</b>\n{}",
+            escape_html(game.display_name()),
+            escape_truncated(message, MAX_MESSAGE_CHARS - TITLE_BUDGET),
+        );
+        self.send_message(http, text).await
+    }
+
+    async fn send_digest(
+        &self,
+        http: &reqwest::Client,
+        game: Game,
+        digest: &Digest,
+    ) -> anyhow::Result<()> {
+        self.send_messages(http, digest_messages(game, digest))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::server::Server;
+
+    #[test]
+    fn formats_new_codes() {
+        let codes = [NewCode {
+            code: "GENSHINGIFT".to_string(),
+            server: Server::Global,
+            rewards: vec!["Primogem ×60".to_string(), "Mora ×5000".to_string()],
+            source: "fandom, game8".to_string(),
+        }];
+
+        assert_eq!(
+            new_codes_messages(Game::Genshin, &codes),
+            vec![
+                "<b>New Genshin Impact Codes</b>\n\n<code>GENSHINGIFT</code>\n\
+                 Primogem ×60, Mora ×5000\nSource: fandom, game8\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_long_messages() {
+        let codes: Vec<NewCode> = (0..200)
+            .map(|i| NewCode {
+                code: format!("GENSHINGIFT{i:03}"),
+                server: Server::Global,
+                rewards: vec!["Primogem ×60".to_string(), "Mora ×5000".to_string()],
+                source: "fandom, game8".to_string(),
+            })
+            .collect();
+
+        let messages = new_codes_messages(Game::Genshin, &codes);
+        assert!(messages.len() > 1);
+        assert!(messages[0].starts_with(&format!(
+            "<b>New Genshin Impact Codes (1/{})</b>",
+            messages.len()
+        )));
+        assert!(
+            messages
+                .iter()
+                .all(|m| m.chars().count() <= MAX_MESSAGE_CHARS)
+        );
+        let listed: usize = messages.iter().map(|m| m.matches("<code>").count()).sum();
+        assert_eq!(listed, codes.len());
+
+        let error = escape_truncated(&"<&>".repeat(3000), 100);
+        assert!(error.chars().count() <= 100);
+        assert!(error.ends_with(";…"));
+    }
+}
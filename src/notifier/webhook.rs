@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac as _};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::database::validation_job::backoff;
+use crate::database::webhook_delivery::{DeliveryStatus, WebhookDelivery};
+use crate::database::webhook_subscription::{WebhookEvent, WebhookSubscription};
+use crate::games::Game;
+use crate::games::server::Server;
+use crate::global::Global;
+use crate::notifier::NewCode;
+
+pub async fn notify_new_codes(global: &Arc<Global>, game: Game, codes: &[NewCode]) {
+    for code in codes {
+        let data = json!({
+            "code": code.code,
+            "server": code.server,
+            "rewards": code.rewards,
+            "sources": code.source.split(", ").filter(|s| !s.is_empty()).collect::<Vec<_>>(),
+        });
+        enqueue(global, game, WebhookEvent::NewCode, data).await;
+    }
+}
+
+pub async fn notify_code_expired(
+    global: &Arc<Global>,
+    game: Game,
+    server: Server,
+    code: &str,
+    reason: &str,
+) {
+    let data = json!({ "code": code, "server": server, "reason": reason });
+    enqueue(global, game, WebhookEvent::CodeExpired, data).await;
+}
+
+pub async fn notify_validation_error(global: &Arc<Global>, game: Game, code: &str, error: &str) {
+    let data = json!({ "code": code, "error": error });
+    enqueue(global, game, WebhookEvent::ValidatorError, data).await;
+}
+
+pub async fn notify_validation_resolved(
+    global: &Arc<Global>,
+    game: Game,
+    alert_key: &str,
+    message: &str,
+) {
+    let data = json!({ "alert": alert_key, "message": message });
+    enqueue(global, game, WebhookEvent::ValidatorResolved, data).await;
+}
+
+/// Queue a delivery of `data` for every subscription that wants it. Failures
+/// are logged so that a notification never interrupts scraping or
+/// validation.
+async fn enqueue(global: &Arc<Global>, game: Game, event: WebhookEvent, data: serde_json::Value) {
+    let subscriptions = match WebhookSubscription::find_matching(&global.db, game, event).await {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            tracing::warn!(game = game.slug(), error = %e, "failed to load webhook subscriptions");
+            return;
+        }
+    };
+
+    if subscriptions.is_empty() {
+        return;
+    }
+
+    let body = json!({
+        "event": event.name(),
+        "game": game.slug(),
+        "at": chrono::Utc::now().to_rfc3339(),
+        "data": data,
+    })
+    .to_string();
+
+    for subscription in subscriptions {
+        let Some(subscription_id) = subscription.id else {
+            continue;
+        };
+        let delivery = WebhookDelivery::new(subscription_id, game, event, body.clone());
+        if let Err(e) = delivery.insert(&global.db).await {
+            tracing::warn!(url = subscription.url, error = %e, "failed to queue webhook delivery");
+        }
+    }
+}
+
+/// Send queued webhook deliveries, retrying failures with exponential
+/// backoff.
+#[tracing::instrument(name = "Webhooks", skip_all)]
+pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
+    let requeued = WebhookDelivery::requeue_sending(&global.db).await?;
+    if requeued > 0 {
+        tracing::info!(requeued, "requeued interrupted webhook deliveries");
+    }
+
+    let poll_interval =
+        Duration::from_secs(global.config.notifications.webhooks.poll_interval_secs);
+
+    loop {
+        match WebhookDelivery::claim_next(&global.db).await {
+            Ok(Some(delivery)) => {
+                if let Err(e) = deliver(&global, &delivery).await {
+                    tracing::error!(id = ?delivery.id, "webhook delivery failed: {:#}", e);
+                }
+            }
+            Ok(None) => tokio::time::sleep(poll_interval).await,
+            Err(e) => {
+                tracing::error!("failed to claim webhook delivery: {:#}", e);
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+    }
+}
+
+#[tracing::instrument(skip(global, delivery), fields(id = ?delivery.id, event = delivery.event.name(), attempts = delivery.attempts))]
+async fn deliver(global: &Arc<Global>, delivery: &WebhookDelivery) -> anyhow::Result<()> {
+    let config = &global.config.notifications.webhooks;
+
+    let subscription =
+        match WebhookSubscription::find_by_id(&global.db, delivery.subscription_id).await? {
+            Some(subscription) if subscription.enabled => subscription,
+            Some(_) => return delivery.fail(&global.db, "subscription disabled").await,
+            None => return delivery.fail(&global.db, "subscription deleted").await,
+        };
+
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let mut request = global
+        .http_client
+        .post(&subscription.url)
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .header("content-type", "application/json")
+        .header("x-webhook-event", delivery.event.name())
+        .header("x-webhook-timestamp", &timestamp)
+        .body(delivery.body.clone());
+    if let Some(id) = delivery.id {
+        request = request.header("x-webhook-delivery", id.to_hex());
+    }
+    if let Some(secret) = subscription.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header(
+            "x-webhook-signature",
+            format!("sha256={}", sign(secret, &timestamp, &delivery.body)),
+        );
+    }
+
+    let (response_status, error) = match request.send().await {
+        Ok(resp) if resp.status().is_success() => {
+            delivery
+                .mark_delivered(&global.db, resp.status().as_u16())
+                .await?;
+            metrics::counter!("webhook_deliveries_total", "event" => delivery.event.name(), "result" => "delivered")
+                .increment(1);
+            tracing::info!(url = subscription.url, "webhook delivered");
+            return Ok(());
+        }
+        Ok(resp) => (
+            Some(resp.status().as_u16()),
+            format!("endpoint responded with {}", resp.status()),
+        ),
+        Err(e) => (None, format!("request failed: {e}")),
+    };
+
+    let delay = backoff(
+        Duration::from_secs(config.retry_backoff_secs),
+        delivery.attempts,
+    );
+    let status = delivery
+        .retry_or_fail(
+            &global.db,
+            response_status,
+            &error,
+            delay,
+            config.max_attempts,
+        )
+        .await?;
+
+    if status == DeliveryStatus::Failed {
+        tracing::warn!(
+            url = subscription.url,
+            error,
+            "giving up on webhook delivery"
+        );
+        metrics::counter!("webhook_deliveries_total", "event" => delivery.event.name(), "result" => "failed")
+            .increment(1);
+    } else {
+        tracing::debug!(
+            url = subscription.url,
+            error,
+            retry_in_secs = delay.as_secs(),
+            "webhook delivery rescheduled"
+        );
+    }
+
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`. Including the timestamp lets
+/// receivers reject replayed deliveries.
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        // echo -n '1700000000.{"event":"new_code"}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", "1700000000", r#"{"event":"new_code"}"#),
+            "e4f120ccaa9822ccba7c1b316d7e922dfbc130e332302fe0f4e288bbf162a68e"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::games::Game;
+
+/// `Primogem ×60`, `Primogem x 60`, `Primogem*60`, `Mora ×10,000`
+static TRAILING_QTY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?)(?:\s*[×*]\s*|\s+[xX]\s*)(\d[\d,.]*)$").expect("invalid trailing qty regex")
+});
+/// `50,000 Credit`, `60x Primogem`
+static LEADING_QTY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d[\d,.]*)\s*[×xX]?\s+(.+)$").expect("invalid leading qty regex")
+});
+
+/// A single reward item granted by a code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reward {
+    pub item: String,
+    pub quantity: Option<u64>,
+}
+
+impl fmt::Display for Reward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quantity {
+            Some(quantity) => write!(f, "{} ×{quantity}", self.item),
+            None => f.write_str(&self.item),
+        }
+    }
+}
+
+impl Reward {
+    /// Parse a free-form reward string as produced by any of the scrapers.
+    pub fn parse(game: Game, raw: &str) -> Option<Self> {
+        let raw = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+        if raw.is_empty() {
+            return None;
+        }
+
+        let (item, quantity) = if let Some(cap) = TRAILING_QTY_RE.captures(&raw) {
+            (cap[1].to_string(), parse_quantity(&cap[2]))
+        } else if let Some(cap) = LEADING_QTY_RE.captures(&raw) {
+            (cap[2].to_string(), parse_quantity(&cap[1]))
+        } else {
+            (raw.clone(), None)
+        };
+
+        let item = canonical_item(game, item.trim());
+        if item.is_empty() {
+            return None;
+        }
+
+        Some(Self { item, quantity })
+    }
+
+    /// Parse a whole reward list, dropping entries that carry no item name.
+    pub fn parse_all(game: Game, raw: &[String]) -> Vec<Self> {
+        raw.iter().filter_map(|r| Self::parse(game, r)).collect()
+    }
+}
+
+fn parse_quantity(value: &str) -> Option<u64> {
+    let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn canonical_item(game: Game, item: &str) -> String {
+    game.reward_aliases()
+        .iter()
+        .find(|(alias, canonical)| {
+            alias.eq_ignore_ascii_case(item) || canonical.eq_ignore_ascii_case(item)
+        })
+        .map(|(_, canonical)| canonical.to_string())
+        .unwrap_or_else(|| item.to_string())
+}
+
+/// Pick the most complete reward list among those reported by different
+/// sources: the most items wins, then the most items with a known quantity.
+/// Ties go to the earliest list, which follows source priority.
+pub fn reconcile(lists: &[Vec<Reward>]) -> Vec<Reward> {
+    let score = |list: &Vec<Reward>| {
+        (
+            list.len(),
+            list.iter().filter(|r| r.quantity.is_some()).count(),
+        )
+    };
+
+    lists
+        .iter()
+        .fold(None::<&Vec<Reward>>, |best, list| match best {
+            Some(best) if score(best) >= score(list) => Some(best),
+            _ => Some(list),
+        })
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reward(item: &str, quantity: Option<u64>) -> Reward {
+        Reward {
+            item: item.to_string(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn parses_existing_scraper_formats() {
+        let cases = [
+            ("Primogem ×60", reward("Primogem", Some(60))),
+            ("Mora ×10,000", reward("Mora", Some(10_000))),
+            ("Mora x 10000", reward("Mora", Some(10_000))),
+            ("Primogem*60", reward("Primogem", Some(60))),
+            ("60 Primogems", reward("Primogem", Some(60))),
+            ("Hero's Wit", reward("Hero's Wit", None)),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(Reward::parse(Game::Genshin, raw), Some(expected), "{raw}");
+        }
+    }
+
+    #[test]
+    fn does_not_split_item_names_containing_x() {
+        assert_eq!(
+            Reward::parse(Game::Honkai, "Phoenix Down"),
+            Some(reward("Phoenix Down", None))
+        );
+        assert_eq!(
+            Reward::parse(Game::Starrail, "50,000 Credit"),
+            Some(reward("Credit", Some(50_000)))
+        );
+    }
+
+    #[test]
+    fn applies_per_game_aliases() {
+        assert_eq!(
+            Reward::parse(Game::Starrail, "Stellar Jades ×60"),
+            Some(reward("Stellar Jade", Some(60)))
+        );
+        assert_eq!(
+            Reward::parse(Game::Genshin, "primogem x60"),
+            Some(reward("Primogem", Some(60)))
+        );
+    }
+
+    #[test]
+    fn reconcile_prefers_most_complete_list() {
+        let short = vec![reward("Primogem", Some(60))];
+        let long = vec![reward("Primogem", Some(60)), reward("Mora", None)];
+        let long_with_quantities = vec![reward("Primogem", Some(60)), reward("Mora", Some(5000))];
+
+        assert_eq!(reconcile(&[short.clone(), long.clone()]), long);
+        assert_eq!(
+            reconcile(&[long, long_with_quantities.clone(), short]),
+            long_with_quantities
+        );
+        assert!(reconcile(&[]).is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
+use crate::games::Game;
 use crate::global::Global;
 use crate::util::sleep_until_aligned;
 
+pub mod pipeline;
 pub mod sources;
 
 #[tracing::instrument(name = "Scraper", skip_all)]
@@ -21,11 +23,11 @@ pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
         sleep_until_aligned(interval_secs).await;
 
         let (r1, r2, r3, r4, r5) = tokio::join!(
-            sources::genshin::scrape_and_store(&global),
-            sources::starrail::scrape_and_store(&global),
-            sources::zenless::scrape_and_store(&global),
-            sources::themis::scrape_and_store(&global),
-            sources::honkai::scrape_and_store(&global),
+            pipeline::scrape_and_store(&global, Game::Genshin),
+            pipeline::scrape_and_store(&global, Game::Starrail),
+            pipeline::scrape_and_store(&global, Game::Zenless),
+            pipeline::scrape_and_store(&global, Game::Themis),
+            pipeline::scrape_and_store(&global, Game::Honkai),
         );
         if let Err(e) = r1 {
             tracing::error!(error = %e, "genshin scraper failed");
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::TryStreamExt as _;
+use mongodb::bson::doc;
+
+use crate::database::code_event::{CodeEvent, CodeEventKind};
+use crate::database::redemption_code::RedemptionCode;
+use crate::database::validation_job::{JobKind, ValidationJob};
+use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
+use crate::global::Global;
+use crate::notifier::{self, NewCode};
+use crate::validator;
+
+use super::sources::{self, ParsedCode};
+
+/// Every source's report of each scraped code, keyed by the uppercase code.
+type Reports = HashMap<String, Vec<(&'static str, ParsedCode)>>;
+
+/// Scrape every registered source for `game`, then store any codes that are
+/// not yet in the database and queue them for validation.
+#[tracing::instrument(skip(global))]
+pub async fn scrape_and_store(global: &Arc<Global>, game: Game) -> anyhow::Result<()> {
+    let sources = sources::registry(game);
+    let results =
+        futures::future::join_all(sources.iter().map(|source| source.scrape(global))).await;
+
+    // Merge all codes from all sources, keeping every source's report in
+    // registry order.
+    let mut all_codes: Reports = HashMap::new();
+
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(scraped) => {
+                for p in scraped {
+                    let reports = all_codes.entry(p.code.to_uppercase()).or_default();
+                    if !reports.iter().any(|(name, _)| *name == source.name()) {
+                        reports.push((source.name(), p));
+                    }
+                }
+            }
+            Err(e) => tracing::error!(
+                game = source.game().slug(),
+                source = source.name(),
+                error = %e,
+                "source scraper failed"
+            ),
+        }
+    }
+
+    if all_codes.is_empty() {
+        return Ok(());
+    }
+
+    // CN codes go to their own collection, by the regions their sources list.
+    let mut by_server: HashMap<Server, Reports> = HashMap::new();
+    for (code, reports) in all_codes {
+        let regions: Vec<Region> = reports
+            .iter()
+            .flat_map(|(_, p)| p.regions.iter().copied())
+            .collect();
+        by_server
+            .entry(game.server_for(&regions))
+            .or_default()
+            .insert(code, reports);
+    }
+
+    for (server, codes) in by_server {
+        store(global, game, server, codes).await?;
+    }
+
+    Ok(())
+}
+
+/// Store the codes scraped for one of `game`'s servers: refresh the source
+/// history of known codes, insert new ones and queue them for validation.
+async fn store(
+    global: &Arc<Global>,
+    game: Game,
+    server: Server,
+    all_codes: Reports,
+) -> anyhow::Result<()> {
+    let collection = RedemptionCode::collection(&global.db, game, server);
+    let total = all_codes.len();
+    let now = bson::DateTime::now();
+
+    // Single $in query across all sources combined
+    let candidates: Vec<String> = all_codes.keys().cloned().collect();
+    let mut existing: HashMap<String, RedemptionCode> = collection
+        .find(doc! { "code": { "$in": &candidates } })
+        .await?
+        .try_collect::<Vec<RedemptionCode>>()
+        .await?
+        .into_iter()
+        .map(|c| (c.code.clone(), c))
+        .collect();
+
+    let mut new_codes = Vec::new();
+    let mut updated_count = 0;
+
+    for (code, reports) in all_codes {
+        match existing.get_mut(&code) {
+            Some(stored) => {
+                stored.record_reports(&reports, now);
+                stored.reconcile(game);
+                RedemptionCode::save_reports(&global.db, game, server, stored).await?;
+                updated_count += 1;
+            }
+            None => new_codes.push((code, reports)),
+        }
+    }
+
+    if new_codes.is_empty() {
+        tracing::info!(
+            total,
+            server = server.slug(),
+            "{} scrape complete, no new codes",
+            game.slug()
+        );
+        if updated_count > 0 {
+            global.invalidate_codes_cache(game).await;
+        }
+        return Ok(());
+    }
+
+    let validation_enabled = validator::validation_enabled(global, game, server);
+
+    let mut new_count = 0;
+    let mut new_unvalidated_codes = Vec::new();
+
+    for (code, reports) in &new_codes {
+        let mut record = RedemptionCode::from_reports(game, code, reports, now);
+        // With validation on, the code stays hidden until the queue worker
+        // has checked it, and is published or kept inactive from there.
+        if validation_enabled {
+            record.active = false;
+            record.pending = true;
+        }
+
+        let source_names = record
+            .sources
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        collection.insert_one(&record).await?;
+
+        // A pending code's discovery is recorded once it is published, so
+        // that the history and digest only show codes that were served.
+        if !validation_enabled {
+            let mut event = CodeEvent::new(game, server, code, CodeEventKind::Discovered);
+            event.sources = record.sources.iter().map(|s| s.name.clone()).collect();
+            event.record(&global.db).await;
+        }
+
+        tracing::info!(code, sources = source_names, "new code discovered");
+        metrics::counter!("scraper_codes_discovered_total", "game" => game.slug()).increment(1);
+        new_count += 1;
+
+        if validation_enabled {
+            ValidationJob::new(game, server, code, JobKind::Discovery, record.regions)
+                .enqueue(&global.db)
+                .await?;
+        } else {
+            global
+                .code_feed
+                .added(game, server, code, record.rewards.clone());
+            new_unvalidated_codes.push(NewCode {
+                code: code.clone(),
+                server,
+                rewards: record.rewards,
+                source: source_names,
+            });
+        }
+    }
+
+    tracing::info!(
+        new = new_count,
+        total,
+        server = server.slug(),
+        "{} scrape complete",
+        game.slug()
+    );
+
+    notifier::notify_new_codes(global, game, &new_unvalidated_codes).await;
+    global.invalidate_codes_cache(game).await;
+
+    Ok(())
+}
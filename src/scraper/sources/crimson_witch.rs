@@ -1,16 +1,42 @@
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use anyhow::Context as _;
 use chrono::{DateTime, Utc};
 use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE, USER_AGENT};
 use serde_json::Value;
 
+use crate::games::Game;
+use crate::games::region::Region;
+use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode};
+
 const NEXT_PUSH_MARKER: &str = "self.__next_f.push(";
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct CrimsonCode {
-    pub code: String,
-    pub rewards: Vec<String>,
+/// Crimson Witch lists codes for several games under per-game URLs.
+pub struct CrimsonWitch {
+    pub game: Game,
+    pub url: &'static str,
+}
+
+#[async_trait::async_trait]
+impl CodeSource for CrimsonWitch {
+    fn name(&self) -> &'static str {
+        "crimson_witch"
+    }
+
+    fn game(&self) -> Game {
+        self.game
+    }
+
+    #[tracing::instrument(name = "crimson_witch", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let codes = scrape(&global.http_client, self.url).await?;
+
+        tracing::info!(count = codes.len(), "scraped codes from crimson witch");
+
+        Ok(codes)
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -24,6 +50,8 @@ struct WireCode {
     expires: Option<String>,
     #[serde(default)]
     rewards: Vec<WireReward>,
+    #[serde(default)]
+    region_locked: Option<Value>,
 }
 
 #[derive(serde::Deserialize)]
@@ -32,7 +60,7 @@ struct WireReward {
     qty: Option<Value>,
 }
 
-pub async fn scrape(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<CrimsonCode>> {
+pub async fn scrape(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<ParsedCode>> {
     let html = client
         .get(url)
         .header(
@@ -57,7 +85,7 @@ pub async fn scrape(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<C
     parse_html_at(&html, Utc::now())
 }
 
-fn parse_html_at(html: &str, now: DateTime<Utc>) -> anyhow::Result<Vec<CrimsonCode>> {
+fn parse_html_at(html: &str, now: DateTime<Utc>) -> anyhow::Result<Vec<ParsedCode>> {
     let rows = extract_initial_codes(html)?;
     let mut seen = HashSet::new();
     let mut codes = Vec::new();
@@ -69,6 +97,13 @@ fn parse_html_at(html: &str, now: DateTime<Utc>) -> anyhow::Result<Vec<CrimsonCo
             continue;
         }
 
+        let published_at = row.start_date.as_deref().and_then(parse_timestamp);
+        let expires_at = row.expires.as_deref().and_then(parse_timestamp);
+        let regions = row
+            .region_locked
+            .as_ref()
+            .map(parse_regions)
+            .unwrap_or_default();
         let rewards = row
             .rewards
             .into_iter()
@@ -85,9 +120,12 @@ fn parse_html_at(html: &str, now: DateTime<Utc>) -> anyhow::Result<Vec<CrimsonCo
             if code.is_empty() || !seen.insert(code.clone()) {
                 continue;
             }
-            codes.push(CrimsonCode {
+            codes.push(ParsedCode {
                 code,
                 rewards: rewards.clone(),
+                published_at,
+                expires_at,
+                regions: regions.clone(),
             });
         }
     }
@@ -119,6 +157,25 @@ fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
         .map(|date| date.with_timezone(&Utc))
 }
 
+/// `region_locked` is a region name, a list of them, or the `$undefined`
+/// placeholder for unrestricted codes.
+fn parse_regions(value: &Value) -> Vec<Region> {
+    match value {
+        Value::String(s) => Region::parse_list(s),
+        Value::Array(values) => {
+            let mut regions: Vec<Region> = values
+                .iter()
+                .filter_map(Value::as_str)
+                .flat_map(Region::parse_list)
+                .collect();
+            regions.sort();
+            regions.dedup();
+            regions
+        }
+        _ => Vec::new(),
+    }
+}
+
 fn split_variants(value: &str) -> Vec<String> {
     value
         .split([';', ','])
@@ -219,16 +276,8 @@ fn extract_balanced_json(value: &str, start: usize) -> Option<&str> {
         match byte {
             b'"' => in_string = true,
             b'[' | b'{' => stack.push(byte),
-            b']' => {
-                if stack.pop() != Some(b'[') {
-                    return None;
-                }
-            }
-            b'}' => {
-                if stack.pop() != Some(b'{') {
-                    return None;
-                }
-            }
+            b']' if stack.pop() != Some(b'[') => return None,
+            b'}' if stack.pop() != Some(b'{') => return None,
             _ => {}
         }
 
@@ -264,17 +313,20 @@ mod tests {
         assert_eq!(
             codes,
             vec![
-                CrimsonCode {
+                ParsedCode {
                     code: "TESTCODE".to_string(),
                     rewards: vec!["Primogem ×60".to_string(), "Mora ×10000".to_string()],
+                    ..Default::default()
                 },
-                CrimsonCode {
+                ParsedCode {
                     code: "ALTONE".to_string(),
                     rewards: vec!["Primogem ×60".to_string(), "Mora ×10000".to_string()],
+                    ..Default::default()
                 },
-                CrimsonCode {
+                ParsedCode {
                     code: "ALTTWO".to_string(),
                     rewards: vec!["Primogem ×60".to_string(), "Mora ×10000".to_string()],
+                    ..Default::default()
                 },
             ]
         );
@@ -307,6 +359,22 @@ mod tests {
 
         assert_eq!(codes[0].code, "FOUND");
         assert_eq!(codes[0].rewards, vec!["Credit ×5000"]);
+        assert!(codes[0].regions.is_empty());
+    }
+
+    #[test]
+    fn reads_region_locks() {
+        let html = html_with_codes(
+            r#"[
+                {"code":"ASIAONLY","rewards":[],"region_locked":"Asia"},
+                {"code":"WEST","rewards":[],"region_locked":["NA","EU"]}
+            ]"#,
+        );
+        let now = Utc.with_ymd_and_hms(2026, 7, 23, 0, 0, 0).unwrap();
+        let codes = parse_html_at(&html, now).unwrap();
+
+        assert_eq!(codes[0].regions, vec![Region::Asia]);
+        assert_eq!(codes[1].regions, vec![Region::America, Region::Europe]);
     }
 
     #[test]
@@ -1,39 +1,49 @@
+use crate::games::Game;
+use crate::games::region::Region;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode, is_named_arg, named_arg, parse_wiki_date};
 use anyhow::Context as _;
 use std::sync::Arc;
 const FANDOM_API: &str = "https://genshin-impact.fandom.com/api.php";
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Fandom;
+
+#[async_trait::async_trait]
+impl CodeSource for Fandom {
+    fn name(&self) -> &'static str {
+        "fandom"
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let resp = global
-        .http_client
-        .get(FANDOM_API)
-        .query(&[
-            ("action", "parse"),
-            ("page", "Promotional_Code"),
-            ("format", "json"),
-            ("prop", "wikitext"),
-        ])
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
-
-    let wikitext = resp["parse"]["wikitext"]["*"]
-        .as_str()
-        .context("failed to extract wikitext")?;
-
-    let codes = parse_wikitext(wikitext);
-
-    tracing::info!(count = codes.len(), "scraped codes from fandom");
-
-    Ok(codes)
+    fn game(&self) -> Game {
+        Game::Genshin
+    }
+
+    #[tracing::instrument(name = "fandom", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let resp = global
+            .http_client
+            .get(FANDOM_API)
+            .query(&[
+                ("action", "parse"),
+                ("page", "Promotional_Code"),
+                ("format", "json"),
+                ("prop", "wikitext"),
+            ])
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        let wikitext = resp["parse"]["wikitext"]["*"]
+            .as_str()
+            .context("failed to extract wikitext")?;
+
+        let codes = parse_wikitext(wikitext);
+
+        tracing::info!(count = codes.len(), "scraped codes from fandom");
+
+        Ok(codes)
+    }
 }
 
 /// Parse the wikitext to extract Code Row entries.
@@ -89,6 +99,19 @@ pub fn parse_wikitext(wikitext: &str) -> Vec<ParsedCode> {
 fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
     let cleaned = strip_html_comments(inner).replace(['\n', '\t'], "");
 
+    let args: Vec<&str> = cleaned.split('|').skip(1).map(|s| s.trim()).collect();
+    let positional: Vec<&str> = args.iter().copied().filter(|a| !is_named_arg(a)).collect();
+    let published_at = positional
+        .get(3)
+        .copied()
+        .or_else(|| named_arg(&args, "discovered"))
+        .and_then(|d| parse_wiki_date(d, false));
+    let expires_at = positional
+        .get(4)
+        .copied()
+        .or_else(|| named_arg(&args, "expiry"))
+        .and_then(|d| parse_wiki_date(d, true));
+
     let parts: Vec<&str> = cleaned
         .split('|')
         .map(|s| s.trim())
@@ -103,9 +126,8 @@ fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
     let server_field = parts[1];
     let rewards_field = parts[2];
 
-    if server_field == "CN" {
-        return None;
-    }
+    // CN-only rows are kept; the pipeline stores them with the CN codes.
+    let regions = Region::parse_list(server_field);
 
     if code_field.contains("notacode") || code_field.is_empty() {
         return None;
@@ -135,6 +157,9 @@ fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
         .map(|code_str| ParsedCode {
             code: code_str.to_string(),
             rewards: rewards.clone(),
+            published_at,
+            expires_at,
+            regions: regions.clone(),
         })
         .collect();
 
@@ -157,3 +182,44 @@ fn strip_html_comments(s: &str) -> String {
     result.push_str(rest);
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone as _, Utc};
+
+    use super::*;
+
+    #[test]
+    fn parses_row_dates() {
+        let codes = parse_wikitext(
+            "{{Code Row|GENSHINGIFT|All|Primogem*60;Mora*10000|2024-08-01|August 10, 2024}}\n\
+             {{Code Row|NODATES|All|Primogem*30||}}",
+        );
+
+        assert_eq!(codes.len(), 2);
+        assert_eq!(
+            codes[0].published_at,
+            Some(Utc.with_ymd_and_hms(2024, 8, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            codes[0].expires_at,
+            Some(Utc.with_ymd_and_hms(2024, 8, 10, 23, 59, 59).unwrap())
+        );
+        assert_eq!(codes[1].published_at, None);
+        assert_eq!(codes[1].expires_at, None);
+    }
+
+    #[test]
+    fn reads_server_column() {
+        let codes = parse_wikitext(
+            "{{Code Row|ASIACODE|Asia|Primogem*60}}\n\
+             {{Code Row|GLOBALCODE|All|Primogem*60}}\n\
+             {{Code Row|CNCODE|CN|Primogem*60}}",
+        );
+
+        assert_eq!(codes.len(), 3);
+        assert_eq!(codes[0].regions, vec![Region::Asia]);
+        assert!(codes[1].regions.is_empty());
+        assert_eq!(codes[2].regions, vec![Region::Cn]);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::games::Game;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode};
 use regex::Regex;
 use std::sync::{Arc, LazyLock};
 
@@ -20,27 +22,34 @@ static REWARD_RE: LazyLock<Regex> = LazyLock::new(|| {
 static TAG_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"<[^>]*>").expect("invalid tag regex"));
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Game8;
+
+#[async_trait::async_trait]
+impl CodeSource for Game8 {
+    fn name(&self) -> &'static str {
+        "game8"
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let html = global
-        .http_client
-        .get(GAME8_URL)
-        .send()
-        .await?
-        .text()
-        .await?;
+    fn game(&self) -> Game {
+        Game::Genshin
+    }
+
+    #[tracing::instrument(name = "game8", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let html = global
+            .http_client
+            .get(GAME8_URL)
+            .send()
+            .await?
+            .text()
+            .await?;
 
-    let codes = parse_html(&html);
+        let codes = parse_html(&html);
 
-    tracing::info!(count = codes.len(), "scraped codes from game8");
+        tracing::info!(count = codes.len(), "scraped codes from game8");
 
-    Ok(codes)
+        Ok(codes)
+    }
 }
 
 pub fn parse_html(html: &str) -> Vec<ParsedCode> {
@@ -76,6 +85,7 @@ pub fn parse_html(html: &str) -> Vec<ParsedCode> {
             results.push(ParsedCode {
                 code: code.to_uppercase(),
                 rewards,
+                ..Default::default()
             });
         }
     }
@@ -1,4 +1,6 @@
+use crate::games::Game;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode};
 use anyhow::Context as _;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -21,11 +23,7 @@ fn icon_url_to_hash(url: &str) -> &str {
     filename.split('.').next().unwrap_or(filename)
 }
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Hoyolab;
 
 #[derive(Deserialize)]
 struct Response {
@@ -60,46 +58,58 @@ struct IconBonus {
     icon_url: String,
 }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let resp = global
-        .http_client
-        .get(HOYOLAB_URL)
-        .header("x-rpc-app_version", "4.8.0")
-        .header("x-rpc-client_type", "4")
-        .header("x-rpc-language", "en-us")
-        .header("Referer", "https://www.hoyolab.com/")
-        .send()
-        .await?
-        .json::<Response>()
-        .await
-        .context("failed to parse hoyolab response")?;
-
-    let codes: Vec<ParsedCode> = resp
-        .data
-        .modules
-        .into_iter()
-        .filter_map(|m| m.exchange_group)
-        .flat_map(|g| g.bonuses)
-        .filter(|b| b.code_status == "ON" && !b.exchange_code.is_empty())
-        .map(|b| {
-            let rewards = b
-                .icon_bonuses
-                .iter()
-                .filter_map(|ib| {
-                    let hash = icon_url_to_hash(&ib.icon_url);
-                    let name = item_name_from_hash(hash)?;
-                    Some(format!("{} ×{}", name, ib.bonus_num))
-                })
-                .collect();
-            ParsedCode {
-                code: b.exchange_code,
-                rewards,
-            }
-        })
-        .collect();
-
-    tracing::info!(count = codes.len(), "scraped codes from hoyolab");
-
-    Ok(codes)
+#[async_trait::async_trait]
+impl CodeSource for Hoyolab {
+    fn name(&self) -> &'static str {
+        "hoyolab"
+    }
+
+    fn game(&self) -> Game {
+        Game::Genshin
+    }
+
+    #[tracing::instrument(name = "hoyolab", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let resp = global
+            .http_client
+            .get(HOYOLAB_URL)
+            .header("x-rpc-app_version", "4.8.0")
+            .header("x-rpc-client_type", "4")
+            .header("x-rpc-language", "en-us")
+            .header("Referer", "https://www.hoyolab.com/")
+            .send()
+            .await?
+            .json::<Response>()
+            .await
+            .context("failed to parse hoyolab response")?;
+
+        let codes: Vec<ParsedCode> = resp
+            .data
+            .modules
+            .into_iter()
+            .filter_map(|m| m.exchange_group)
+            .flat_map(|g| g.bonuses)
+            .filter(|b| b.code_status == "ON" && !b.exchange_code.is_empty())
+            .map(|b| {
+                let rewards = b
+                    .icon_bonuses
+                    .iter()
+                    .filter_map(|ib| {
+                        let hash = icon_url_to_hash(&ib.icon_url);
+                        let name = item_name_from_hash(hash)?;
+                        Some(format!("{} ×{}", name, ib.bonus_num))
+                    })
+                    .collect();
+                ParsedCode {
+                    code: b.exchange_code,
+                    rewards,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        tracing::info!(count = codes.len(), "scraped codes from hoyolab");
+
+        Ok(codes)
+    }
 }
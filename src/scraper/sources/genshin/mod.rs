@@ -1,17 +1,7 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::sync::Arc;
-
-use futures::TryStreamExt as _;
-use mongodb::bson::doc;
-
-use crate::database::redemption_code::RedemptionCode;
 use crate::games::Game;
-use crate::global::Global;
-use crate::notifier::discord;
-use crate::validator::hoyoverse_api;
 
-use super::crimson_witch;
+use super::CodeSource;
+use super::crimson_witch::CrimsonWitch;
 
 const CRIMSON_WITCH_URL: &str = "https://www.crimsonwitch.com/codes/Genshin_Impact";
 
@@ -19,164 +9,14 @@ pub mod fandom;
 pub mod game8;
 pub mod hoyolab;
 
-#[tracing::instrument(name = "genshin", skip_all)]
-pub async fn scrape_and_store(global: &Arc<Global>) -> anyhow::Result<()> {
-    let (fandom_result, game8_result, hoyolab_result, crimson_witch_result) = tokio::join!(
-        fandom::scrape(global),
-        game8::scrape(global),
-        hoyolab::scrape(global),
-        crimson_witch::scrape(&global.http_client, CRIMSON_WITCH_URL)
-    );
-
-    // Merge all codes from all sources, deduping by code in-memory.
-    // Value: (rewards, source) — first source to report a code wins.
-    let mut all_codes: HashMap<String, (Vec<String>, &'static str)> = HashMap::new();
-
-    match fandom_result {
-        Ok(scraped) => {
-            for p in scraped {
-                all_codes
-                    .entry(p.code.to_uppercase())
-                    .or_insert((p.rewards, "fandom"));
-            }
-        }
-        Err(e) => tracing::error!(error = %e, "fandom scraper failed"),
-    }
-
-    match game8_result {
-        Ok(scraped) => {
-            for p in scraped {
-                all_codes
-                    .entry(p.code.to_uppercase())
-                    .or_insert((p.rewards, "game8"));
-            }
-        }
-        Err(e) => tracing::error!(error = %e, "game8 scraper failed"),
-    }
-
-    match hoyolab_result {
-        Ok(scraped) => {
-            for p in scraped {
-                all_codes
-                    .entry(p.code.to_uppercase())
-                    .or_insert((p.rewards, "hoyolab"));
-            }
-        }
-        Err(e) => tracing::error!(error = %e, "hoyolab scraper failed"),
-    }
-
-    match crimson_witch_result {
-        Ok(scraped) => {
-            for p in scraped {
-                all_codes
-                    .entry(p.code)
-                    .or_insert((p.rewards, "crimson_witch"));
-            }
-        }
-        Err(e) => tracing::error!(error = %e, "crimson witch scraper failed"),
-    }
-
-    if all_codes.is_empty() {
-        return Ok(());
-    }
-
-    let collection = RedemptionCode::collection(&global.db, Game::Genshin);
-    let total = all_codes.len();
-
-    // Single $in query across all sources combined
-    let candidates: Vec<String> = all_codes.keys().cloned().collect();
-    let existing: HashSet<String> = collection
-        .find(doc! { "code": { "$in": &candidates } })
-        .await?
-        .try_collect::<Vec<RedemptionCode>>()
-        .await?
-        .into_iter()
-        .map(|c| c.code)
-        .collect();
-
-    let new_codes: Vec<(String, Vec<String>, &'static str)> = all_codes
-        .into_iter()
-        .filter(|(code, _)| !existing.contains(code))
-        .map(|(code, (rewards, source))| (code, rewards, source))
-        .collect();
-
-    if new_codes.is_empty() {
-        tracing::info!(total, "genshin scrape complete, no new codes");
-        return Ok(());
-    }
-
-    let validation_enabled = global
-        .config
-        .validator
-        .game_config(Game::Genshin)
-        .is_some_and(|c| c.enabled)
-        && Game::Genshin.redeem_endpoint().is_some();
-
-    let mut new_count = 0;
-    let mut new_valid_codes: Vec<(String, Vec<String>, String)> = Vec::new();
-
-    for (code, rewards, source) in &new_codes {
-        if validation_enabled {
-            let valid = loop {
-                match hoyoverse_api::validate_code(global, Game::Genshin, code).await {
-                    Ok(resp) if resp.is_cooldown() => {
-                        tracing::warn!(code, "hit cooldown, retrying in 6s");
-                        tokio::time::sleep(std::time::Duration::from_secs(6)).await;
-                        continue;
-                    }
-                    Ok(resp) => break resp.is_code_valid(),
-                    Err(e) => {
-                        tracing::warn!(code, error = %e, "validation request failed, inserting anyway");
-                        break true;
-                    }
-                }
-            };
-
-            if !valid {
-                tracing::warn!(code, "code is invalid, storing as inactive");
-                collection
-                    .insert_one(RedemptionCode {
-                        code: code.clone(),
-                        active: false,
-                        date: bson::DateTime::now(),
-                        rewards: rewards.clone(),
-                        source: source.to_string(),
-                    })
-                    .await?;
-                metrics::counter!("scraper_codes_invalid_total", "game" => Game::Genshin.slug())
-                    .increment(1);
-                tokio::time::sleep(std::time::Duration::from_secs(6)).await;
-                continue;
-            }
-
-            tokio::time::sleep(std::time::Duration::from_secs(6)).await;
-        }
-
-        let doc = RedemptionCode {
-            code: code.clone(),
-            active: true,
-            date: bson::DateTime::now(),
-            rewards: rewards.clone(),
-            source: source.to_string(),
-        };
-
-        collection.insert_one(doc).await?;
-        tracing::info!(code, source, "new code discovered");
-        metrics::counter!("scraper_codes_discovered_total", "game" => Game::Genshin.slug())
-            .increment(1);
-        new_valid_codes.push((code.clone(), rewards.clone(), source.to_string()));
-        new_count += 1;
-    }
-
-    tracing::info!(new = new_count, total, "genshin scrape complete");
-
-    if new_count > 0 {
-        discord::notify_new_codes(global, Game::Genshin, &new_valid_codes).await;
-        global
-            .response_cache
-            .remove(&format!("/mihoyo/{}/codes", Game::Genshin.slug()))
-            .await;
-    }
-
-    Ok(())
+pub fn sources() -> Vec<Box<dyn CodeSource>> {
+    vec![
+        Box::new(fandom::Fandom),
+        Box::new(game8::Game8),
+        Box::new(hoyolab::Hoyolab),
+        Box::new(CrimsonWitch {
+            game: Game::Genshin,
+            url: CRIMSON_WITCH_URL,
+        }),
+    ]
 }
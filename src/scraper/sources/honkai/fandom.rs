@@ -1,41 +1,50 @@
 use anyhow::Context as _;
 use std::sync::Arc;
 
+use crate::games::Game;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode, parse_wiki_date};
 
 const FANDOM_API: &str = "https://honkaiimpact3.fandom.com/api.php";
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Fandom;
+
+#[async_trait::async_trait]
+impl CodeSource for Fandom {
+    fn name(&self) -> &'static str {
+        "fandom"
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let resp = global
-        .http_client
-        .get(FANDOM_API)
-        .query(&[
-            ("action", "parse"),
-            ("page", "Exchange_Rewards"),
-            ("format", "json"),
-            ("prop", "wikitext"),
-        ])
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
-
-    let wikitext = resp["parse"]["wikitext"]["*"]
-        .as_str()
-        .context("failed to extract wikitext")?;
-
-    let codes = parse_wikitext(wikitext);
-
-    tracing::info!(count = codes.len(), "scraped codes from fandom");
-
-    Ok(codes)
+    fn game(&self) -> Game {
+        Game::Honkai
+    }
+
+    #[tracing::instrument(name = "fandom", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let resp = global
+            .http_client
+            .get(FANDOM_API)
+            .query(&[
+                ("action", "parse"),
+                ("page", "Exchange_Rewards"),
+                ("format", "json"),
+                ("prop", "wikitext"),
+            ])
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        let wikitext = resp["parse"]["wikitext"]["*"]
+            .as_str()
+            .context("failed to extract wikitext")?;
+
+        let codes = parse_wikitext(wikitext);
+
+        tracing::info!(count = codes.len(), "scraped codes from fandom");
+
+        Ok(codes)
+    }
 }
 
 /// Parse only the active codes section (before `==Legacy`).
@@ -78,7 +87,12 @@ pub fn parse_wikitext(wikitext: &str) -> Vec<ParsedCode> {
 
         let rewards = parse_item_templates(cols[cols.len() - 1]);
 
-        codes.push(ParsedCode { code, rewards });
+        codes.push(ParsedCode {
+            code,
+            rewards,
+            published_at: parse_wiki_date(cols[1], false),
+            ..Default::default()
+        });
     }
 
     codes
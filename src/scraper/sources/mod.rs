@@ -1,6 +1,135 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+use crate::games::Game;
+use crate::games::region::Region;
+use crate::global::Global;
+
 pub mod crimson_witch;
 pub mod genshin;
 pub mod honkai;
 pub mod starrail;
 pub mod themis;
 pub mod zenless;
+
+/// A single code entry as reported by one source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedCode {
+    pub code: String,
+    pub rewards: Vec<String>,
+    /// When the source says the code was released, if it says.
+    pub published_at: Option<DateTime<Utc>>,
+    /// When the source says the code stops working, if it says.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Regions the code is restricted to; empty if the source gives none.
+    pub regions: Vec<Region>,
+}
+
+/// A site that publishes redemption codes for one game.
+#[async_trait::async_trait]
+pub trait CodeSource: Send + Sync {
+    /// Stable identifier stored alongside codes reported by this source.
+    fn name(&self) -> &'static str;
+
+    fn game(&self) -> Game;
+
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>>;
+}
+
+/// All sources registered for a game, in merge priority order.
+pub fn registry(game: Game) -> Vec<Box<dyn CodeSource>> {
+    match game {
+        Game::Genshin => genshin::sources(),
+        Game::Starrail => starrail::sources(),
+        Game::Zenless => zenless::sources(),
+        Game::Honkai => honkai::sources(),
+        Game::Themis => themis::sources(),
+    }
+}
+
+// Two-digit years are tried first: `%Y` would happily read "24" as year 24.
+const DATE_FORMATS: &[&str] = &[
+    "%b %d, %y",
+    "%Y-%m-%d",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%d %B %Y",
+];
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// Parse the loosely formatted dates found on wiki pages.
+///
+/// Values without a time of day resolve to the start of that day (UTC), or
+/// the last second of it when `end_of_day` is set, so that an expiry date
+/// stays valid for the whole day. Anything unparseable (`Unknown`,
+/// `Indefinite`, ...) yields `None`.
+pub fn parse_wiki_date(value: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    let value = value.split('<').next().unwrap_or(value).trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    if let Some(date) = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    {
+        return Some(date.and_utc());
+    }
+
+    let date = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())?;
+    let time = if end_of_day {
+        NaiveTime::from_hms_opt(23, 59, 59)?
+    } else {
+        NaiveTime::MIN
+    };
+    Some(date.and_time(time).and_utc())
+}
+
+/// Whether a wiki template argument is a `name=value` pair rather than a
+/// positional value.
+pub fn is_named_arg(arg: &str) -> bool {
+    arg.split_once('=').is_some_and(|(name, _)| {
+        let name = name.trim();
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Look up a `name=value` template argument.
+pub fn named_arg<'a>(args: &[&'a str], name: &str) -> Option<&'a str> {
+    args.iter().find_map(|arg| {
+        arg.split_once('=')
+            .filter(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    #[test]
+    fn parses_wiki_date_formats() {
+        let start = Utc.with_ymd_and_hms(2024, 8, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 8, 1, 23, 59, 59).unwrap();
+
+        assert_eq!(parse_wiki_date("2024-08-01", false), Some(start));
+        assert_eq!(parse_wiki_date("August 1, 2024", false), Some(start));
+        assert_eq!(parse_wiki_date("Aug 1, 24", false), Some(start));
+        assert_eq!(parse_wiki_date("August 1, 2024", true), Some(end));
+        assert_eq!(
+            parse_wiki_date("2024-08-01 12:00", true),
+            Some(Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap())
+        );
+        assert_eq!(parse_wiki_date("Indefinite", true), None);
+        assert_eq!(parse_wiki_date("", false), None);
+    }
+}
@@ -1,40 +1,50 @@
+use crate::games::Game;
+use crate::games::region::Region;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode, is_named_arg, named_arg, parse_wiki_date};
 use anyhow::Context as _;
 use std::sync::Arc;
 
 const FANDOM_API: &str = "https://honkai-star-rail.fandom.com/api.php";
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Fandom;
+
+#[async_trait::async_trait]
+impl CodeSource for Fandom {
+    fn name(&self) -> &'static str {
+        "fandom"
+    }
+
+    fn game(&self) -> Game {
+        Game::Starrail
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let resp = global
-        .http_client
-        .get(FANDOM_API)
-        .query(&[
-            ("action", "parse"),
-            ("page", "Redemption_Code"),
-            ("format", "json"),
-            ("prop", "wikitext"),
-        ])
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
-
-    let wikitext = resp["parse"]["wikitext"]["*"]
-        .as_str()
-        .context("failed to extract wikitext")?;
-
-    let codes = parse_wikitext(wikitext);
-
-    tracing::info!(count = codes.len(), "scraped codes from fandom");
-
-    Ok(codes)
+    #[tracing::instrument(name = "fandom", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let resp = global
+            .http_client
+            .get(FANDOM_API)
+            .query(&[
+                ("action", "parse"),
+                ("page", "Redemption_Code"),
+                ("format", "json"),
+                ("prop", "wikitext"),
+            ])
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        let wikitext = resp["parse"]["wikitext"]["*"]
+            .as_str()
+            .context("failed to extract wikitext")?;
+
+        let codes = parse_wikitext(wikitext);
+
+        tracing::info!(count = codes.len(), "scraped codes from fandom");
+
+        Ok(codes)
+    }
 }
 
 /// Parse the wikitext to extract Redemption Code Row entries.
@@ -111,9 +121,8 @@ fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
     let code_field = fields[0];
     let server_field = fields[1];
 
-    if server_field == "CN" {
-        return None;
-    }
+    // CN-only rows are kept; the pipeline stores them with the CN codes.
+    let regions = Region::parse_list(server_field);
 
     if code_field.is_empty() {
         return None;
@@ -121,6 +130,21 @@ fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
 
     let rewards = extract_rewards(&fields);
 
+    // Dates are positional too, but may be left blank, so index into the
+    // unfiltered arguments rather than `fields`.
+    let args: Vec<&str> = parts.iter().skip(1).map(|s| s.trim()).collect();
+    let positional: Vec<&str> = args.iter().copied().filter(|a| !is_named_arg(a)).collect();
+    let published_at = positional
+        .get(3)
+        .copied()
+        .or_else(|| named_arg(&args, "discovered"))
+        .and_then(|d| parse_wiki_date(d, false));
+    let expires_at = positional
+        .get(4)
+        .copied()
+        .or_else(|| named_arg(&args, "expiry"))
+        .and_then(|d| parse_wiki_date(d, true));
+
     let parsed: Vec<ParsedCode> = code_field
         .split(';')
         .map(|s| s.trim())
@@ -128,6 +152,9 @@ fn parse_code_row(inner: &str) -> Option<Vec<ParsedCode>> {
         .map(|code_str| ParsedCode {
             code: code_str.to_string(),
             rewards: rewards.clone(),
+            published_at,
+            expires_at,
+            regions: regions.clone(),
         })
         .collect();
 
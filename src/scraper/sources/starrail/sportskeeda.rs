@@ -1,4 +1,6 @@
+use crate::games::Game;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode};
 use regex::Regex;
 use std::sync::{Arc, LazyLock};
 
@@ -11,27 +13,34 @@ static LI_RE: LazyLock<Regex> = LazyLock::new(|| {
         .expect("invalid regex")
 });
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct Sportskeeda;
+
+#[async_trait::async_trait]
+impl CodeSource for Sportskeeda {
+    fn name(&self) -> &'static str {
+        "sportskeeda"
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let html = global
-        .http_client
-        .get(SPORTSKEEDA_URL)
-        .send()
-        .await?
-        .text()
-        .await?;
+    fn game(&self) -> Game {
+        Game::Starrail
+    }
+
+    #[tracing::instrument(name = "sportskeeda", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let html = global
+            .http_client
+            .get(SPORTSKEEDA_URL)
+            .send()
+            .await?
+            .text()
+            .await?;
 
-    let codes = parse_html(&html);
+        let codes = parse_html(&html);
 
-    tracing::info!(count = codes.len(), "scraped codes from sportskeeda");
+        tracing::info!(count = codes.len(), "scraped codes from sportskeeda");
 
-    Ok(codes)
+        Ok(codes)
+    }
 }
 
 pub fn parse_html(html: &str) -> Vec<ParsedCode> {
@@ -77,7 +86,11 @@ pub fn parse_html(html: &str) -> Vec<ParsedCode> {
         let rewards: Vec<String> = rewards.into_iter().filter(|r| !r.is_empty()).collect();
 
         if !code.is_empty() {
-            results.push(ParsedCode { code, rewards });
+            results.push(ParsedCode {
+                code,
+                rewards,
+                ..Default::default()
+            });
         }
     }
 
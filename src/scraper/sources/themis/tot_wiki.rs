@@ -1,4 +1,6 @@
+use crate::games::Game;
 use crate::global::Global;
+use crate::scraper::sources::{CodeSource, ParsedCode};
 use regex::Regex;
 use std::sync::{Arc, LazyLock};
 
@@ -11,27 +13,34 @@ static ROW_RE: LazyLock<Regex> = LazyLock::new(|| {
 static TAG_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"<[^>]*>").expect("invalid tag regex"));
 
-#[derive(Debug)]
-pub struct ParsedCode {
-    pub code: String,
-    pub rewards: Vec<String>,
-}
+pub struct TotWiki;
+
+#[async_trait::async_trait]
+impl CodeSource for TotWiki {
+    fn name(&self) -> &'static str {
+        "tot_wiki"
+    }
 
-#[tracing::instrument(skip(global))]
-pub async fn scrape(global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
-    let html = global
-        .http_client
-        .get(TOT_WIKI_URL)
-        .send()
-        .await?
-        .text()
-        .await?;
+    fn game(&self) -> Game {
+        Game::Themis
+    }
+
+    #[tracing::instrument(name = "tot_wiki", skip_all)]
+    async fn scrape(&self, global: &Arc<Global>) -> anyhow::Result<Vec<ParsedCode>> {
+        let html = global
+            .http_client
+            .get(TOT_WIKI_URL)
+            .send()
+            .await?
+            .text()
+            .await?;
 
-    let codes = parse_html(&html);
+        let codes = parse_html(&html);
 
-    tracing::info!(count = codes.len(), "scraped codes from tot_wiki");
+        tracing::info!(count = codes.len(), "scraped codes from tot_wiki");
 
-    Ok(codes)
+        Ok(codes)
+    }
 }
 
 pub fn parse_html(html: &str) -> Vec<ParsedCode> {
@@ -67,6 +76,7 @@ pub fn parse_html(html: &str) -> Vec<ParsedCode> {
             results.push(ParsedCode {
                 code,
                 rewards: rewards.clone(),
+                ..Default::default()
             });
         }
     }
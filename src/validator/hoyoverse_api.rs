@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
 use anyhow::Context as _;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::config::ValidatorAccount;
+use crate::database::code_event::{self, CodeEvent, CodeEventKind};
 use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
 use crate::global::Global;
+use crate::hoyolab::HoyolabRequest;
+use crate::notifier;
 
 #[derive(Debug, Deserialize)]
 pub struct RedeemResponse {
@@ -12,97 +18,295 @@ pub struct RedeemResponse {
     pub message: String,
 }
 
+/// What a redemption attempt says about a code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedeemOutcome {
+    /// Redeemed successfully.
+    Valid,
+    /// The account already redeemed it; the code still works.
+    AlreadyRedeemed,
+    Expired,
+    /// Does not exist, is malformed or is not activated yet.
+    Invalid,
+    /// Every redemption has been used up.
+    UsageLimit,
+    /// Rate limited; try again later.
+    Cooldown,
+    /// The account's cookie, uid or region is wrong.
+    Credentials,
+    /// The account's level is too low; the code still works.
+    LevelTooLow,
+    /// A retcode not seen before. Nothing is concluded about the code.
+    Unknown(i32),
+}
+
+impl RedeemOutcome {
+    pub fn from_retcode(retcode: i32) -> Self {
+        match retcode {
+            0 => Self::Valid,
+            // -2018 = already redeemed (alt)
+            -2017 | -2018 => Self::AlreadyRedeemed,
+            -2001 => Self::Expired,
+            // -1065 = invalid code
+            // -2003 = incorrectly formatted
+            // -2004 = invalid code
+            // -2014 = code not activated
+            -1065 | -2003 | -2004 | -2014 => Self::Invalid,
+            -2006 => Self::UsageLimit,
+            -2016 => Self::Cooldown,
+            // -1071 = invalid/expired cookies
+            // -1073 = no game account bound to this HoYoLab account
+            // -1075 = no character on this server
+            -1071 | -1073 | -1075 => Self::Credentials,
+            // -2011 = game level too low (alt)
+            -2021 | -2011 => Self::LevelTooLow,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// Metric label for this outcome.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::AlreadyRedeemed => "already_redeemed",
+            Self::Expired => "expired",
+            Self::Invalid => "invalid",
+            Self::UsageLimit => "usage_limit",
+            Self::Cooldown => "cooldown",
+            Self::Credentials => "credentials",
+            Self::LevelTooLow => "level_too_low",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
 impl RedeemResponse {
+    pub fn outcome(&self) -> RedeemOutcome {
+        RedeemOutcome::from_retcode(self.retcode)
+    }
+
     /// Code was successfully redeemed or already redeemed (still active).
     pub fn is_code_valid(&self) -> bool {
-        // 0     = success
-        // -2017 = already redeemed
-        // -2018 = already redeemed (alt)
-        // -2021 = game level too low (code is still valid)
-        // -2011 = game level too low (alt)
-        matches!(self.retcode, 0 | -2017 | -2018 | -2021 | -2011)
+        matches!(
+            self.outcome(),
+            RedeemOutcome::Valid | RedeemOutcome::AlreadyRedeemed | RedeemOutcome::LevelTooLow
+        )
     }
 
     /// Code has expired.
     pub fn is_expired(&self) -> bool {
-        // -2001 = code has expired
-        self.retcode == -2001
+        self.outcome() == RedeemOutcome::Expired
     }
 
-    /// Code is invalid / does not exist.
+    /// Code is invalid, does not exist or has no redemptions left.
     pub fn is_invalid(&self) -> bool {
-        // -1065 = invalid code
-        // -2003 = incorrectly formatted
-        // -2004 = invalid code
-        // -2006 = max usage limit reached
-        // -2014 = code not activated
-        matches!(self.retcode, -1065 | -2003 | -2004 | -2006 | -2014)
+        matches!(
+            self.outcome(),
+            RedeemOutcome::Invalid | RedeemOutcome::UsageLimit
+        )
     }
 
     /// Redemption is on cooldown (rate limited).
     pub fn is_cooldown(&self) -> bool {
-        self.retcode == -2016
+        self.outcome() == RedeemOutcome::Cooldown
     }
 
     /// Credentials are invalid (expired cookie, wrong uid/region, no game account).
     pub fn is_credentials_error(&self) -> bool {
-        // -1071 = invalid/expired cookies
-        // -1073 = no game account bound to this HoYoLab account
-        // -1075 = no character on this server
-        matches!(self.retcode, -1071 | -1073 | -1075)
+        self.outcome() == RedeemOutcome::Credentials
+    }
+
+    /// The retcode is not one we know how to interpret.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self.outcome(), RedeemOutcome::Unknown(_))
     }
 }
 
-/// Validate a redemption code against the HoYoverse API.
+/// Validate a redemption code against the HoYoverse API, using the next
+/// account from the pool on `server` that can redeem codes locked to
+/// `regions`.
+///
+/// Accounts that fail with a credentials error are quarantined and the code
+/// is retried on the next one. Returns `None` if no usable account is left.
 #[tracing::instrument(skip(global))]
 pub async fn validate_code(
     global: &Arc<Global>,
     game: Game,
+    server: Server,
+    code: &str,
+    regions: &[Region],
+) -> anyhow::Result<Option<RedeemResponse>> {
+    loop {
+        let Some(account) = global.validator_pool.acquire(game, server, regions).await else {
+            tracing::warn!(code, ?regions, "no usable validator account");
+            return Ok(None);
+        };
+
+        let resp = match redeem(global, game, server, code, &account).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let error = format!("{e:#}");
+                notifier::notify_validation_error(global, game, REQUEST_ALERT_KEY, code, &error)
+                    .await;
+                return Err(e);
+            }
+        };
+        notifier::resolve_validation_error(global, game, REQUEST_ALERT_KEY).await;
+
+        if let RedeemOutcome::Unknown(retcode) = resp.outcome()
+            && code_event::note_unknown_retcode(&global.db, game, retcode, &resp.message).await?
+        {
+            tracing::warn!(retcode, message = %resp.message, "first sighting of unknown retcode");
+            let error = format!("unknown retcode {retcode}: {}", resp.message);
+            let alert_key = format!("retcode:{retcode}");
+            notifier::notify_validation_error(global, game, &alert_key, code, &error).await;
+        }
+
+        if resp.is_credentials_error() {
+            tracing::error!(
+                uid = account.uid,
+                retcode = resp.retcode,
+                message = %resp.message,
+                "credentials error, quarantining account"
+            );
+            global.validator_pool.quarantine(game, &account.uid);
+            let error = format!(
+                "account {} quarantined after credentials error (retcode {}): {}",
+                account.uid, resp.retcode, resp.message
+            );
+            notifier::notify_validation_error(
+                global,
+                game,
+                &credentials_alert_key(&account.uid),
+                code,
+                &error,
+            )
+            .await;
+            continue;
+        }
+
+        notifier::resolve_validation_error(global, game, &credentials_alert_key(&account.uid))
+            .await;
+        return Ok(Some(resp));
+    }
+}
+
+/// Alert key for failed requests to the redemption API.
+const REQUEST_ALERT_KEY: &str = "request";
+
+fn credentials_alert_key(uid: &str) -> String {
+    format!("credentials:{uid}")
+}
+
+/// Redeem a code on a specific account.
+#[tracing::instrument(skip(global, account), fields(uid = account.uid, server = server.slug()))]
+async fn redeem(
+    global: &Arc<Global>,
+    game: Game,
+    server: Server,
     code: &str,
+    account: &ValidatorAccount,
 ) -> anyhow::Result<RedeemResponse> {
-    let endpoint = game
-        .redeem_endpoint()
-        .with_context(|| format!("validation not yet supported for {}", game.display_name()))?;
+    let endpoint = game.redeem_endpoint(server).with_context(|| {
+        format!(
+            "validation not yet supported for {} on {} servers",
+            game.display_name(),
+            server.slug()
+        )
+    })?;
 
     let game_biz = game
-        .game_biz()
+        .game_biz(server)
         .with_context(|| format!("game_biz not configured for {}", game.display_name()))?;
 
-    let game_config = global
-        .config
-        .validator
-        .game_config(game)
-        .with_context(|| format!("{} has no redemption API", game.display_name()))?;
     let timestamp = chrono::Utc::now().timestamp_millis().to_string();
+    let (lang, lang_key) = match server {
+        Server::Global => ("en", "en-us"),
+        Server::Cn => ("zh-cn", "zh-cn"),
+    };
 
     let mut params = vec![
         ("cdkey", code),
-        ("uid", game_config.uid.as_str()),
-        ("region", game_config.region.as_str()),
-        ("lang", "en"),
+        ("uid", account.uid.as_str()),
+        ("region", account.region.as_str()),
+        ("lang", lang),
         ("game_biz", game_biz),
         ("t", timestamp.as_str()),
     ];
     if game == Game::Genshin {
-        params.push(("sLangKey", "en-us"));
+        params.push(("sLangKey", lang_key));
     }
 
-    let mut req = global
-        .http_client
-        .get(endpoint)
+    let mut req = HoyolabRequest::get(&global.http_client, "redeem", endpoint)
         .query(&params)
-        .header("Cookie", &game_config.cookie);
+        .cookie(&account.cookie);
     if game == Game::Themis {
         req = req.header("Referer", crate::games::themis::REFERER);
     }
-    let resp = req.send().await?.json::<RedeemResponse>().await?;
+    // Retcodes are the answer here, so the envelope is kept as is.
+    let envelope = req.send_envelope::<serde::de::IgnoredAny>().await?;
+    let resp = RedeemResponse {
+        retcode: envelope.retcode,
+        message: envelope.message,
+    };
 
+    let outcome = resp.outcome();
     tracing::info!(
         code,
         retcode = resp.retcode,
         message = %resp.message,
+        outcome = outcome.label(),
         "validated code"
     );
+    metrics::counter!(
+        "validator_outcomes_total",
+        "game" => game.slug(),
+        "outcome" => outcome.label()
+    )
+    .increment(1);
+
+    let mut event = CodeEvent::new(game, server, code, CodeEventKind::Validated);
+    event.outcome = Some(outcome);
+    event.retcode = Some(resp.retcode);
+    event.message = Some(resp.message.clone());
+    event.account = Some(account.uid.clone());
+    event.record(&global.db).await;
 
     Ok(resp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_retcodes() {
+        let cases = [
+            (0, RedeemOutcome::Valid),
+            (-2017, RedeemOutcome::AlreadyRedeemed),
+            (-2001, RedeemOutcome::Expired),
+            (-2003, RedeemOutcome::Invalid),
+            (-2006, RedeemOutcome::UsageLimit),
+            (-2016, RedeemOutcome::Cooldown),
+            (-1071, RedeemOutcome::Credentials),
+            (-2011, RedeemOutcome::LevelTooLow),
+            (-9999, RedeemOutcome::Unknown(-9999)),
+        ];
+
+        for (retcode, expected) in cases {
+            assert_eq!(RedeemOutcome::from_retcode(retcode), expected, "{retcode}");
+        }
+    }
+
+    #[test]
+    fn unknown_retcodes_are_not_valid() {
+        let resp = RedeemResponse {
+            retcode: -9999,
+            message: "something new".to_string(),
+        };
+        assert!(!resp.is_code_valid());
+        assert!(!resp.is_invalid());
+        assert!(resp.is_unknown());
+    }
+}
@@ -1,35 +1,76 @@
 use std::sync::Arc;
 
 use crate::database::redemption_code::RedemptionCode;
+use crate::database::validation_job::{JobKind, ValidationJob};
 use crate::games::Game;
+use crate::games::server::Server;
 use crate::global::Global;
-use crate::notifier::discord;
 use crate::util::sleep_until_aligned;
 
 pub mod hoyoverse_api;
+pub mod pool;
+pub mod queue;
 
+/// Runs the validation queue workers and, if enabled, periodically queues
+/// every active code for revalidation.
 #[tracing::instrument(name = "Validator", skip_all)]
 pub async fn run(global: Arc<Global>) -> anyhow::Result<()> {
-    if !global.config.validator.enabled {
-        tracing::info!("validator is disabled");
-        std::future::pending::<()>().await;
-        return Ok(());
+    let requeued = ValidationJob::requeue_running(&global.db).await?;
+    if requeued > 0 {
+        tracing::info!(
+            requeued,
+            "returned interrupted validation jobs to the queue"
+        );
     }
 
-    let interval_secs = global.config.validator.interval_secs;
-    tracing::info!(interval_secs, "starting validator");
-
-    loop {
-        sleep_until_aligned(interval_secs).await;
-
-        if let Err(e) = validate_all_codes(&global).await {
-            tracing::error!("validation cycle failed: {:#}", e);
+    let all_games = [
+        Game::Genshin,
+        Game::Starrail,
+        Game::Zenless,
+        Game::Honkai,
+        Game::Themis,
+    ];
+    for game in all_games {
+        let enabled = global
+            .config
+            .validator
+            .game_config(game)
+            .is_some_and(|c| c.enabled);
+        if enabled && global.validator_pool.account_count(game) == 0 {
+            tracing::warn!(
+                game = game.display_name(),
+                "validation enabled but no accounts configured, new codes are published unvalidated"
+            );
         }
     }
+
+    let workers = games_with_validation(&global)
+        .flat_map(|game| (0..global.validator_pool.account_count(game)).map(move |_| game))
+        .map(|game| queue::run_worker(global.clone(), game))
+        .collect::<Vec<_>>();
+
+    tokio::join!(
+        futures::future::join_all(workers),
+        revalidate_periodically(global.clone())
+    );
+
+    Ok(())
 }
 
-#[tracing::instrument(skip_all)]
-async fn validate_all_codes(global: &Arc<Global>) -> anyhow::Result<()> {
+/// Whether codes for `game` on `server` should be checked against the
+/// redemption API. Codes are only checked once an account on that server is
+/// configured; otherwise they are published without validation.
+pub fn validation_enabled(global: &Global, game: Game, server: Server) -> bool {
+    global
+        .config
+        .validator
+        .game_config(game)
+        .is_some_and(|c| c.enabled)
+        && game.redeem_endpoint(server).is_some()
+        && global.validator_pool.serves(game, server)
+}
+
+fn games_with_validation(global: &Global) -> impl Iterator<Item = Game> + '_ {
     let all_games = [
         Game::Genshin,
         Game::Starrail,
@@ -38,84 +79,62 @@ async fn validate_all_codes(global: &Arc<Global>) -> anyhow::Result<()> {
         Game::Themis,
     ];
 
-    for game in all_games {
-        let Some(game_config) = global.config.validator.game_config(game) else {
-            continue; // game has no redemption API (e.g. Honkai)
-        };
+    all_games.into_iter().filter(|&game| {
+        game.servers()
+            .iter()
+            .any(|&server| validation_enabled(global, game, server))
+    })
+}
 
-        if !game_config.enabled {
-            continue;
-        }
+async fn revalidate_periodically(global: Arc<Global>) {
+    if !global.config.validator.enabled {
+        tracing::info!("periodic revalidation is disabled");
+        std::future::pending::<()>().await;
+        return;
+    }
 
-        if game.redeem_endpoint().is_none() {
-            tracing::warn!(
-                game = game.display_name(),
-                "redeem endpoint not configured, skipping"
-            );
-            continue;
-        }
+    let interval_secs = global.config.validator.interval_secs;
+    tracing::info!(interval_secs, "starting periodic revalidation");
 
-        let codes = RedemptionCode::find_active(&global.db, game).await?;
+    loop {
+        sleep_until_aligned(interval_secs).await;
 
-        tracing::info!(
-            game = game.display_name(),
-            count = codes.len(),
-            "validating active codes"
-        );
+        if let Err(e) = enqueue_active_codes(&global).await {
+            tracing::error!("revalidation cycle failed: {:#}", e);
+        }
+    }
+}
 
-        for code in &codes {
-            match hoyoverse_api::validate_code(global, game, &code.code).await {
-                Ok(resp) => {
-                    if resp.is_credentials_error() {
-                        tracing::error!(
-                            game = game.display_name(),
-                            retcode = resp.retcode,
-                            message = %resp.message,
-                            "invalid credentials, skipping remaining codes"
-                        );
-                        discord::notify_validation_error(
-                            global,
-                            game,
-                            &code.code,
-                            &format!(
-                                "credentials error (retcode {}): {}",
-                                resp.retcode, resp.message
-                            ),
-                        )
-                        .await;
-                        break;
-                    } else if resp.is_expired() || resp.is_invalid() {
-                        tracing::warn!(
-                            code = code.code,
-                            retcode = resp.retcode,
-                            message = %resp.message,
-                            "marking code as inactive"
-                        );
-                        RedemptionCode::set_active(&global.db, game, &code.code, false).await?;
-                        metrics::counter!("validator_codes_deactivated_total", "game" => game.slug()).increment(1);
-                    } else if resp.is_cooldown() {
-                        tracing::warn!(
-                            game = game.display_name(),
-                            "hit redemption cooldown, skipping remaining codes"
-                        );
-                        break;
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(code = code.code, error = %e, "failed to validate code");
-                    discord::notify_validation_error(global, game, &code.code, &e.to_string())
-                        .await;
-                }
+#[tracing::instrument(skip_all)]
+async fn enqueue_active_codes(global: &Arc<Global>) -> anyhow::Result<()> {
+    for game in games_with_validation(global) {
+        let mut codes = Vec::new();
+        for &server in game.servers() {
+            if validation_enabled(global, game, server) {
+                let active = RedemptionCode::find_active(&global.db, game, server).await?;
+                codes.extend(active.into_iter().map(|code| (server, code)));
             }
+        }
 
-            // Rate limit: HoYoverse enforces ~5s between redemptions
-            tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+        let mut queued = 0;
+        for (server, code) in codes.into_iter().filter(|(_, c)| !c.pinned) {
+            let job = ValidationJob::new(
+                game,
+                server,
+                &code.code,
+                JobKind::Revalidation,
+                code.regions,
+            );
+            if job.enqueue(&global.db).await?.1 {
+                queued += 1;
+            }
         }
 
-        global
-            .response_cache
-            .remove(&format!("/mihoyo/{}/codes", game.slug()))
-            .await;
+        tracing::info!(
+            game = game.display_name(),
+            queued,
+            "queued active codes for revalidation"
+        );
     }
 
     Ok(())
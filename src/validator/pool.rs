@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::config::{ValidatorAccount, ValidatorConfig};
+use crate::games::Game;
+use crate::games::region::Region;
+use crate::games::server::Server;
+
+/// HoYoverse enforces ~5s between redemptions per account.
+pub const REDEEM_COOLDOWN: Duration = Duration::from_secs(6);
+
+/// The validator accounts configured for every game.
+///
+/// Accounts are handed out round-robin so that the per-account redemption
+/// cooldown is spread across them, region-locked codes go to an account on a
+/// matching server where there is one, CN codes only go to CN accounts and
+/// the rest only to overseas ones, and accounts that return a credentials error are taken
+/// out of rotation for `quarantine_secs`.
+pub struct AccountPool {
+    games: HashMap<Game, GamePool>,
+    quarantine: Duration,
+}
+
+struct GamePool {
+    slots: Vec<Slot>,
+    next: AtomicUsize,
+}
+
+struct Slot {
+    account: ValidatorAccount,
+    region: Option<Region>,
+    server: Server,
+    state: Mutex<SlotState>,
+}
+
+#[derive(Default)]
+struct SlotState {
+    /// When the account may next be used for a redemption.
+    ready_at: Option<Instant>,
+    quarantined_until: Option<Instant>,
+}
+
+impl AccountPool {
+    pub fn new(config: &ValidatorConfig) -> Self {
+        let all_games = [
+            Game::Genshin,
+            Game::Starrail,
+            Game::Zenless,
+            Game::Honkai,
+            Game::Themis,
+        ];
+
+        let games = all_games
+            .into_iter()
+            .filter_map(|game| {
+                let game_config = config.game_config(game)?;
+                let slots = game_config
+                    .all_accounts()
+                    .into_iter()
+                    .map(|account| {
+                        let region = Region::from_server(&account.region);
+                        Slot {
+                            region,
+                            server: region.map_or(Server::Global, Server::of_region),
+                            account,
+                            state: Mutex::new(SlotState::default()),
+                        }
+                    })
+                    .collect();
+                Some((
+                    game,
+                    GamePool {
+                        slots,
+                        next: AtomicUsize::new(0),
+                    },
+                ))
+            })
+            .collect();
+
+        Self {
+            games,
+            quarantine: Duration::from_secs(config.quarantine_secs),
+        }
+    }
+
+    /// Take the next usable account on `server` for a code locked to
+    /// `regions` (empty for unrestricted codes), waiting out its cooldown.
+    /// Returns `None` if every matching account is quarantined or none is on
+    /// the server.
+    pub async fn acquire(
+        &self,
+        game: Game,
+        server: Server,
+        regions: &[Region],
+    ) -> Option<ValidatorAccount> {
+        let (account, wait) = self.reserve(game, server, regions, Instant::now())?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Some(account)
+    }
+
+    /// Pick the next eligible account in rotation and reserve its next
+    /// redemption slot, returning how long to wait before using it. A code
+    /// locked to regions that no account on `server` is in goes to any
+    /// account on the server instead.
+    fn reserve(
+        &self,
+        game: Game,
+        server: Server,
+        regions: &[Region],
+        now: Instant,
+    ) -> Option<(ValidatorAccount, Duration)> {
+        let pool = self.games.get(&game)?;
+        if pool.slots.is_empty() {
+            return None;
+        }
+
+        let in_regions =
+            |slot: &Slot| regions.is_empty() || slot.region.is_some_and(|r| regions.contains(&r));
+        let any_in_regions = pool
+            .slots
+            .iter()
+            .any(|slot| slot.server == server && in_regions(slot));
+
+        let start = pool.next.fetch_add(1, Ordering::Relaxed);
+        (0..pool.slots.len())
+            .map(|i| &pool.slots[(start + i) % pool.slots.len()])
+            .filter(|slot| slot.server == server)
+            .filter(|slot| !any_in_regions || in_regions(slot))
+            .find_map(|slot| {
+                let mut state = slot.state.lock().expect("account pool lock poisoned");
+                if state.quarantined_until.is_some_and(|until| until > now) {
+                    return None;
+                }
+
+                let ready_at = state.ready_at.map_or(now, |ready| ready.max(now));
+                state.ready_at = Some(ready_at + REDEEM_COOLDOWN);
+                Some((slot.account.clone(), ready_at - now))
+            })
+    }
+
+    /// Take an account out of rotation after a credentials error.
+    pub fn quarantine(&self, game: Game, uid: &str) {
+        let Some(pool) = self.games.get(&game) else {
+            return;
+        };
+
+        for slot in pool.slots.iter().filter(|s| s.account.uid == uid) {
+            let mut state = slot.state.lock().expect("account pool lock poisoned");
+            state.quarantined_until = Some(Instant::now() + self.quarantine);
+        }
+
+        metrics::counter!("validator_accounts_quarantined_total", "game" => game.slug())
+            .increment(1);
+    }
+
+    /// Number of accounts configured for `game`, quarantined or not.
+    pub fn account_count(&self, game: Game) -> usize {
+        self.games.get(&game).map_or(0, |pool| pool.slots.len())
+    }
+
+    /// Whether any account for `game` is on `server`, quarantined or not.
+    pub fn serves(&self, game: Game, server: Server) -> bool {
+        self.games
+            .get(&game)
+            .is_some_and(|pool| pool.slots.iter().any(|slot| slot.server == server))
+    }
+
+    /// Whether any account for `game` on `server` is currently out of
+    /// quarantine.
+    pub fn has_available(&self, game: Game, server: Server) -> bool {
+        let now = Instant::now();
+        self.games.get(&game).is_some_and(|pool| {
+            pool.slots
+                .iter()
+                .filter(|slot| slot.server == server)
+                .any(|slot| {
+                    let state = slot.state.lock().expect("account pool lock poisoned");
+                    state.quarantined_until.is_none_or(|until| until <= now)
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameValidatorConfig;
+
+    fn account(uid: &str, region: &str) -> ValidatorAccount {
+        ValidatorAccount {
+            cookie: "cookie".to_string(),
+            uid: uid.to_string(),
+            region: region.to_string(),
+        }
+    }
+
+    fn pool(accounts: Vec<ValidatorAccount>) -> AccountPool {
+        AccountPool::new(&ValidatorConfig {
+            genshin: GameValidatorConfig {
+                enabled: true,
+                cookie: String::new(),
+                accounts,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn rotates_accounts_and_spaces_reuse() {
+        let pool = pool(vec![account("1", "os_usa"), account("2", "os_euro")]);
+        let now = Instant::now();
+
+        let (first, wait) = pool
+            .reserve(Game::Genshin, Server::Global, &[], now)
+            .unwrap();
+        assert_eq!((first.uid.as_str(), wait), ("1", Duration::ZERO));
+        let (second, wait) = pool
+            .reserve(Game::Genshin, Server::Global, &[], now)
+            .unwrap();
+        assert_eq!((second.uid.as_str(), wait), ("2", Duration::ZERO));
+        let (third, wait) = pool
+            .reserve(Game::Genshin, Server::Global, &[], now)
+            .unwrap();
+        assert_eq!((third.uid.as_str(), wait), ("1", REDEEM_COOLDOWN));
+    }
+
+    #[test]
+    fn routes_region_locked_codes() {
+        let pool = pool(vec![account("1", "os_usa"), account("2", "os_asia")]);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            let (account, _) = pool
+                .reserve(Game::Genshin, Server::Global, &[Region::Asia], now)
+                .unwrap();
+            assert_eq!(account.uid, "2");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_any_account_on_the_server() {
+        let pool = pool(vec![account("1", "os_usa"), account("2", "cn_gf01")]);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            let (account, _) = pool
+                .reserve(Game::Genshin, Server::Global, &[Region::Europe], now)
+                .unwrap();
+            assert_eq!(account.uid, "1");
+        }
+        assert!(
+            pool.reserve(Game::Starrail, Server::Global, &[Region::Europe], now)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn keeps_cn_accounts_to_cn_codes() {
+        let pool = pool(vec![account("1", "cn_gf01"), account("2", "os_usa")]);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            let (account, _) = pool
+                .reserve(Game::Genshin, Server::Global, &[], now)
+                .unwrap();
+            assert_eq!(account.uid, "2");
+            let (account, _) = pool
+                .reserve(Game::Genshin, Server::Cn, &[Region::Cn], now)
+                .unwrap();
+            assert_eq!(account.uid, "1");
+        }
+        assert!(pool.serves(Game::Genshin, Server::Cn));
+        assert!(!pool.serves(Game::Starrail, Server::Cn));
+    }
+
+    #[test]
+    fn skips_quarantined_accounts() {
+        let pool = pool(vec![
+            account("1", "os_usa"),
+            account("2", "os_usa"),
+            account("3", "cn_gf01"),
+        ]);
+        pool.quarantine(Game::Genshin, "1");
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            let (account, _) = pool
+                .reserve(Game::Genshin, Server::Global, &[], now)
+                .unwrap();
+            assert_eq!(account.uid, "2");
+        }
+
+        pool.quarantine(Game::Genshin, "2");
+        assert!(
+            pool.reserve(Game::Genshin, Server::Global, &[], now)
+                .is_none()
+        );
+        assert!(!pool.has_available(Game::Genshin, Server::Global));
+        assert!(pool.has_available(Game::Genshin, Server::Cn));
+    }
+}
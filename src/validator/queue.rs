@@ -0,0 +1,292 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database::code_event::{self, CodeEvent, CodeEventKind};
+use crate::database::redemption_code::RedemptionCode;
+use crate::database::validation_job::{self, JobKind, JobStatus, ValidationJob};
+use crate::global::Global;
+use crate::notifier::{self, NewCode, webhook};
+use crate::scraper::sources::ParsedCode;
+use crate::validator::hoyoverse_api::{self, RedeemResponse};
+use crate::validator::pool::REDEEM_COOLDOWN;
+
+/// Source name stored on codes accepted through the submission endpoint.
+pub const SUBMISSION_SOURCE: &str = "user";
+
+/// Work through the validation queue for one game. One worker runs per
+/// configured account, so the account pool's cooldown keeps each account to
+/// a single redemption every few seconds.
+#[tracing::instrument(skip(global))]
+pub async fn run_worker(global: Arc<Global>, game: crate::games::Game) {
+    let poll_interval = Duration::from_secs(global.config.validator.poll_interval_secs);
+
+    loop {
+        match ValidationJob::claim_next(&global.db, game).await {
+            Ok(Some(job)) => {
+                if let Err(e) = process(&global, &job).await {
+                    tracing::error!(code = job.code, "validation job failed: {:#}", e);
+                }
+            }
+            Ok(None) => tokio::time::sleep(poll_interval).await,
+            Err(e) => {
+                tracing::error!("failed to claim validation job: {:#}", e);
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+    }
+}
+
+#[tracing::instrument(skip(global, job), fields(code = job.code, kind = ?job.kind, attempts = job.attempts))]
+async fn process(global: &Arc<Global>, job: &ValidationJob) -> anyhow::Result<()> {
+    let resp =
+        match hoyoverse_api::validate_code(global, job.game, job.server, &job.code, &job.regions)
+            .await
+        {
+            Ok(Some(resp)) if resp.is_cooldown() => {
+                job.postpone(&global.db, "redemption cooldown", REDEEM_COOLDOWN)
+                    .await?;
+                return Ok(());
+            }
+            Ok(Some(resp)) => match deferral(job, &resp) {
+                Some(reason) => return retry(global, job, &reason).await,
+                None => resp,
+            },
+            Ok(None) if !global.validator_pool.has_available(job.game, job.server) => {
+                return retry(global, job, "all validator accounts are quarantined").await;
+            }
+            Ok(None) => {
+                return retry(global, job, "no validator account on the code's server").await;
+            }
+            Err(e) => return retry(global, job, &format!("{e:#}")).await,
+        };
+
+    // Act first so that a submission poller never sees the job done before
+    // the code is stored.
+    let stored = match apply(global, job, &resp).await {
+        Ok(stored) => stored,
+        Err(e) => return retry(global, job, &format!("{e:#}")).await,
+    };
+    job.complete(
+        &global.db,
+        resp.outcome(),
+        resp.retcode,
+        &resp.message,
+        stored,
+    )
+    .await
+}
+
+async fn retry(global: &Arc<Global>, job: &ValidationJob, error: &str) -> anyhow::Result<()> {
+    let config = &global.config.validator;
+    let delay =
+        validation_job::backoff(Duration::from_secs(config.retry_backoff_secs), job.attempts);
+    let status = job
+        .retry_or_fail(&global.db, error, delay, config.max_attempts)
+        .await?;
+
+    if status == JobStatus::Failed {
+        tracing::warn!(error, "giving up on validation job");
+        metrics::counter!("validator_jobs_failed_total", "game" => job.game.slug()).increment(1);
+
+        // The code stays pending and unpublished; an admin can publish it
+        // by hand once it has been checked some other way.
+        if job.kind == JobKind::Discovery {
+            let alert_key = format!("discovery:{}", job.code);
+            let error = format!(
+                "gave up validating new code after {} attempts, it stays unpublished: {error}",
+                job.attempts
+            );
+            notifier::notify_validation_error(global, job.game, &alert_key, &job.code, &error)
+                .await;
+        }
+    } else {
+        tracing::debug!(
+            error,
+            retry_in_secs = delay.as_secs(),
+            "validation job rescheduled"
+        );
+    }
+
+    Ok(())
+}
+
+/// Why a redemption result is retried instead of acted on. An unknown
+/// retcode says nothing either way about a new code, so it stays pending
+/// rather than being published or rejected; the retcode has already been
+/// alerted.
+fn deferral(job: &ValidationJob, resp: &RedeemResponse) -> Option<String> {
+    (job.kind == JobKind::Discovery && resp.is_unknown())
+        .then(|| format!("unknown retcode {}: {}", resp.retcode, resp.message))
+}
+
+/// Act on a redemption result. Returns whether a submitted code was stored.
+async fn apply(
+    global: &Arc<Global>,
+    job: &ValidationJob,
+    resp: &RedeemResponse,
+) -> anyhow::Result<bool> {
+    let game = job.game;
+    let server = job.server;
+
+    match job.kind {
+        JobKind::Discovery if resp.is_code_valid() => {
+            if let Some(record) =
+                RedemptionCode::resolve_pending(&global.db, game, server, &job.code, true).await?
+            {
+                let mut event = CodeEvent::new(game, server, &job.code, CodeEventKind::Discovered);
+                event.sources = record.sources.iter().map(|s| s.name.clone()).collect();
+                event.record(&global.db).await;
+                global
+                    .code_feed
+                    .added(game, server, &job.code, record.rewards.clone());
+                announce(global, job, record).await;
+                global.invalidate_codes_cache(game).await;
+            }
+        }
+        // The code was never published, so there is nothing to retract.
+        JobKind::Discovery => {
+            tracing::warn!(
+                code = job.code,
+                retcode = resp.retcode,
+                "code is invalid, keeping it inactive"
+            );
+            if RedemptionCode::resolve_pending(&global.db, game, server, &job.code, false)
+                .await?
+                .is_some()
+            {
+                let mut event = CodeEvent::new(game, server, &job.code, CodeEventKind::Deactivated);
+                event.message = Some(code_event::REJECTED_ON_DISCOVERY.to_string());
+                event.record(&global.db).await;
+            }
+            metrics::counter!("scraper_codes_invalid_total", "game" => game.slug()).increment(1);
+        }
+        JobKind::Submission if resp.is_code_valid() => {
+            return store_submission(global, job).await;
+        }
+        // Rejected submissions are not stored; the outcome on the job is
+        // what the submitter sees when polling.
+        JobKind::Submission => {}
+        JobKind::Revalidation if resp.is_expired() || resp.is_invalid() => {
+            tracing::warn!(
+                code = job.code,
+                retcode = resp.retcode,
+                message = %resp.message,
+                "marking code as inactive"
+            );
+            let reason = format!("retcode {}: {}", resp.retcode, resp.message);
+            if RedemptionCode::set_active(&global.db, game, server, &job.code, false, &reason)
+                .await?
+            {
+                global
+                    .code_feed
+                    .deactivated(game, server, &job.code, &reason);
+                webhook::notify_code_expired(global, game, server, &job.code, &reason).await;
+            }
+            metrics::counter!("validator_codes_deactivated_total", "game" => game.slug())
+                .increment(1);
+            global.invalidate_codes_cache(game).await;
+        }
+        JobKind::Revalidation => {}
+    }
+
+    Ok(false)
+}
+
+/// Store a submitted code that the redemption API accepted. Returns `false`
+/// if it was already stored.
+async fn store_submission(global: &Arc<Global>, job: &ValidationJob) -> anyhow::Result<bool> {
+    let game = job.game;
+    let server = job.server;
+
+    // A scraper may have found it while the job was queued.
+    if RedemptionCode::exists(&global.db, game, server, &job.code).await? {
+        return Ok(false);
+    }
+
+    let report = ParsedCode {
+        code: job.code.clone(),
+        regions: job.regions.clone(),
+        ..Default::default()
+    };
+    let record = RedemptionCode::from_reports(
+        game,
+        &job.code,
+        &[(SUBMISSION_SOURCE, report)],
+        bson::DateTime::now(),
+    );
+    RedemptionCode::collection(&global.db, game, server)
+        .insert_one(&record)
+        .await?;
+
+    let mut event = CodeEvent::new(game, server, &job.code, CodeEventKind::Discovered);
+    event.sources = vec![SUBMISSION_SOURCE.to_string()];
+    event.record(&global.db).await;
+    global
+        .code_feed
+        .added(game, server, &job.code, record.rewards.clone());
+
+    tracing::info!(code = job.code, "submitted code accepted");
+    metrics::counter!("submissions_accepted_total", "game" => game.slug()).increment(1);
+
+    announce(global, job, record).await;
+    global.invalidate_codes_cache(game).await;
+
+    Ok(true)
+}
+
+/// Announce a newly published code on the notification backends.
+async fn announce(global: &Arc<Global>, job: &ValidationJob, record: RedemptionCode) {
+    let source_names = record
+        .sources
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let code = NewCode {
+        code: record.code,
+        server: job.server,
+        rewards: record.rewards,
+        source: source_names,
+    };
+    notifier::notify_new_codes(global, job.game, &[code]).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::Game;
+    use crate::games::server::Server;
+
+    fn response(retcode: i32) -> RedeemResponse {
+        RedeemResponse {
+            retcode,
+            message: "message".to_string(),
+        }
+    }
+
+    #[test]
+    fn keeps_new_codes_pending_on_unknown_retcodes() {
+        let discovery = ValidationJob::new(
+            Game::Genshin,
+            Server::Global,
+            "GENSHINGIFT",
+            JobKind::Discovery,
+            Vec::new(),
+        );
+        assert_eq!(
+            deferral(&discovery, &response(-123456)).as_deref(),
+            Some("unknown retcode -123456: message")
+        );
+        assert_eq!(deferral(&discovery, &response(0)), None);
+        assert_eq!(deferral(&discovery, &response(-2001)), None);
+
+        let revalidation = ValidationJob::new(
+            Game::Genshin,
+            Server::Global,
+            "GENSHINGIFT",
+            JobKind::Revalidation,
+            Vec::new(),
+        );
+        assert_eq!(deferral(&revalidation, &response(-123456)), None);
+    }
+}